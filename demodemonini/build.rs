@@ -0,0 +1,98 @@
+//! Validate all shaders at build time
+//! so that errors show up in `cargo build` instead of crashing the demo at startup.
+
+use std::{fs, path::Path, process::ExitCode};
+
+#[path = "src/preprocess.rs"]
+mod preprocess;
+
+/// Every combination of preprocessor flags the shaders can be compiled with at runtime
+/// (see `flags` in src/shaders.rs).
+const FLAG_SETS: &[&[&str]] = &[&[], &["PUSH_CONSTANTS"]];
+
+fn main() -> ExitCode {
+    let shader_dir = Path::new("src/shaders");
+    println!("cargo:rerun-if-changed=src/shaders");
+    println!("cargo:rerun-if-changed=src/preprocess.rs");
+
+    let mut failed = false;
+    for file_name in shader_entry_points(shader_dir) {
+        for flags in FLAG_SETS {
+            if let Err(message) = validate(shader_dir, &file_name, flags) {
+                eprintln!("{message}\n");
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Every .wgsl file at the root of the shader directory is a complete shader.
+/// Files in subdirectories are only meant to be included in other shaders.
+fn shader_entry_points(shader_dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(shader_dir)
+        .expect("Failed to read shader directory")
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "wgsl"))
+        .filter_map(|path| Some(path.file_name()?.to_str()?.to_string()))
+        .collect();
+    names.sort();
+    names
+}
+
+fn validate(shader_dir: &Path, file_name: &str, flags: &[&str]) -> Result<(), String> {
+    let read = |path: &str| fs::read_to_string(shader_dir.join(path)).map_err(|e| e.to_string());
+    let source = read(file_name)?;
+    let preprocessed =
+        preprocess::preprocess_with_origins(file_name, &source, flags, &mut |path| read(path))
+            .map_err(|err| format!("error: src/shaders/{err}"))?;
+
+    // point the first line of the message to the original file
+    // since the full report below is relative to the preprocessed source
+    let headline = |message: &str, location: Option<naga::SourceLocation>| {
+        let origin = location.and_then(|loc| preprocessed.origin(loc.line_number as usize));
+        match origin {
+            Some((file, line)) => format!("error: src/shaders/{file}:{line}: {message}"),
+            None => format!("error: src/shaders/{file_name}: {message}"),
+        }
+    };
+
+    let report_path = if flags.is_empty() {
+        format!("{file_name} (preprocessed)")
+    } else {
+        format!("{file_name} (preprocessed with {})", flags.join(", "))
+    };
+    let module = naga::front::wgsl::parse_str(&preprocessed.source).map_err(|err| {
+        format!(
+            "{}\n{}",
+            headline(err.message(), err.location(&preprocessed.source)),
+            err.emit_to_string_with_path(&preprocessed.source, &report_path)
+        )
+    })?;
+
+    // capabilities actually available depend on the adapter,
+    // which gets checked at runtime; here we only care about the shader being well-formed
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::all(),
+    )
+    .validate(&module)
+    .map_err(|err| {
+        format!(
+            "{}\n{}",
+            headline(
+                &err.as_inner().to_string(),
+                err.location(&preprocessed.source)
+            ),
+            err.emit_to_string_with_path(&preprocessed.source, &report_path)
+        )
+    })?;
+
+    Ok(())
+}
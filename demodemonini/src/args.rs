@@ -0,0 +1,313 @@
+//! Command line options for the native build.
+
+use std::path::PathBuf;
+
+use super::{
+    audio::{Signal, Source},
+    capture, theme,
+    video::Placement,
+};
+
+const USAGE: &str = "\
+Usage: demodemonini [thumbnail] [OPTIONS]
+
+Commands:
+  thumbnail            Render a small representative frame into thumbnails/demodemonini.png
+                       for the gallery, same as --frame-at with fixed defaults
+
+Options:
+  --record <FILE>      Record a video through ffmpeg from the start,
+                       e.g. out.mp4, out.webm or out.gif
+  --fps <N>            Frame rate of recordings [default: 60]
+  --duration <SECONDS> Stop recording and quit after this much time
+  --size <WxH>         Window size in physical pixels, e.g. 480x360 for a small gif
+  --seed <N>           Seed for everything random, to make renders reproducible
+  --headless           Render without a window at a fixed time step and save the frames,
+                       into --record if given or a png sequence otherwise
+  --frames <N>         Number of frames to render in headless mode,
+                       instead of calculating it from --duration
+  --frame-at <SECONDS> Render a single frame at this time without a window and quit
+  --out <FILE>         Where to save the frame from --frame-at,
+                       named after the current time by default
+  --watermark <TEXT>   Signature to stamp in the corner of recordings
+  --timecode           Stamp a timecode in the corner of recordings
+  --bench <FRAMES>     Run this many frames without vsync, print frame
+                       and fire simulation timings, and quit
+  --still-scale <N>    How many times the window size to render stills
+                       saved with Ctrl+Shift+S, 1 to 8 [default: 4]
+  --music <FILE>       Play a soundtrack and keep the picture in time with it
+  --start <SECONDS>    Start the music and everything timed by it this far in
+  --timeline <FILE>    Show and hide layers and ramp effects over time
+                       as choreographed in this file
+  --audio <SIGNAL>     Listen to audio and make the fire glow
+                       with bass, mid, treble, loudness or onset
+  --audio-source <SRC> What --audio listens to: default for the default input,
+                       loopback for whatever the machine is playing,
+                       or part of an input device's name [default: default]
+  --video <FILE>       Play a video in a loop as a layer of the picture
+  --video-placement <P>
+                       back to show the video behind everything but the background,
+                       middle to show it between the fire and the characters
+                       [default: back]
+  --camera <INDEX>     Burn what this camera sees into the fire, 0 for the default
+  --ndi <NAME>         Send the rendered frames over the network as an NDI source
+                       with this name, needs the NDI runtime installed
+  --twitch <CHANNEL>   Let viewers toggle layers with !fire, !characters
+                       and !postprocess in this channel's chat
+  --chat-commands <FILE>
+                       Use the chat commands set up in this file for --twitch
+                       instead, a command and an action on each line
+  --record-input <FILE>
+                       Save keys, mouse movement and other input with their times
+  --play-input <FILE>  Replay input saved with --record-input, exactly the same way
+                       when given the same --seed with --headless or --record
+  --time-of-day        Change the colors through the day following the local clock,
+                       from night to dawn, day and dusk
+  --hour <H>           Use the colors for this hour instead of the clock, e.g. 18.5
+  --feed <FILE>        Poll a URL for JSON data and drive the wind, fire height,
+                       glow or colors with it as set up in this file
+  --wallpaper          Draw behind the desktop icons at a lower frame rate,
+                       on Windows and X11
+  --remote <ADDR>      Accept remote control commands and parameter values
+                       over WebSocket on this address, e.g. 0.0.0.0:9001
+  --help               Print this message";
+
+// a moment where the fire has had time to grow,
+// with a fixed seed so the thumbnail only changes when the picture does
+const THUMBNAIL_T: f64 = 4.;
+const THUMBNAIL_SEED: u64 = 2024;
+const THUMBNAIL_SIZE: (u32, u32) = (320, 240);
+const THUMBNAIL_PATH: &str = "thumbnails/demodemonini.png";
+
+#[derive(Clone, Debug)]
+pub struct Args {
+    pub record: Option<PathBuf>,
+    pub fps: u32,
+    pub duration: Option<f64>,
+    pub size: Option<(u32, u32)>,
+    pub seed: Option<u64>,
+    pub headless: bool,
+    pub frames: Option<u32>,
+    pub frame_at: Option<f64>,
+    pub out: Option<PathBuf>,
+    pub watermark: Option<String>,
+    pub timecode: bool,
+    pub bench: Option<u32>,
+    pub still_scale: u32,
+    pub music: Option<PathBuf>,
+    pub start: f64,
+    pub timeline: Option<PathBuf>,
+    pub audio: Option<Signal>,
+    pub audio_source: Source,
+    pub video: Option<PathBuf>,
+    pub video_placement: Placement,
+    pub camera: Option<u32>,
+    pub ndi: Option<String>,
+    pub twitch: Option<String>,
+    pub chat_commands: Option<PathBuf>,
+    pub record_input: Option<PathBuf>,
+    pub play_input: Option<PathBuf>,
+    pub time_of_day: bool,
+    pub hour: Option<f32>,
+    pub feed: Option<PathBuf>,
+    pub wallpaper: bool,
+    pub remote: Option<String>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            record: None,
+            fps: 60,
+            duration: None,
+            size: None,
+            seed: None,
+            headless: false,
+            frames: None,
+            frame_at: None,
+            out: None,
+            watermark: None,
+            timecode: false,
+            bench: None,
+            still_scale: 4,
+            music: None,
+            start: 0.,
+            timeline: None,
+            audio: None,
+            audio_source: Source::Default,
+            video: None,
+            video_placement: Placement::Back,
+            camera: None,
+            ndi: None,
+            twitch: None,
+            chat_commands: None,
+            record_input: None,
+            play_input: None,
+            time_of_day: false,
+            hour: None,
+            feed: None,
+            wallpaper: false,
+            remote: None,
+        }
+    }
+}
+
+impl Args {
+    /// Whether anything should be stamped on recorded frames.
+    pub fn has_watermark(&self) -> bool {
+        self.watermark.is_some() || self.timecode
+    }
+
+    /// The text to stamp on the given frame of a recording.
+    pub fn watermark_text(&self, frame: u32) -> String {
+        capture::watermark_text(self.watermark.as_deref(), self.timecode, frame, self.fps)
+    }
+
+    /// The hour to pick the colors for, if they should follow the time of day.
+    pub fn theme_hour(&self) -> Option<f32> {
+        self.hour
+            .or_else(|| self.time_of_day.then(theme::local_hour))
+    }
+
+    /// Parse the arguments the program was started with.
+    /// Prints usage and exits if asked to with `--help`.
+    pub fn parse() -> anyhow::Result<Self> {
+        let mut parsed = Self::default();
+        let mut args = std::env::args().skip(1).peekable();
+        let thumbnail = args.next_if(|arg| arg == "thumbnail").is_some();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or(anyhow::anyhow!("{name} needs a value\n\n{USAGE}"))
+            };
+            match arg.as_str() {
+                "--record" => {
+                    parsed.record = Some(value("--record")?.into());
+                }
+                "--fps" => {
+                    parsed.fps = value("--fps")?.parse()?;
+                    if parsed.fps == 0 {
+                        anyhow::bail!("--fps must be positive");
+                    }
+                }
+                "--duration" => {
+                    parsed.duration = Some(value("--duration")?.parse()?);
+                }
+                "--size" => {
+                    let size = value("--size")?;
+                    let (w, h) = size
+                        .split_once('x')
+                        .ok_or(anyhow::anyhow!("--size should look like 480x360"))?;
+                    parsed.size = Some((w.parse()?, h.parse()?));
+                }
+                "--seed" => {
+                    parsed.seed = Some(value("--seed")?.parse()?);
+                }
+                "--headless" => {
+                    parsed.headless = true;
+                }
+                "--frames" => {
+                    parsed.frames = Some(value("--frames")?.parse()?);
+                }
+                "--frame-at" => {
+                    parsed.frame_at = Some(value("--frame-at")?.parse()?);
+                }
+                "--out" => {
+                    parsed.out = Some(value("--out")?.into());
+                }
+                "--watermark" => {
+                    parsed.watermark = Some(value("--watermark")?);
+                }
+                "--timecode" => {
+                    parsed.timecode = true;
+                }
+                "--bench" => {
+                    parsed.bench = Some(value("--bench")?.parse()?);
+                }
+                "--still-scale" => {
+                    parsed.still_scale = value("--still-scale")?.parse()?;
+                    if !(1..=8).contains(&parsed.still_scale) {
+                        anyhow::bail!("--still-scale must be between 1 and 8");
+                    }
+                }
+                "--music" => {
+                    parsed.music = Some(value("--music")?.into());
+                }
+                "--start" => {
+                    parsed.start = value("--start")?.parse()?;
+                    if parsed.start < 0. {
+                        anyhow::bail!("--start can't be negative");
+                    }
+                }
+                "--timeline" => {
+                    parsed.timeline = Some(value("--timeline")?.into());
+                }
+                "--audio" => {
+                    let name = value("--audio")?;
+                    parsed.audio = Some(Signal::from_name(&name).ok_or(anyhow::anyhow!(
+                        "Unknown signal {name:?}, expected {}",
+                        Signal::NAMES
+                    ))?);
+                }
+                "--audio-source" => {
+                    parsed.audio_source = Source::from_name(&value("--audio-source")?);
+                }
+                "--video" => {
+                    parsed.video = Some(value("--video")?.into());
+                }
+                "--video-placement" => {
+                    let name = value("--video-placement")?;
+                    parsed.video_placement = Placement::from_name(&name).ok_or(anyhow::anyhow!(
+                        "Unknown video placement {name:?}, expected back or middle"
+                    ))?;
+                }
+                "--camera" => {
+                    parsed.camera = Some(value("--camera")?.parse()?);
+                }
+                "--ndi" => {
+                    parsed.ndi = Some(value("--ndi")?);
+                }
+                "--twitch" => {
+                    parsed.twitch = Some(value("--twitch")?);
+                }
+                "--chat-commands" => {
+                    parsed.chat_commands = Some(value("--chat-commands")?.into());
+                }
+                "--record-input" => {
+                    parsed.record_input = Some(value("--record-input")?.into());
+                }
+                "--play-input" => {
+                    parsed.play_input = Some(value("--play-input")?.into());
+                }
+                "--time-of-day" => {
+                    parsed.time_of_day = true;
+                }
+                "--hour" => {
+                    parsed.hour = Some(value("--hour")?.parse()?);
+                }
+                "--feed" => {
+                    parsed.feed = Some(value("--feed")?.into());
+                }
+                "--wallpaper" => {
+                    parsed.wallpaper = true;
+                }
+                "--remote" => {
+                    parsed.remote = Some(value("--remote")?);
+                }
+                "--help" | "-h" => {
+                    println!("{USAGE}");
+                    std::process::exit(0);
+                }
+                other => anyhow::bail!("Unknown argument {other}\n\n{USAGE}"),
+            }
+        }
+        // options given explicitly still override the thumbnail defaults
+        if thumbnail {
+            parsed.frame_at.get_or_insert(THUMBNAIL_T);
+            parsed.seed.get_or_insert(THUMBNAIL_SEED);
+            parsed.size.get_or_insert(THUMBNAIL_SIZE);
+            parsed.out.get_or_insert(THUMBNAIL_PATH.into());
+        }
+        Ok(parsed)
+    }
+}
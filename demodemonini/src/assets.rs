@@ -0,0 +1,133 @@
+//! Loading the big binary assets, i.e. the painted images.
+//!
+//! Native builds always have them embedded in the binary,
+//! and reload them from the source tree when they're edited.
+//! The web build fetches them from next to the page so the wasm download stays small,
+//! falling back to embedded copies if it was built with the `embed-assets` feature.
+
+use std::borrow::Cow;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{path::PathBuf, sync::mpsc};
+
+#[cfg(not(target_arch = "wasm32"))]
+use notify::Watcher;
+
+// image files that make up the picture, including compressed versions and layers
+#[cfg(not(target_arch = "wasm32"))]
+const WATCHED_EXTENSIONS: [&str; 2] = ["png", "ktx2"];
+
+/// Get the contents of an asset file from the demodemonini directory.
+pub async fn load(name: &str) -> anyhow::Result<Cow<'static, [u8]>> {
+    #[cfg(target_arch = "wasm32")]
+    match fetch(name).await {
+        Ok(bytes) => return Ok(Cow::Owned(bytes)),
+        Err(err) if embedded(name).is_some() => {
+            log::warn!("Failed to fetch {name}, using the embedded copy: {err}");
+        }
+        Err(err) => return Err(err.context(format!("Failed to fetch {name}"))),
+    }
+    embedded(name)
+        .map(Cow::Borrowed)
+        .ok_or_else(|| anyhow::anyhow!("{name} isn't embedded in this build"))
+}
+
+#[cfg(any(not(target_arch = "wasm32"), feature = "embed-assets"))]
+fn embedded(name: &str) -> Option<&'static [u8]> {
+    match name {
+        "characters.png" => Some(include_bytes!("../characters.png")),
+        _ => None,
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", not(feature = "embed-assets")))]
+fn embedded(_name: &str) -> Option<&'static [u8]> {
+    None
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn fetch(url: &str) -> anyhow::Result<Vec<u8>> {
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::JsFuture;
+
+    let js_err = |err: wasm_bindgen::JsValue| anyhow::anyhow!("{err:?}");
+    let window = web_sys::window().ok_or(anyhow::anyhow!("No window to fetch from"))?;
+    let response: web_sys::Response = JsFuture::from(window.fetch_with_str(url))
+        .await
+        .map_err(js_err)?
+        .dyn_into()
+        .map_err(js_err)?;
+    if !response.ok() {
+        anyhow::bail!("HTTP status {}", response.status());
+    }
+    let buffer = JsFuture::from(response.array_buffer().map_err(js_err)?)
+        .await
+        .map_err(js_err)?;
+    Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+}
+
+/// Where an asset file is in the source tree.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn source_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(name)
+}
+
+/// Watches the asset files in the source tree for changes.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct AssetWatcher {
+    // kept alive for as long as we want to receive events
+    _watcher: notify::RecommendedWatcher,
+    events: mpsc::Receiver<notify::Result<notify::Event>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl AssetWatcher {
+    /// Start watching the asset files.
+    ///
+    /// Returns `None` if the source tree isn't available,
+    /// e.g. when the binary has been moved to another machine.
+    pub fn new() -> Option<Self> {
+        let dir = source_path("");
+        if !dir.is_dir() {
+            return None;
+        }
+
+        let (sender, events) = mpsc::channel();
+        let mut watcher = match notify::recommended_watcher(sender) {
+            Ok(w) => w,
+            Err(err) => {
+                eprintln!("Failed to start asset watcher: {err}");
+                return None;
+            }
+        };
+        if let Err(err) = watcher.watch(&dir, notify::RecursiveMode::Recursive) {
+            eprintln!("Failed to watch {}: {err}", dir.display());
+            return None;
+        }
+
+        Some(Self {
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// Whether any image has changed since the last call.
+    ///
+    /// Painting programs may write a file in several steps,
+    /// so a change can also be seen while the file is still incomplete.
+    /// Loading it then fails, and the next step of the write counts as another change.
+    pub fn images_changed(&self) -> bool {
+        // go through every event so they don't count again on the next call
+        let mut changed = false;
+        let changed_paths = self
+            .events
+            .try_iter()
+            .filter_map(|ev| ev.ok())
+            .filter(|ev| !ev.kind.is_access())
+            .flat_map(|ev| ev.paths);
+        for path in changed_paths {
+            let ext = path.extension().and_then(|ext| ext.to_str());
+            changed |= ext.is_some_and(|ext| WATCHED_EXTENSIONS.contains(&ext));
+        }
+        changed
+    }
+}
@@ -0,0 +1,14 @@
+/// A rectangle in texture coordinates, (0, 0) being the top left corner of the texture.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UvRect {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+}
+
+impl UvRect {
+    /// The whole texture.
+    pub const FULL: UvRect = UvRect {
+        min: [0., 0.],
+        max: [1., 1.],
+    };
+}
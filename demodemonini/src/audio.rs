@@ -0,0 +1,280 @@
+//! Listening to a microphone, line-in or whatever the machine is playing
+//! and boiling the sound down to a few signals for things in the picture to react to.
+//!
+//! All signals are roughly between 0 and 1. Band energies are normalized against
+//! their recent peaks so that quiet and loud inputs both use the whole range.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use rustfft::num_complex::Complex;
+
+const FFT_SIZE: usize = 1024;
+// frequency ranges of the bands in Hz
+const BASS: (f32, f32) = (20., 250.);
+const MID: (f32, f32) = (250., 2000.);
+const TREBLE: (f32, f32) = (2000., 8000.);
+// time constant of the smoothing applied to every signal, in seconds
+const SMOOTHING_TIME: f64 = 0.08;
+// how fast the peaks used for normalizing fall back after a loud part, per second
+const PEAK_DECAY: f32 = 0.5;
+// keeps silence from being amplified into noise
+const MIN_PEAK: f32 = 1e-3;
+// an onset is a jump in spectral flux this many times over its recent average
+const ONSET_THRESHOLD: f32 = 1.8;
+const FLUX_HISTORY_LEN: usize = 30;
+const ONSET_DECAY_TIME: f64 = 0.15;
+
+/// Something measured from the sound that the picture can follow.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Signal {
+    Bass,
+    Mid,
+    Treble,
+    /// Overall loudness regardless of frequency.
+    Loudness,
+    /// Jumps to 1 at the start of a beat or note and fades out.
+    Onset,
+}
+
+impl Signal {
+    pub const NAMES: &'static str = "bass, mid, treble, loudness or onset";
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "bass" => Signal::Bass,
+            "mid" => Signal::Mid,
+            "treble" => Signal::Treble,
+            "loudness" => Signal::Loudness,
+            "onset" => Signal::Onset,
+            _ => return None,
+        })
+    }
+}
+
+/// Where to listen for sound.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// The system's default input device.
+    Default,
+    /// What the machine is currently playing.
+    Loopback,
+    /// The first input device whose name contains this.
+    Named(String),
+}
+
+impl Source {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "default" => Source::Default,
+            "loopback" => Source::Loopback,
+            other => Source::Named(other.to_string()),
+        }
+    }
+}
+
+pub struct AudioInput {
+    // capture stops when the stream is dropped
+    _stream: cpal::Stream,
+    // the latest mono samples, written from the capture thread
+    samples: Arc<Mutex<VecDeque<f32>>>,
+    sample_rate: f32,
+    fft: Arc<dyn rustfft::Fft<f32>>,
+    window: Vec<f32>,
+    buffer: Vec<Complex<f32>>,
+    prev_spectrum: Vec<f32>,
+    flux_history: VecDeque<f32>,
+    band_peaks: [f32; 3],
+    bands: [f32; 3],
+    loudness: f32,
+    onset: f32,
+}
+
+impl AudioInput {
+    /// Start capturing from the given source.
+    pub fn start(source: &Source) -> anyhow::Result<Self> {
+        let (device, config) = find_device(source)?;
+        let sample_rate = config.sample_rate().0 as f32;
+        let samples = Arc::new(Mutex::new(VecDeque::with_capacity(2 * FFT_SIZE)));
+        let stream = match config.sample_format() {
+            cpal::SampleFormat::F32 => build_stream::<f32>(&device, &config.config(), &samples),
+            cpal::SampleFormat::I16 => build_stream::<i16>(&device, &config.config(), &samples),
+            cpal::SampleFormat::U16 => build_stream::<u16>(&device, &config.config(), &samples),
+            other => anyhow::bail!("Unsupported audio sample format {other}"),
+        }?;
+        stream.play()?;
+        println!(
+            "Listening to {}",
+            device.name().unwrap_or_else(|_| "audio input".into())
+        );
+
+        // Hann window to keep the edges of the buffer from smearing the spectrum
+        let window = (0..FFT_SIZE)
+            .map(|i| {
+                let x = i as f32 / (FFT_SIZE - 1) as f32;
+                0.5 - 0.5 * (std::f32::consts::TAU * x).cos()
+            })
+            .collect();
+        Ok(Self {
+            _stream: stream,
+            samples,
+            sample_rate,
+            fft: rustfft::FftPlanner::new().plan_fft_forward(FFT_SIZE),
+            window,
+            buffer: vec![Complex::default(); FFT_SIZE],
+            prev_spectrum: vec![0.; FFT_SIZE / 2],
+            flux_history: VecDeque::with_capacity(FLUX_HISTORY_LEN),
+            band_peaks: [MIN_PEAK; 3],
+            bands: [0.; 3],
+            loudness: 0.,
+            onset: 0.,
+        })
+    }
+
+    /// The current value of a signal.
+    pub fn get(&self, signal: Signal) -> f32 {
+        match signal {
+            Signal::Bass => self.bands[0],
+            Signal::Mid => self.bands[1],
+            Signal::Treble => self.bands[2],
+            Signal::Loudness => self.loudness,
+            Signal::Onset => self.onset,
+        }
+    }
+
+    /// Analyze the latest sound, `dt` seconds after the last update.
+    pub fn update(&mut self, dt: f64) {
+        {
+            let samples = self.samples.lock().unwrap();
+            // not enough sound captured yet right after starting
+            if samples.len() < FFT_SIZE {
+                return;
+            }
+            for ((out, sample), weight) in self.buffer.iter_mut().zip(&*samples).zip(&self.window) {
+                *out = Complex::new(sample * weight, 0.);
+            }
+        }
+        let rms = (self.buffer.iter().map(|c| c.re * c.re).sum::<f32>() / FFT_SIZE as f32).sqrt();
+        self.fft.process(&mut self.buffer);
+
+        let spectrum: Vec<f32> = self.buffer[..FFT_SIZE / 2]
+            .iter()
+            .map(|c| c.norm() / FFT_SIZE as f32)
+            .collect();
+        let bin_hz = self.sample_rate / FFT_SIZE as f32;
+        let smoothing = 1. - (-dt / SMOOTHING_TIME).exp() as f32;
+        let peak_decay = PEAK_DECAY.powf(dt as f32);
+        for ((band, peak), (low, high)) in self
+            .bands
+            .iter_mut()
+            .zip(&mut self.band_peaks)
+            .zip([BASS, MID, TREBLE])
+        {
+            let bins =
+                &spectrum[(low / bin_hz) as usize..((high / bin_hz) as usize).min(spectrum.len())];
+            let energy =
+                (bins.iter().map(|m| m * m).sum::<f32>() / bins.len().max(1) as f32).sqrt();
+            *peak = (*peak * peak_decay).max(energy).max(MIN_PEAK);
+            *band += (energy / *peak - *band) * smoothing;
+        }
+        // a full scale sine wave has an RMS of about 0.43 after windowing
+        self.loudness += ((2. * rms).min(1.) - self.loudness) * smoothing;
+
+        // onsets from spectral flux, i.e. how much louder frequencies got since last time
+        let flux: f32 = spectrum
+            .iter()
+            .zip(&self.prev_spectrum)
+            .map(|(new, old)| (new - old).max(0.))
+            .sum();
+        let avg_flux =
+            self.flux_history.iter().sum::<f32>() / self.flux_history.len().max(1) as f32;
+        if self.flux_history.len() == FLUX_HISTORY_LEN
+            && flux > ONSET_THRESHOLD * avg_flux
+            && flux > MIN_PEAK
+        {
+            self.onset = 1.;
+        } else {
+            self.onset *= (-dt / ONSET_DECAY_TIME).exp() as f32;
+        }
+        if self.flux_history.len() == FLUX_HISTORY_LEN {
+            self.flux_history.pop_front();
+        }
+        self.flux_history.push_back(flux);
+        self.prev_spectrum = spectrum;
+    }
+}
+
+fn find_device(source: &Source) -> anyhow::Result<(cpal::Device, cpal::SupportedStreamConfig)> {
+    let host = cpal::default_host();
+    let device = match source {
+        Source::Default => host
+            .default_input_device()
+            .ok_or(anyhow::anyhow!("No audio input device"))?,
+        // WASAPI records what an output device plays when it's opened for input
+        #[cfg(target_os = "windows")]
+        Source::Loopback => {
+            let device = host
+                .default_output_device()
+                .ok_or(anyhow::anyhow!("No audio output device to listen to"))?;
+            let config = device.default_output_config()?;
+            return Ok((device, config));
+        }
+        // PulseAudio and PipeWire show what their outputs play as "monitor" inputs
+        #[cfg(not(target_os = "windows"))]
+        Source::Loopback => find_input(&host, "monitor").map_err(|_| {
+            anyhow::anyhow!(
+                "No monitor input found to listen to the system's output. \
+                 Try making the output's monitor the default input, e.g. with pavucontrol"
+            )
+        })?,
+        Source::Named(name) => find_input(&host, name)?,
+    };
+    let config = device.default_input_config()?;
+    Ok((device, config))
+}
+
+fn find_input(host: &cpal::Host, name: &str) -> anyhow::Result<cpal::Device> {
+    let name = name.to_lowercase();
+    host.input_devices()?
+        .find(|device| {
+            device
+                .name()
+                .is_ok_and(|device_name| device_name.to_lowercase().contains(&name))
+        })
+        .ok_or(anyhow::anyhow!("No audio input device matching {name:?}"))
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    samples: &Arc<Mutex<VecDeque<f32>>>,
+) -> anyhow::Result<cpal::Stream>
+where
+    T: cpal::SizedSample,
+    f32: cpal::FromSample<T>,
+{
+    let channels = config.channels as usize;
+    let samples = samples.clone();
+    let stream = device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            let mut samples = samples.lock().unwrap();
+            for frame in data.chunks(channels) {
+                let mono = frame
+                    .iter()
+                    .map(|&s| cpal::Sample::to_sample::<f32>(s))
+                    .sum::<f32>()
+                    / channels as f32;
+                samples.push_back(mono);
+            }
+            let excess = samples.len().saturating_sub(FFT_SIZE);
+            samples.drain(..excess);
+        },
+        |err| eprintln!("Audio input error: {err}"),
+        None,
+    )?;
+    Ok(stream)
+}
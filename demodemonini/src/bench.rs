@@ -0,0 +1,72 @@
+//! Timing a fixed number of frames with `--bench`,
+//! to compare performance across commits and machines.
+
+use std::time::Duration;
+
+/// Frame times collected over a benchmark run.
+pub struct Bench {
+    frames: u32,
+    frame_times: Vec<Duration>,
+    // the first frame includes startup and compiling pipelines, so it isn't counted
+    skipped_first: bool,
+    size: (u32, u32),
+    adapter: wgpu::AdapterInfo,
+}
+
+impl Bench {
+    pub fn new(frames: u32, size: (u32, u32), adapter: wgpu::AdapterInfo) -> Self {
+        Self {
+            frames,
+            frame_times: Vec::with_capacity(frames as usize),
+            skipped_first: false,
+            size,
+            adapter,
+        }
+    }
+
+    /// Record how long the previous frame took.
+    /// Returns true once all the frames have been recorded.
+    pub fn record_frame(&mut self, time: Duration) -> bool {
+        if !self.skipped_first {
+            self.skipped_first = true;
+        } else {
+            self.frame_times.push(time);
+        }
+        self.frame_times.len() >= self.frames as usize
+    }
+
+    /// A summary of the run to print.
+    pub fn report(&self, fire_step_times: &[Duration]) -> String {
+        format!(
+            "Benchmarked {} frames of {}x{} on {} ({:?})\n\
+             frame time: {}\n\
+             fire step:  {} over {} steps",
+            self.frame_times.len(),
+            self.size.0,
+            self.size.1,
+            self.adapter.name,
+            self.adapter.backend,
+            stats(&self.frame_times),
+            stats(fire_step_times),
+            fire_step_times.len(),
+        )
+    }
+}
+
+fn stats(times: &[Duration]) -> String {
+    if times.is_empty() {
+        return "no samples".to_string();
+    }
+    let mut sorted = times.to_vec();
+    sorted.sort();
+    let avg = sorted.iter().sum::<Duration>() / sorted.len() as u32;
+    // nearest rank
+    let p99 = sorted[(sorted.len() * 99).div_ceil(100) - 1];
+    let ms = |d: Duration| d.as_secs_f64() * 1000.;
+    format!(
+        "min {:.3} ms, avg {:.3} ms, p99 {:.3} ms",
+        ms(sorted[0]),
+        ms(avg),
+        ms(p99)
+    )
+}
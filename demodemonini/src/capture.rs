@@ -0,0 +1,340 @@
+//! Reading rendered frames back from the GPU to save or share them.
+
+use std::{
+    borrow::Cow,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, Command, Stdio},
+};
+
+/// Where a captured frame should go.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaptureTarget {
+    /// A png file in the working directory.
+    File,
+    /// The system clipboard, for pasting straight into chats and documents.
+    Clipboard,
+}
+
+/// A buffer that a frame is copied into during rendering
+/// and read back from once the GPU is done with it.
+pub struct FrameCapture {
+    buffer: wgpu::Buffer,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    // rows in the buffer are padded to wgpu's copy alignment
+    padded_bytes_per_row: u32,
+}
+
+impl FrameCapture {
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let padded_bytes_per_row = Self::padded_bytes_per_row(width);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("frame capture"),
+            size: Self::buffer_size(width, height),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        Self {
+            buffer,
+            format,
+            width,
+            height,
+            padded_bytes_per_row,
+        }
+    }
+
+    /// Whether this can capture frames of the given format and size,
+    /// or a new one is needed.
+    pub fn fits(&self, format: wgpu::TextureFormat, width: u32, height: u32) -> bool {
+        (self.format, self.width, self.height) == (format, width, height)
+    }
+
+    /// Bytes in the buffer needed to capture a frame of this size.
+    pub fn buffer_size(width: u32, height: u32) -> u64 {
+        Self::padded_bytes_per_row(width) as u64 * height as u64
+    }
+
+    fn padded_bytes_per_row(width: u32) -> u32 {
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        (4 * width).div_ceil(align) * align
+    }
+
+    /// Record a copy of the texture into the capture buffer.
+    /// The texture needs `COPY_SRC` usage and must be the size given in [`new`][Self::new].
+    pub fn copy_from(&self, encoder: &mut wgpu::CommandEncoder, texture: &wgpu::Texture) {
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &self.buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(self.padded_bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    /// Wait for the copy to finish and read the frame into an image.
+    /// Must be called after the commands from [`copy_from`][Self::copy_from] are submitted.
+    pub fn read(&self, device: &wgpu::Device) -> anyhow::Result<image::RgbaImage> {
+        // checked before mapping so the buffer isn't left mapped if this fails
+        let swap_red_blue = match self.format {
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            other => anyhow::bail!("Can't capture frames in format {other:?}"),
+        };
+
+        let slice = self.buffer.slice(..);
+        let (sender, receiver) = futures::channel::oneshot::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        // waiting for the device also runs the callback,
+        // so the result is ready without blocking on an executor
+        device.poll(wgpu::Maintain::Wait);
+        futures::FutureExt::now_or_never(receiver)
+            .ok_or(anyhow::anyhow!("Frame capture wasn't ready after waiting"))???;
+
+        let mut pixels = Vec::with_capacity((4 * self.width * self.height) as usize);
+        {
+            let data = slice.get_mapped_range();
+            for row in data.chunks_exact(self.padded_bytes_per_row as usize) {
+                pixels.extend_from_slice(&row[..(4 * self.width) as usize]);
+            }
+        }
+        self.buffer.unmap();
+
+        for pixel in pixels.chunks_exact_mut(4) {
+            if swap_red_blue {
+                pixel.swap(0, 2);
+            }
+            // the picture is opaque, but the swapchain's alpha may not be
+            pixel[3] = 255;
+        }
+        image::RgbaImage::from_raw(self.width, self.height, pixels)
+            .ok_or(anyhow::anyhow!("Captured frame has the wrong size"))
+    }
+}
+
+/// Save a captured frame as a png named after the current time.
+pub fn save_to_file(frame: &image::RgbaImage) -> anyhow::Result<String> {
+    let path = format!("demodemonini-{}.png", timestamp()?);
+    frame.save(&path)?;
+    Ok(path)
+}
+
+/// Writes every frame it's given as a numbered png into its own directory,
+/// to be assembled into a video with other tools.
+pub struct SequenceRecorder {
+    dir: PathBuf,
+    frame_count: u32,
+}
+
+impl SequenceRecorder {
+    /// Start a new recording in a directory named after the current time.
+    pub fn new() -> anyhow::Result<Self> {
+        let dir = PathBuf::from(format!("demodemonini-recording-{}", timestamp()?));
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            frame_count: 0,
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn save(&mut self, frame: &image::RgbaImage) -> anyhow::Result<()> {
+        frame.save(self.dir.join(format!("{:05}.png", self.frame_count)))?;
+        self.frame_count += 1;
+        Ok(())
+    }
+}
+
+/// Streams frames as raw pixels into an ffmpeg process that encodes them into a video.
+///
+/// The codec is picked based on the file extension,
+/// with explicit bt709 color tags so players don't guess the colors wrong.
+/// Gifs get a palette optimized for the whole clip and loop forever.
+pub struct VideoRecorder {
+    path: PathBuf,
+    ffmpeg: Child,
+    stdin: ChildStdin,
+    width: u32,
+    height: u32,
+    frame_count: u32,
+}
+
+impl VideoRecorder {
+    /// Start ffmpeg, expecting frames of the given size at `fps` frames per second.
+    pub fn new(path: &Path, width: u32, height: u32, fps: u32) -> anyhow::Result<Self> {
+        // yuv420p needs even dimensions, and the conversion is where colors usually go wrong
+        let yuv_args = [
+            "-vf",
+            "scale=trunc(iw/2)*2:trunc(ih/2)*2:out_color_matrix=bt709:out_range=tv",
+            "-pix_fmt",
+            "yuv420p",
+            "-color_primaries",
+            "bt709",
+            "-color_trc",
+            "bt709",
+            "-colorspace",
+            "bt709",
+            "-color_range",
+            "tv",
+        ];
+        let output_args: Vec<&str> = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("mp4" | "mkv" | "mov") => ["-c:v", "libx264", "-preset", "slow", "-crf", "16"]
+                .into_iter()
+                .chain(yuv_args)
+                .collect(),
+            Some("webm") => ["-c:v", "libvpx-vp9", "-crf", "20", "-b:v", "0"]
+                .into_iter()
+                .chain(yuv_args)
+                .collect(),
+            // a palette generated from the whole clip looks a lot better than a generic one
+            Some("gif") => vec![
+                "-vf",
+                "split[a][b];[a]palettegen=stats_mode=full[p];[b][p]paletteuse=dither=sierra2_4a",
+                "-loop",
+                "0",
+            ],
+            _ => yuv_args.to_vec(),
+        };
+        let mut ffmpeg = Command::new("ffmpeg")
+            .args(["-y", "-loglevel", "error"])
+            .args(["-f", "rawvideo", "-pixel_format", "rgba"])
+            .args(["-video_size", &format!("{width}x{height}")])
+            .args(["-framerate", &fps.to_string()])
+            .args(["-i", "-"])
+            .args(output_args)
+            .arg(path)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|err| anyhow::anyhow!("Failed to start ffmpeg: {err}"))?;
+        let stdin = ffmpeg.stdin.take().expect("ffmpeg stdin was piped");
+        Ok(Self {
+            path: path.to_owned(),
+            ffmpeg,
+            stdin,
+            width,
+            height,
+            frame_count: 0,
+        })
+    }
+
+    pub fn save(&mut self, frame: &image::RgbaImage) -> anyhow::Result<()> {
+        if frame.dimensions() != (self.width, self.height) {
+            anyhow::bail!("The window was resized, which can't be recorded into a video");
+        }
+        self.stdin.write_all(frame.as_raw())?;
+        self.frame_count += 1;
+        Ok(())
+    }
+
+    /// Close the stream and wait for ffmpeg to finish writing the file.
+    pub fn finish(self) -> anyhow::Result<()> {
+        let Self {
+            mut ffmpeg, stdin, ..
+        } = self;
+        drop(stdin);
+        let status = ffmpeg.wait()?;
+        if !status.success() {
+            anyhow::bail!("ffmpeg exited with {status}");
+        }
+        Ok(())
+    }
+}
+
+/// Either kind of recording, so they can be started and stopped the same way.
+pub enum Recorder {
+    Sequence(SequenceRecorder),
+    Video(VideoRecorder),
+}
+
+impl Recorder {
+    pub fn frame_count(&self) -> u32 {
+        match self {
+            Self::Sequence(rec) => rec.frame_count,
+            Self::Video(rec) => rec.frame_count,
+        }
+    }
+
+    pub fn save(&mut self, frame: &image::RgbaImage) -> anyhow::Result<()> {
+        match self {
+            Self::Sequence(rec) => rec.save(frame),
+            Self::Video(rec) => rec.save(frame),
+        }
+    }
+
+    /// Finish writing everything and describe what was recorded.
+    pub fn finish(self) -> anyhow::Result<String> {
+        Ok(match self {
+            Self::Sequence(rec) => format!(
+                "Recorded {} frames to {}",
+                rec.frame_count,
+                rec.dir.display()
+            ),
+            Self::Video(rec) => {
+                let summary = format!(
+                    "Recorded {} frames to {}",
+                    rec.frame_count,
+                    rec.path.display()
+                );
+                rec.finish()?;
+                summary
+            }
+        })
+    }
+}
+
+/// Text to stamp on a recorded frame: an optional signature
+/// and optionally a `minutes:seconds:frames` timecode counted from the start of the recording.
+pub fn watermark_text(signature: Option<&str>, timecode: bool, frame: u32, fps: u32) -> String {
+    let mut text = signature.unwrap_or_default().to_string();
+    if timecode {
+        if !text.is_empty() {
+            text += "  ";
+        }
+        let seconds = frame / fps;
+        text += &format!("{:02}:{:02}:{:02}", seconds / 60, seconds % 60, frame % fps);
+    }
+    text
+}
+
+// milliseconds since the unix epoch, for unique file names
+fn timestamp() -> anyhow::Result<u128> {
+    Ok(std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_millis())
+}
+
+/// Put a captured frame on the system clipboard.
+///
+/// On some platforms the clipboard only holds the image while the [`arboard::Clipboard`]
+/// that set it is alive, so the same one should be kept around and reused.
+pub fn copy_to_clipboard(
+    clipboard: &mut arboard::Clipboard,
+    frame: &image::RgbaImage,
+) -> anyhow::Result<()> {
+    clipboard.set_image(arboard::ImageData {
+        width: frame.width() as usize,
+        height: frame.height() as usize,
+        bytes: Cow::Borrowed(frame.as_raw()),
+    })?;
+    Ok(())
+}
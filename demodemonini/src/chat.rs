@@ -0,0 +1,144 @@
+//! Letting the audience of a stream toggle layers from Twitch chat,
+//! with commands like `!fire` and `!characters`.
+//!
+//! The commands can be set up in a text file with a command
+//! and the name of the action it triggers on each line:
+//!
+//! ```text
+//! !fire fire
+//! !people characters
+//! !glitch postprocess
+//! ```
+//!
+//! Chat is read anonymously over plain IRC, so no account or token is needed.
+//! Each action has a cooldown so a busy chat can't make the picture flicker.
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Write},
+    net::TcpStream,
+    path::Path,
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+use super::input::Action;
+
+const SERVER: &str = "irc.chat.twitch.tv:6667";
+// any nick of this form logs in read-only without a password
+const NICK: &str = "justinfan53421";
+const COOLDOWN: Duration = Duration::from_secs(5);
+// used when no file is given
+const DEFAULT_COMMANDS: [(&str, Action); 4] = [
+    ("!fire", Action::ToggleFire),
+    ("!characters", Action::ToggleCharacters),
+    ("!postprocess", Action::TogglePostprocess),
+    ("!glitch", Action::TogglePostprocess),
+];
+
+type Commands = HashMap<String, Action>;
+
+pub struct ChatControl {
+    actions: mpsc::Receiver<Action>,
+}
+
+impl ChatControl {
+    /// Join the chat of the given Twitch channel,
+    /// with the commands set up in the file at `commands_path` if there is one.
+    pub fn join(channel: &str, commands_path: Option<&Path>) -> anyhow::Result<Self> {
+        let commands = match commands_path {
+            Some(path) => load_commands(path)?,
+            None => DEFAULT_COMMANDS
+                .iter()
+                .map(|&(command, action)| (command.to_string(), action))
+                .collect(),
+        };
+        let channel = channel.trim_start_matches('#').to_lowercase();
+        let mut stream = TcpStream::connect(SERVER)?;
+        write!(stream, "NICK {NICK}\r\nJOIN #{channel}\r\n")?;
+        println!("Listening to chat commands in #{channel}");
+
+        let (send, actions) = mpsc::channel();
+        std::thread::spawn(move || {
+            if let Err(err) = listen(stream, &commands, &send) {
+                eprintln!("Disconnected from chat: {err}");
+            }
+        });
+        Ok(Self { actions })
+    }
+
+    /// Add the actions commanded since the last call to `actions`.
+    pub fn poll(&self, actions: &mut Vec<Action>) {
+        actions.extend(self.actions.try_iter());
+    }
+}
+
+/// Read commands from a file as described in the module docs.
+fn load_commands(path: &Path) -> anyhow::Result<Commands> {
+    let file = std::fs::read_to_string(path)
+        .map_err(|err| anyhow::anyhow!("Failed to read {}: {err}", path.display()))?;
+    let mut commands = Commands::new();
+    for (line_idx, line) in file.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = |msg: &str| anyhow::anyhow!("{}:{}: {msg}", path.display(), line_idx + 1);
+        let [command, name] = line.split_whitespace().collect::<Vec<_>>()[..] else {
+            return Err(invalid("expected <COMMAND> <ACTION>"));
+        };
+        let action = match Action::from_name(name) {
+            // nobody in chat should be able to quit the demo
+            Some(Action::Quit) => return Err(invalid("quitting can't be a chat command")),
+            Some(action) => action,
+            None => return Err(invalid(&format!("unknown action {name:?}"))),
+        };
+        commands.insert(command.to_string(), action);
+    }
+    Ok(commands)
+}
+
+fn listen(
+    stream: TcpStream,
+    commands: &Commands,
+    actions: &mpsc::Sender<Action>,
+) -> anyhow::Result<()> {
+    let mut writer = stream.try_clone()?;
+    // by action so that commands for the same one share a cooldown
+    let mut last_used: HashMap<&str, Instant> = HashMap::new();
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        // the server disconnects clients that don't answer pings
+        if let Some(server) = line.strip_prefix("PING ") {
+            write!(writer, "PONG {server}\r\n")?;
+            continue;
+        }
+        // messages look like ":nick!nick@nick.tmi.twitch.tv PRIVMSG #channel :text"
+        let Some((_, text)) = line
+            .split_once(" PRIVMSG ")
+            .and_then(|(_, m)| m.split_once(" :"))
+        else {
+            continue;
+        };
+        let Some(&action) = text
+            .split_whitespace()
+            .next()
+            .and_then(|command| commands.get(command))
+        else {
+            continue;
+        };
+        let now = Instant::now();
+        if last_used
+            .get(action.name())
+            .is_some_and(|&last| now - last < COOLDOWN)
+        {
+            continue;
+        }
+        last_used.insert(action.name(), now);
+        // the receiver is only gone when the demo is shutting down
+        if actions.send(action).is_err() {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
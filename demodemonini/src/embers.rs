@@ -0,0 +1,190 @@
+use rand::Rng;
+
+use super::{
+    lines::{tessellate_polyline, LinePoint},
+    pipelines::{InstancedTexturePipeline, LineVertex, QuadInstance, TexturePipeline},
+    textures::{create_rgba_texture, ColorSpace},
+};
+
+const MAX_EMBERS: usize = 64;
+const SPAWN_RATE: f32 = 12.;
+// size of the generated glow texture
+const DOT_SIZE: u32 = 16;
+// faint streaks left behind the embers, as points along their path
+const TRAIL_POINTS: usize = 5;
+const TRAIL_DURATION: f32 = 0.25;
+const WOBBLE_AMOUNT: f32 = 0.05;
+const WOBBLE_FREQ: f32 = 3.;
+
+#[derive(Clone, Copy, Debug)]
+struct Ember {
+    pos: [f32; 2],
+    vel: [f32; 2],
+    age: f32,
+    lifetime: f32,
+    size: f32,
+    // offset for the sideways wobble so embers don't move in sync
+    phase: f32,
+}
+
+/// Glowing sparks rising from the fire, all drawn with a single instanced draw call.
+pub struct Embers {
+    embers: Vec<Ember>,
+    instances: Vec<QuadInstance>,
+    instance_buf: wgpu::Buffer,
+    trail_verts: Vec<LineVertex>,
+    trail_buf: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    // kept around for as long as the bind group uses it
+    _texture: wgpu::Texture,
+    // horizontal range and height embers spawn from
+    spawn_x: [f32; 2],
+    spawn_y: f32,
+    depth: f32,
+    spawn_timer: f32,
+}
+
+impl Embers {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        pipeline: &TexturePipeline,
+        sampler: &wgpu::Sampler,
+        spawn_x: [f32; 2],
+        spawn_y: f32,
+        depth: f32,
+    ) -> Self {
+        // soft round dot, tinted per ember in the shader
+        let dot = image::RgbaImage::from_fn(DOT_SIZE, DOT_SIZE, |x, y| {
+            let half = DOT_SIZE as f32 / 2.;
+            let dx = (x as f32 + 0.5 - half) / half;
+            let dy = (y as f32 + 0.5 - half) / half;
+            let falloff = (1. - (dx * dx + dy * dy).sqrt()).max(0.);
+            image::Rgba([255, 255, 255, (255. * falloff * falloff) as u8])
+        });
+        let texture = create_rgba_texture(device, queue, "ember", &dot, ColorSpace::Linear);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = pipeline.create_bind_group(device, "ember", &view, sampler);
+
+        Self {
+            embers: Vec::with_capacity(MAX_EMBERS),
+            instances: Vec::with_capacity(MAX_EMBERS),
+            instance_buf: InstancedTexturePipeline::create_instance_buffer(
+                device, "embers", MAX_EMBERS,
+            ),
+            trail_verts: Vec::new(),
+            trail_buf: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("ember trails"),
+                size: (MAX_EMBERS * (TRAIL_POINTS - 1) * 6 * std::mem::size_of::<LineVertex>())
+                    as u64,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            bind_group,
+            _texture: texture,
+            spawn_x,
+            spawn_y,
+            depth,
+            spawn_timer: 0.,
+        }
+    }
+
+    /// Move the embers forward in time and upload their new positions.
+    pub fn update(&mut self, queue: &wgpu::Queue, dt: f32, rng: &mut impl Rng) {
+        self.spawn_timer += dt;
+        while self.spawn_timer > 1. / SPAWN_RATE {
+            self.spawn_timer -= 1. / SPAWN_RATE;
+            if self.embers.len() >= MAX_EMBERS {
+                continue;
+            }
+            self.embers.push(Ember {
+                pos: [
+                    rng.gen_range(self.spawn_x[0]..self.spawn_x[1]),
+                    self.spawn_y,
+                ],
+                vel: [rng.gen_range(-0.03..0.03), rng.gen_range(0.12..0.3)],
+                age: 0.,
+                lifetime: rng.gen_range(1.5..3.5),
+                size: rng.gen_range(0.004..0.01),
+                phase: rng.gen_range(0. ..std::f32::consts::TAU),
+            });
+        }
+
+        for ember in &mut self.embers {
+            ember.age += dt;
+            let wobble = WOBBLE_AMOUNT * (WOBBLE_FREQ * ember.age + ember.phase).sin();
+            ember.pos[0] += (ember.vel[0] + wobble) * dt;
+            ember.pos[1] += ember.vel[1] * dt;
+        }
+        self.embers.retain(|e| e.age < e.lifetime);
+
+        self.instances.clear();
+        self.instances.extend(self.embers.iter().map(|ember| {
+            // glow brightly at first and fade out towards the end of life
+            let life_left = 1. - ember.age / ember.lifetime;
+            QuadInstance {
+                center: ember.pos,
+                half_size: [ember.size, ember.size],
+                rotation: 0.,
+                depth: self.depth,
+                uv_min: [0., 0.],
+                uv_max: [1., 1.],
+                tint: [1., 0.45 + 0.4 * life_left, 0.15, life_left],
+            }
+        }));
+        queue.write_buffer(&self.instance_buf, 0, bytemuck::cast_slice(&self.instances));
+
+        self.trail_verts.clear();
+        let mut trail = Vec::with_capacity(TRAIL_POINTS);
+        for ember in &self.embers {
+            let life_left = 1. - ember.age / ember.lifetime;
+            trail.clear();
+            trail.extend((0..TRAIL_POINTS).map(|i| {
+                // fraction of the way to the end of the trail
+                let along = i as f32 / (TRAIL_POINTS - 1) as f32;
+                let back_t = (along * TRAIL_DURATION).min(ember.age);
+                LinePoint {
+                    pos: ember.past_pos(back_t),
+                    width: ember.size * (1. - along),
+                    color: [1., 0.5, 0.2, 0.5 * life_left * (1. - along)],
+                }
+            }));
+            tessellate_polyline(&trail, self.depth, &mut self.trail_verts);
+        }
+        queue.write_buffer(&self.trail_buf, 0, bytemuck::cast_slice(&self.trail_verts));
+    }
+
+    /// Draw the streaks behind the embers, expecting the line pipeline to be set already.
+    pub fn draw_trails<'pass>(&'pass self, pass: &mut wgpu::RenderPass<'pass>) {
+        if self.trail_verts.is_empty() {
+            return;
+        }
+        pass.set_vertex_buffer(0, self.trail_buf.slice(..));
+        pass.draw(0..self.trail_verts.len() as u32, 0..1);
+    }
+
+    /// Draw the embers, expecting the instanced texture pipeline to be set already.
+    pub fn draw<'pass>(&'pass self, pass: &mut wgpu::RenderPass<'pass>) {
+        if self.instances.is_empty() {
+            return;
+        }
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.set_vertex_buffer(0, self.instance_buf.slice(..));
+        pass.draw(0..6, 0..self.instances.len() as u32);
+    }
+}
+
+impl Ember {
+    /// Where the ember was `back_t` seconds ago,
+    /// following the same wobbly path as the simulation in [`Embers::update`].
+    fn past_pos(&self, back_t: f32) -> [f32; 2] {
+        // integral of the wobble velocity between then and now
+        let wobble_at =
+            |age: f32| -WOBBLE_AMOUNT / WOBBLE_FREQ * (WOBBLE_FREQ * age + self.phase).cos();
+        let wobble_dist = wobble_at(self.age) - wobble_at(self.age - back_t);
+        [
+            self.pos[0] - self.vel[0] * back_t - wobble_dist,
+            self.pos[1] - self.vel[1] * back_t,
+        ]
+    }
+}
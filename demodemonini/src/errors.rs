@@ -0,0 +1,108 @@
+use std::{
+    fmt::Display,
+    sync::{Arc, Mutex},
+};
+
+// only the latest few errors are kept around for display,
+// everything goes to the log file anyway
+const MAX_KEPT_ERRORS: usize = 8;
+
+/// Collects errors from the GPU and the rest of the demo
+/// so they can be shown on screen and written to a log file instead of crashing,
+/// making problems on other people's machines diagnosable.
+///
+/// Cheap to clone, all clones share the same log.
+#[derive(Clone, Default)]
+pub struct ErrorLog {
+    inner: Arc<Mutex<ErrorLogInner>>,
+}
+
+#[derive(Default)]
+struct ErrorLogInner {
+    messages: Vec<String>,
+    changed: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    file: Option<std::fs::File>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const LOG_FILE_NAME: &str = "demodemonini-errors.log";
+
+impl ErrorLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an error that happened while doing `context`.
+    pub fn push(&self, context: &str, err: impl Display) {
+        let message = format!("{context}: {err}");
+
+        #[cfg(not(target_arch = "wasm32"))]
+        eprintln!("{message}");
+        #[cfg(target_arch = "wasm32")]
+        log::error!("{message}");
+
+        let mut inner = self.inner.lock().unwrap();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            use std::io::Write;
+            // only create the file once something actually goes wrong
+            if inner.file.is_none() {
+                match std::fs::File::create(LOG_FILE_NAME) {
+                    Ok(f) => {
+                        eprintln!("Writing errors to {LOG_FILE_NAME}");
+                        inner.file = Some(f);
+                    }
+                    Err(err) => eprintln!("Failed to create {LOG_FILE_NAME}: {err}"),
+                }
+            }
+            if let Some(file) = &mut inner.file {
+                let _ = writeln!(file, "{message}\n");
+            }
+        }
+
+        inner.messages.push(message);
+        if inner.messages.len() > MAX_KEPT_ERRORS {
+            inner.messages.remove(0);
+        }
+        inner.changed = true;
+    }
+
+    /// Route errors that weren't caught by an error scope into this log
+    /// instead of wgpu's default handler, which panics.
+    pub fn capture_uncaptured(&self, device: &wgpu::Device) {
+        let log = self.clone();
+        device.on_uncaptured_error(Box::new(move |err| log.push("Uncaptured GPU error", err)));
+    }
+
+    /// Pop the innermost error scope on the device
+    /// and record the error it caught, if any.
+    ///
+    /// On native the result is available immediately,
+    /// on the web it arrives asynchronously a bit later.
+    pub fn pop_scope(&self, device: &wgpu::Device, context: &'static str) {
+        let result = device.pop_error_scope();
+        let log = self.clone();
+        let record = async move {
+            if let Some(err) = result.await {
+                log.push(context, err);
+            }
+        };
+        // not blocking on it because this can be called from inside an async main
+        #[cfg(not(target_arch = "wasm32"))]
+        futures::FutureExt::now_or_never(record);
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(record);
+    }
+
+    /// Get the recorded messages if there have been new ones since the last call.
+    pub fn take_changed(&self) -> Option<Vec<String>> {
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.changed {
+            return None;
+        }
+        inner.changed = false;
+        Some(inner.messages.clone())
+    }
+}
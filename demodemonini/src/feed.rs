@@ -0,0 +1,177 @@
+//! Driving parameters of the picture with outside data like weather or sensor readings,
+//! for installations that react to their surroundings.
+//!
+//! Anything that produces values can be a [`SignalSource`].
+//! The one built in polls a URL for JSON, set up in a text file like this:
+//!
+//! ```text
+//! url https://api.open-meteo.com/v1/forecast?latitude=60.17&longitude=24.94&current=wind_speed_10m,temperature_2m
+//! every 600
+//! wind /current/wind_speed_10m 0 15
+//! glow /current/temperature_2m -20 30
+//! ```
+//!
+//! `every` is the polling interval in seconds, 60 by default.
+//! The other lines map a number in the response, found with a JSON pointer,
+//! onto a parameter so that the first value of the range gives 0 and the second gives 1.
+//! Values outside the range are clamped.
+
+use std::{
+    path::Path,
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+use super::scene::Scene;
+
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Something in the picture that outside data can control,
+/// each driven with a value between 0 and 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Param {
+    /// From still air to a strong wind blowing the fire to the right.
+    Wind,
+    /// From a low fire to a tall one, the default height being halfway.
+    Heat,
+    /// From no glow around the fire to twice the default.
+    Glow,
+    /// The hour of the day to pick colors for, from midnight to midnight.
+    Hour,
+}
+
+impl Param {
+    pub const NAMES: &'static str = "wind, heat, glow or hour";
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "wind" => Param::Wind,
+            "heat" => Param::Heat,
+            "glow" => Param::Glow,
+            "hour" => Param::Hour,
+            _ => return None,
+        })
+    }
+
+    /// Set the parameter in the scene. The hour isn't part of the scene,
+    /// so it's returned to pick the colors with instead.
+    pub fn apply(self, scene: &mut Scene, value: f32) -> Option<f32> {
+        match self {
+            Param::Wind => scene.set_wind(value),
+            Param::Heat => scene.set_heat(value),
+            Param::Glow => scene.glow = 2. * value,
+            Param::Hour => return Some(24. * value),
+        }
+        None
+    }
+}
+
+/// Something that gives values to parameters, polled every frame.
+pub trait SignalSource {
+    /// Values that have arrived since the last call, between 0 and 1.
+    fn poll(&mut self) -> Vec<(Param, f32)>;
+}
+
+// a number in the response mapped onto a parameter
+struct Mapping {
+    param: Param,
+    pointer: String,
+    range: (f64, f64),
+}
+
+/// Polls a URL for JSON in a background thread, see the module docs.
+pub struct HttpFeed {
+    values: mpsc::Receiver<(Param, f32)>,
+}
+
+impl HttpFeed {
+    /// Start polling as set up in the file at `path`.
+    pub fn start(path: &Path) -> anyhow::Result<Self> {
+        let config = std::fs::read_to_string(path)
+            .map_err(|err| anyhow::anyhow!("Failed to read {}: {err}", path.display()))?;
+        let mut url = None;
+        let mut interval = DEFAULT_INTERVAL;
+        let mut mappings = Vec::new();
+        for (line_idx, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |msg: &str| anyhow::anyhow!("{}:{}: {msg}", path.display(), line_idx + 1);
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words[..] {
+                ["url", u] => url = Some(u.to_string()),
+                ["every", seconds] => {
+                    let seconds: f64 = seconds
+                        .parse()
+                        .map_err(|_| invalid("expected the interval in seconds"))?;
+                    interval = Duration::from_secs_f64(seconds.max(1.));
+                }
+                [param, pointer, from, to] => {
+                    let param = Param::from_name(param).ok_or_else(|| {
+                        invalid(&format!("unknown parameter, expected {}", Param::NAMES))
+                    })?;
+                    let parse = |v: &str| v.parse().map_err(|_| invalid("expected a number"));
+                    mappings.push(Mapping {
+                        param,
+                        pointer: pointer.to_string(),
+                        range: (parse(from)?, parse(to)?),
+                    });
+                }
+                _ => {
+                    return Err(invalid(
+                        "expected url, every or <PARAM> <POINTER> <FROM> <TO>",
+                    ))
+                }
+            }
+        }
+        let url = url.ok_or(anyhow::anyhow!("{} has no url to poll", path.display()))?;
+        println!("Polling {url} every {} s", interval.as_secs_f64());
+
+        let (send, values) = mpsc::channel();
+        std::thread::spawn(move || poll_loop(&url, interval, &mappings, &send));
+        Ok(Self { values })
+    }
+}
+
+impl SignalSource for HttpFeed {
+    fn poll(&mut self) -> Vec<(Param, f32)> {
+        self.values.try_iter().collect()
+    }
+}
+
+fn poll_loop(
+    url: &str,
+    interval: Duration,
+    mappings: &[Mapping],
+    values: &mpsc::Sender<(Param, f32)>,
+) {
+    let agent = ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build();
+    loop {
+        let started = Instant::now();
+        // a failed request is retried on the next round, keeping the previous values
+        match agent.get(url).call() {
+            Ok(response) => match response.into_json::<serde_json::Value>() {
+                Ok(json) => {
+                    for mapping in mappings {
+                        let Some(number) = json.pointer(&mapping.pointer).and_then(|v| v.as_f64())
+                        else {
+                            eprintln!("No number at {} in the feed", mapping.pointer);
+                            continue;
+                        };
+                        let (from, to) = mapping.range;
+                        let value = ((number - from) / (to - from)).clamp(0., 1.) as f32;
+                        // the receiver is only gone when the demo is shutting down
+                        if values.send((mapping.param, value)).is_err() {
+                            return;
+                        }
+                    }
+                }
+                Err(err) => eprintln!("Invalid JSON from {url}: {err}"),
+            },
+            Err(err) => eprintln!("Failed to poll {url}: {err}"),
+        }
+        std::thread::sleep(interval.saturating_sub(started.elapsed()));
+    }
+}
@@ -1,9 +1,10 @@
 use enterpolation::{linear::Linear, Curve};
 use itertools::{iproduct, izip};
-use lazy_static::lazy_static;
-use palette::{IntoColor, LinSrgba, Srgba};
+use palette::{LinSrgba, Srgba};
 use rand::Rng;
 
+use super::textures::ColorSpace;
+
 /// "Doom fire"
 /// based on this: https://fabiensanglard.net/doom_fire_psx/
 #[derive(Clone, Debug)]
@@ -12,40 +13,50 @@ pub struct Fire {
     pub height: usize,
     // average amount of cooling per row propagated upwards
     pub cooling_rate: f32,
+    // extra push to the right, or to the left if negative, between -1 and 1
+    pub wind: f32,
     // heat value in the range [0, 1] for every pixel in the fire
     heat_buf: Vec<f32>,
+    // minimum heat kept up in every pixel, e.g. from a camera image
+    fuel: Option<Vec<f32>>,
+    palette_lut: [[u8; 4]; PALETTE_SIZE],
 }
 
+/// Colors of the fire from coldest to hottest.
+pub type FirePalette = [Srgba; 6];
+
+/// The fire's colors as painted, used unless a theme changes them.
+pub const DEFAULT_PALETTE: FirePalette = [
+    Srgba::new(0., 0., 0., 0.3),
+    Srgba::new(0.250, 0.015, 0., 0.5),
+    Srgba::new(0.450, 0.170, 0.070, 0.7),
+    Srgba::new(0.850, 0.506, 0.161, 0.8),
+    Srgba::new(0.960, 0.812, 0.154, 0.8),
+    Srgba::new(1., 1., 1., 0.8),
+];
+// heat of each color in the palette
+const PALETTE_KNOTS: [f32; 6] = [0., 0.3, 0.5, 0.8, 0.95, 1.];
+
 // generate a lookup table for the color palette
 const PALETTE_SIZE: usize = 32;
-lazy_static! {
-    static ref PALETTE_LUT: [[u8; 4]; PALETTE_SIZE] = {
-        let curve = Linear::builder()
-            .elements([
-                Srgba::new(0., 0., 0., 0.3).into_linear(),
-                Srgba::new(0.250, 0.015, 0., 0.5).into_linear(),
-                Srgba::new(0.450, 0.170, 0.070, 0.7).into_linear(),
-                Srgba::new(0.850, 0.506, 0.161, 0.8).into_linear(),
-                Srgba::new(0.960, 0.812, 0.154, 0.8).into_linear(),
-                Srgba::new(1., 1., 1., 0.8).into_linear(),
-            ])
-            .knots([0., 0.3, 0.5, 0.8, 0.95, 1.])
-            .build()
-            .unwrap();
-        let vals = curve.take(PALETTE_SIZE);
-        let mut lut = [[0; 4]; PALETTE_SIZE];
-        for (color, lut_val) in izip!(vals, lut.iter_mut()) {
-            let c_lin: LinSrgba = color.into_color();
-            let as_u8 = |channel: f32| (u8::MAX as f32 * channel).round() as u8;
-            *lut_val = [
-                as_u8(c_lin.red),
-                as_u8(c_lin.green),
-                as_u8(c_lin.blue),
-                as_u8(c_lin.alpha),
-            ];
-        }
-        lut
-    };
+fn palette_lut(palette: &[LinSrgba; 6]) -> [[u8; 4]; PALETTE_SIZE] {
+    let curve = Linear::builder()
+        .elements(*palette)
+        .knots(PALETTE_KNOTS)
+        .build()
+        .unwrap();
+    let vals = curve.take(PALETTE_SIZE);
+    let mut lut = [[0; 4]; PALETTE_SIZE];
+    for (c_lin, lut_val) in izip!(vals, lut.iter_mut()) {
+        let as_u8 = |channel: f32| (u8::MAX as f32 * channel).round() as u8;
+        *lut_val = [
+            as_u8(c_lin.red),
+            as_u8(c_lin.green),
+            as_u8(c_lin.blue),
+            as_u8(c_lin.alpha),
+        ];
+    }
+    lut
 }
 
 impl Fire {
@@ -61,32 +72,60 @@ impl Fire {
             width,
             height,
             cooling_rate,
+            wind: 0.,
             heat_buf,
+            fuel: None,
+            palette_lut: palette_lut(&DEFAULT_PALETTE.map(|c| c.into_linear())),
         }
     }
 
-    pub fn propagate(&mut self) {
+    pub fn propagate(&mut self, rng: &mut impl Rng) {
         // random jitter in the amount of cooling
         let cooling_variance = self.cooling_rate * 0.9;
         let cooling_range =
             self.cooling_rate - cooling_variance..=self.cooling_rate + cooling_variance;
-        let mut rng = rand::thread_rng();
 
         for (x, y) in iproduct!(0..self.width, 1..self.height) {
             let source_idx = y * self.width + x;
             let target_idx = {
                 let above = source_idx - self.width;
-                let wind: isize = rng.gen_range(-1..=2);
+                let mut wind: isize = rng.gen_range(-1..=2);
+                // only drawing a random number with wind keeps seeded renders the same without it
+                if self.wind != 0. && rng.gen::<f32>() < self.wind.abs() {
+                    wind += self.wind.signum() as isize;
+                }
                 (above as isize + wind).max(0) as usize
             };
             let cooling = rng.gen_range(cooling_range.clone());
             self.heat_buf[target_idx] = (self.heat_buf[source_idx] - cooling).max(0.);
         }
+
+        if let Some(fuel) = &self.fuel {
+            for (heat, &fuel) in self.heat_buf.iter_mut().zip(fuel) {
+                *heat = heat.max(fuel);
+            }
+        }
+    }
+
+    /// Keep every pixel burning at least as hot as the corresponding value in `fuel`,
+    /// given row by row from the top, or stop doing so with `None`.
+    pub fn set_fuel(&mut self, fuel: Option<Vec<f32>>) {
+        if let Some(fuel) = &fuel {
+            debug_assert_eq!(fuel.len(), self.heat_buf.len());
+        }
+        self.fuel = fuel;
+    }
+
+    /// Color the fire with a different palette, given in linear color
+    /// so that palettes can be blended between.
+    /// Shows up the next time the texture is written.
+    pub fn set_palette(&mut self, palette: &[LinSrgba; 6]) {
+        self.palette_lut = palette_lut(palette);
     }
 
     pub fn create_texture(&self, device: &wgpu::Device) -> wgpu::Texture {
         device.create_texture(&wgpu::TextureDescriptor {
-            label: None,
+            label: Some("fire"),
             size: wgpu::Extent3d {
                 width: self.width as u32,
                 height: self.height as u32,
@@ -95,20 +134,20 @@ impl Fire {
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8Unorm,
+            // the palette is converted to linear when the lookup table is built
+            format: ColorSpace::Linear.rgba8_format(),
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
             view_formats: &[],
         })
     }
 
     pub fn write_texture(&self, queue: &wgpu::Queue, texture: &wgpu::Texture) {
-        // TODO: color with a palette
         let color_data: Vec<[u8; 4]> = self
             .heat_buf
             .iter()
             .map(|&temp| {
                 let lut_idx = ((temp * PALETTE_SIZE as f32) as usize).min(PALETTE_SIZE - 1);
-                PALETTE_LUT[lut_idx]
+                self.palette_lut[lut_idx]
             })
             .collect();
 
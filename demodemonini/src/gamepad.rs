@@ -0,0 +1,46 @@
+//! Gamepad input, so the demo can be driven from a couch or a show booth without a keyboard.
+
+use gilrs::{Button, EventType, Gilrs};
+
+use super::{capture::CaptureTarget, input::Action};
+
+pub struct Gamepads {
+    gilrs: Gilrs,
+}
+
+impl Gamepads {
+    /// Start listening to gamepads, or return `None` if they're not supported on this system.
+    pub fn new() -> Option<Self> {
+        match Gilrs::new() {
+            Ok(gilrs) => Some(Self { gilrs }),
+            Err(err) => {
+                eprintln!("Gamepads aren't available: {err}");
+                None
+            }
+        }
+    }
+
+    /// Add the actions for buttons pressed since the last call to `actions`.
+    pub fn poll(&mut self, actions: &mut Vec<Action>) {
+        while let Some(event) = self.gilrs.next_event() {
+            if let EventType::ButtonPressed(button, _) = event.event {
+                actions.extend(button_action(button));
+            }
+        }
+    }
+}
+
+// quitting is left out so it can't happen by accident in front of an audience
+fn button_action(button: Button) -> Option<Action> {
+    Some(match button {
+        Button::South => Action::ToggleFire,
+        Button::East => Action::ToggleCharacters,
+        Button::West => Action::TogglePostprocess,
+        Button::North => Action::Capture(CaptureTarget::File),
+        Button::Start => Action::ToggleRecording,
+        Button::Select => Action::SaveStill,
+        Button::DPadLeft => Action::SeekBackward,
+        Button::DPadRight => Action::SeekForward,
+        _ => return None,
+    })
+}
@@ -0,0 +1,370 @@
+//! Rendering frames into offscreen textures instead of a window,
+//! for servers, reproducible final renders and high resolution stills.
+
+use std::ops::Range;
+
+use super::{
+    args::Args,
+    assets,
+    capture::{self, FrameCapture, Recorder, SequenceRecorder, VideoRecorder},
+    errors::ErrorLog,
+    input::Action,
+    input_log::{InputEvent, InputPlayback},
+    pipelines::ViewRegion,
+    request_device,
+    scene::Scene,
+    text::{Corner, TextOverlay},
+    theme::Theme,
+    timeline::Timeline,
+    SWAPCHAIN_FORMAT,
+};
+
+// same as the window's default size on a display without scaling
+const DEFAULT_SIZE: (u32, u32) = (1440, 1080);
+
+/// Render frames 0..N, frame `i` at time `i / fps`,
+/// and write them into the file given with `--record` or a png sequence.
+pub async fn render(args: &Args) -> anyhow::Result<()> {
+    let frame_count = match (args.frames, args.duration) {
+        (Some(frames), _) => frames,
+        (None, Some(duration)) => (duration * args.fps as f64).round() as u32,
+        (None, None) => anyhow::bail!("Headless rendering needs --frames or --duration"),
+    };
+    let mut offscreen = Offscreen::new(args).await?;
+    let (width, height) = offscreen.size;
+
+    let mut recorder = match &args.record {
+        Some(path) => Recorder::Video(VideoRecorder::new(path, width, height, args.fps)?),
+        None => Recorder::Sequence(SequenceRecorder::new()?),
+    };
+    println!(
+        "Rendering {frame_count} frames of {width}x{height} with seed {}",
+        offscreen.seed
+    );
+
+    let dt = 1. / args.fps as f64;
+    for frame in 0..frame_count {
+        // the first frame shows the starting state at t = 0
+        offscreen.update(if frame == 0 { 0. } else { dt });
+        offscreen.set_watermark(&args.watermark_text(recorder.frame_count()));
+        recorder.save(&offscreen.render()?)?;
+    }
+
+    println!("{}", recorder.finish()?);
+    offscreen.check_errors()
+}
+
+/// Simulate up to time `t` in steps of `1 / fps` and render a single frame,
+/// saving it to the file given with `--out` or one named after the current time.
+pub async fn render_frame_at(args: &Args, t: f64) -> anyhow::Result<()> {
+    let mut offscreen = Offscreen::new(args).await?;
+
+    // same steps as a recording at this frame rate would take, so the frames match
+    let dt = 1. / args.fps as f64;
+    let steps = (t / dt).round() as u32;
+    offscreen.update(0.);
+    for _ in 0..steps {
+        offscreen.update(dt);
+    }
+    let frame = offscreen.render()?;
+
+    let path = match &args.out {
+        Some(path) => {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            frame.save(path)?;
+            path.display().to_string()
+        }
+        None => capture::save_to_file(&frame)?,
+    };
+    println!(
+        "Saved frame at t = {:.3} with seed {} to {path}",
+        steps as f64 * dt,
+        offscreen.seed
+    );
+    offscreen.check_errors()
+}
+
+/// Render the scene as it is right now at `scale` times the size it's normally drawn at,
+/// and save it as a png.
+///
+/// Pictures too big for one texture on this device are rendered in tiles
+/// and stitched together.
+/// The scene is resized back to `normal_size` afterwards.
+pub fn render_still(
+    scene: &mut Scene,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    normal_size: (u32, u32),
+    scale: u32,
+) -> anyhow::Result<String> {
+    let (width, height) = (normal_size.0 * scale, normal_size.1 * scale);
+    let (margin_x, margin_y) = (tile_margin(width), tile_margin(height));
+    let limits = device.limits();
+    let too_big = || anyhow::anyhow!("{width}x{height} is too big to render even in tiles");
+    let fits_side = |size: u32, margin: u32, count: u32| {
+        padded_tile_len(size, margin, count) <= limits.max_texture_dimension_2d
+    };
+    let cols = (1..=width)
+        .find(|&n| fits_side(width, margin_x, n))
+        .ok_or_else(too_big)?;
+    // cutting the picture into rows shrinks the capture buffer too
+    let rows = (1..=height)
+        .find(|&n| {
+            fits_side(height, margin_y, n)
+                && FrameCapture::buffer_size(
+                    padded_tile_len(width, margin_x, cols),
+                    padded_tile_len(height, margin_y, n),
+                ) <= limits.max_buffer_size
+        })
+        .ok_or_else(too_big)?;
+    if cols * rows > 1 {
+        println!("Rendering {width}x{height} in {cols}x{rows} tiles to fit in a texture");
+    }
+
+    let mut render_tiles = || -> anyhow::Result<image::RgbaImage> {
+        let mut picture = image::RgbaImage::new(width, height);
+        for row in 0..rows {
+            let (y, padded_y) = tile_span(height, margin_y, rows, row);
+            for col in 0..cols {
+                let (x, padded_x) = tile_span(width, margin_x, cols, col);
+                let (tile_w, tile_h) = (padded_x.len() as u32, padded_y.len() as u32);
+                // clip space has y pointing up and the picture has it pointing down
+                let to_clip = |p: u32, size: u32| 2. * p as f32 / size as f32 - 1.;
+                scene.set_view(
+                    queue,
+                    ViewRegion::between(
+                        [
+                            to_clip(padded_x.start, width),
+                            -to_clip(padded_y.end, height),
+                        ],
+                        [
+                            to_clip(padded_x.end, width),
+                            -to_clip(padded_y.start, height),
+                        ],
+                    ),
+                );
+                scene.resize(device, tile_w, tile_h);
+                let target = OffscreenTarget::new(device, tile_w, tile_h);
+                let tile = target.render(scene, None, device, queue)?;
+                let inner = image::imageops::crop_imm(
+                    &tile,
+                    x.start - padded_x.start,
+                    y.start - padded_y.start,
+                    x.len() as u32,
+                    y.len() as u32,
+                );
+                image::imageops::replace(&mut picture, &*inner, x.start as i64, y.start as i64);
+            }
+        }
+        Ok(picture)
+    };
+    let picture = render_tiles();
+    scene.set_view(queue, ViewRegion::FULL);
+    scene.resize(device, normal_size.0, normal_size.1);
+
+    capture::save_to_file(&picture?)
+}
+
+// the postprocess distortion reaches up to about 5.6% of the picture's size away
+// from the pixel being drawn, plus a bit for chromatic aberration and filtering
+fn tile_margin(size: u32) -> u32 {
+    (size as f32 * 0.06).ceil() as u32 + 2
+}
+
+/// The part of a picture `size` pixels long that tile `idx` of `count` covers,
+/// and the same with a margin on the sides that have other tiles next to them.
+fn tile_span(size: u32, margin: u32, count: u32, idx: u32) -> (Range<u32>, Range<u32>) {
+    // in u64 so the product can't overflow
+    let edge = |idx: u32| (idx as u64 * size as u64 / count as u64) as u32;
+    let (start, end) = (edge(idx), edge(idx + 1));
+    let padded = start.saturating_sub(margin)..(end + margin).min(size);
+    (start..end, padded)
+}
+
+// the longest that `tile_span` makes a padded tile
+fn padded_tile_len(size: u32, margin: u32, count: u32) -> u32 {
+    (0..count)
+        .map(|idx| tile_span(size, margin, count, idx).1.len() as u32)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Everything needed to render the scene without a window.
+struct Offscreen {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    error_log: ErrorLog,
+    scene: Scene,
+    target: OffscreenTarget,
+    watermark: Option<TextOverlay>,
+    timeline: Option<Timeline>,
+    input: Option<InputPlayback>,
+    size: (u32, u32),
+    seed: u64,
+}
+
+impl Offscreen {
+    async fn new(args: &Args) -> anyhow::Result<Self> {
+        let size = args.size.unwrap_or(DEFAULT_SIZE);
+        // a seed is always picked so the render can be repeated later
+        let seed = args.seed.unwrap_or_else(rand::random);
+
+        let instance = wgpu::Instance::default();
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                force_fallback_adapter: false,
+                compatible_surface: None,
+            })
+            .await
+            .ok_or(anyhow::anyhow!("Adapter request failed"))?;
+        let (device, queue) = request_device(&adapter).await?;
+        let error_log = ErrorLog::new();
+        error_log.capture_uncaptured(&device);
+
+        let characters_png = assets::load("characters.png").await?;
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let mut scene = Scene::new(&device, &queue, &characters_png, size.0, size.1, seed)?;
+        if let Some(hour) = args.theme_hour() {
+            scene.set_theme(&queue, &Theme::at_hour(hour));
+        }
+        let target = OffscreenTarget::new(&device, size.0, size.1);
+        let watermark = args.has_watermark().then(|| {
+            TextOverlay::with_style(size.into(), Corner::BottomRight, [255, 255, 255, 220])
+        });
+        error_log.pop_scope(&device, "Creating resources");
+        let timeline = args.timeline.as_deref().map(Timeline::load).transpose()?;
+        let input = args
+            .play_input
+            .as_deref()
+            .map(InputPlayback::load)
+            .transpose()?;
+
+        Ok(Self {
+            device,
+            queue,
+            error_log,
+            scene,
+            target,
+            watermark,
+            timeline,
+            input,
+            size,
+            seed,
+        })
+    }
+
+    fn update(&mut self, dt: f64) {
+        if let Some(input) = &mut self.input {
+            for event in input.take_until(self.scene.t) {
+                match event {
+                    InputEvent::Action(Action::ToggleFire) => {
+                        self.scene.draw_fire = !self.scene.draw_fire;
+                    }
+                    InputEvent::Action(Action::ToggleCharacters) => {
+                        self.scene.draw_characters = !self.scene.draw_characters;
+                    }
+                    InputEvent::Action(Action::TogglePostprocess) => {
+                        self.scene.draw_postprocess = !self.scene.draw_postprocess;
+                    }
+                    InputEvent::Mouse(pos) => self.scene.mouse_pos = pos,
+                    // the rest act on the window or the machine, not on the picture
+                    InputEvent::Action(_) => {}
+                }
+            }
+        }
+        if let Some(timeline) = &mut self.timeline {
+            let t = self.scene.t + dt;
+            timeline.apply(&mut self.scene, t);
+        }
+        self.scene.update(&self.queue, dt);
+    }
+
+    /// Change the text stamped on rendered frames, if there's a watermark.
+    fn set_watermark(&mut self, text: &str) {
+        if let Some(watermark) = &mut self.watermark {
+            watermark.set_text(
+                &self.device,
+                &self.queue,
+                &self.scene.tex_pl,
+                &self.scene.nearest_sampler,
+                text,
+            );
+        }
+    }
+
+    fn render(&self) -> anyhow::Result<image::RgbaImage> {
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let frame = self.target.render(
+            &self.scene,
+            self.watermark.as_ref(),
+            &self.device,
+            &self.queue,
+        );
+        self.error_log.pop_scope(&self.device, "Rendering");
+        frame
+    }
+
+    // a reproducible render with errors in it isn't worth much
+    fn check_errors(&self) -> anyhow::Result<()> {
+        if self.error_log.take_changed().is_some() {
+            anyhow::bail!("There were errors during rendering");
+        }
+        Ok(())
+    }
+}
+
+/// A texture to render into in place of a window, with a buffer to read it back from.
+struct OffscreenTarget {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    capture: FrameCapture,
+}
+
+impl OffscreenTarget {
+    fn new(device: &wgpu::Device, width: u32, height: u32) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("offscreen target"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: SWAPCHAIN_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self {
+            texture,
+            view,
+            capture: FrameCapture::new(device, SWAPCHAIN_FORMAT, width, height),
+        }
+    }
+
+    /// Render a frame with an optional overlay on top and wait for it to be read back.
+    fn render(
+        &self,
+        scene: &Scene,
+        overlay: Option<&TextOverlay>,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> anyhow::Result<image::RgbaImage> {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("offscreen frame"),
+        });
+        scene.render(&mut encoder, &self.view);
+        if let Some(overlay) = overlay.filter(|o| !o.is_empty()) {
+            let mut overlay_pass = scene.begin_pass_on_top(&mut encoder, &self.view);
+            overlay.draw(&mut overlay_pass);
+        }
+        self.capture.copy_from(&mut encoder, &self.texture);
+        queue.submit(Some(encoder.finish()));
+        self.capture.read(device)
+    }
+}
@@ -0,0 +1,86 @@
+//! Things the viewer can do, and the keys they're bound to.
+
+use winit::event::{ModifiersState, VirtualKeyCode};
+
+#[cfg(not(target_arch = "wasm32"))]
+use super::capture::CaptureTarget;
+
+/// Something the viewer can do from the keyboard or, on native, a gamepad.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    ToggleFire,
+    ToggleCharacters,
+    TogglePostprocess,
+    /// Render a high resolution still.
+    #[cfg(not(target_arch = "wasm32"))]
+    SaveStill,
+    #[cfg(not(target_arch = "wasm32"))]
+    Capture(CaptureTarget),
+    /// Start or stop recording a png sequence.
+    #[cfg(not(target_arch = "wasm32"))]
+    ToggleRecording,
+    /// Jump back in the soundtrack.
+    #[cfg(not(target_arch = "wasm32"))]
+    SeekBackward,
+    #[cfg(not(target_arch = "wasm32"))]
+    SeekForward,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Action {
+    /// Names used for actions in text, e.g. by remote control and input recordings.
+    const NAMES: [(&'static str, Action); 10] = [
+        ("quit", Action::Quit),
+        ("fire", Action::ToggleFire),
+        ("characters", Action::ToggleCharacters),
+        ("postprocess", Action::TogglePostprocess),
+        ("still", Action::SaveStill),
+        ("capture", Action::Capture(CaptureTarget::File)),
+        ("copy", Action::Capture(CaptureTarget::Clipboard)),
+        ("record", Action::ToggleRecording),
+        ("back", Action::SeekBackward),
+        ("forward", Action::SeekForward),
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|&(_, action)| action)
+    }
+
+    pub fn name(self) -> &'static str {
+        Self::NAMES
+            .iter()
+            .find(|(_, a)| *a == self)
+            .map(|&(name, _)| name)
+            .expect("every action has a name")
+    }
+}
+
+/// The action bound to a key, if any.
+// modifiers only matter for keys that don't exist on the web
+#[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+pub fn key_action(key: VirtualKeyCode, modifiers: ModifiersState) -> Option<Action> {
+    use VirtualKeyCode::*;
+    Some(match key {
+        Q => Action::Quit,
+        F => Action::ToggleFire,
+        #[cfg(not(target_arch = "wasm32"))]
+        S if (modifiers.ctrl() || modifiers.logo()) && modifiers.shift() => Action::SaveStill,
+        #[cfg(not(target_arch = "wasm32"))]
+        S if modifiers.ctrl() || modifiers.logo() => Action::Capture(CaptureTarget::File),
+        #[cfg(not(target_arch = "wasm32"))]
+        C if modifiers.ctrl() || modifiers.logo() => Action::Capture(CaptureTarget::Clipboard),
+        #[cfg(not(target_arch = "wasm32"))]
+        R => Action::ToggleRecording,
+        #[cfg(not(target_arch = "wasm32"))]
+        Left => Action::SeekBackward,
+        #[cfg(not(target_arch = "wasm32"))]
+        Right => Action::SeekForward,
+        C => Action::ToggleCharacters,
+        P => Action::TogglePostprocess,
+        _ => return None,
+    })
+}
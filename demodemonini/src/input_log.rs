@@ -0,0 +1,113 @@
+//! Recording what the viewer does and playing it back later,
+//! so an interactive performance can be rendered again exactly
+//! or compared between versions of the demo.
+//!
+//! Recordings are text with one event per line: the scene time in seconds
+//! followed by an action name, e.g. `2.5 fire`, or the mouse position in clip space,
+//! e.g. `3.1 mouse 0.25 -0.5`, or `3.4 mouse none` when it left the window.
+//! Played back with the same seed at a fixed time step, e.g. in headless mode,
+//! the result is the same every time.
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use super::input::Action;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputEvent {
+    Action(Action),
+    Mouse(Option<[f32; 2]>),
+}
+
+/// Writes input events into a file as they happen.
+pub struct InputRecorder {
+    file: BufWriter<File>,
+    last_mouse: Option<[f32; 2]>,
+}
+
+impl InputRecorder {
+    pub fn create(path: &Path) -> anyhow::Result<Self> {
+        let file = BufWriter::new(File::create(path)?);
+        println!("Recording input to {}", path.display());
+        Ok(Self {
+            file,
+            last_mouse: None,
+        })
+    }
+
+    pub fn record_action(&mut self, t: f64, action: Action) -> anyhow::Result<()> {
+        writeln!(self.file, "{t} {}", action.name())?;
+        Ok(())
+    }
+
+    /// Record the mouse position if it's moved since the last call.
+    pub fn record_mouse(&mut self, t: f64, mouse: Option<[f32; 2]>) -> anyhow::Result<()> {
+        if mouse == self.last_mouse {
+            return Ok(());
+        }
+        self.last_mouse = mouse;
+        match mouse {
+            Some([x, y]) => writeln!(self.file, "{t} mouse {x} {y}")?,
+            None => writeln!(self.file, "{t} mouse none")?,
+        }
+        Ok(())
+    }
+}
+
+/// Gives back recorded input events as their time comes.
+pub struct InputPlayback {
+    // sorted by time
+    events: Vec<(f64, InputEvent)>,
+    next_idx: usize,
+}
+
+impl InputPlayback {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let mut events = Vec::new();
+        for (line_idx, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event = parse_line(line).map_err(|err| {
+                anyhow::anyhow!("{}: line {}: {err}", path.display(), line_idx + 1)
+            })?;
+            events.push(event);
+        }
+        events.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(Self {
+            events,
+            next_idx: 0,
+        })
+    }
+
+    /// Take the events that happened at or before time `t` and haven't been taken yet.
+    pub fn take_until(&mut self, t: f64) -> impl Iterator<Item = InputEvent> + '_ {
+        let start = self.next_idx;
+        while self.events.get(self.next_idx).is_some_and(|ev| ev.0 <= t) {
+            self.next_idx += 1;
+        }
+        self.events[start..self.next_idx].iter().map(|ev| ev.1)
+    }
+}
+
+fn parse_line(line: &str) -> anyhow::Result<(f64, InputEvent)> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let [t, name, rest @ ..] = words.as_slice() else {
+        anyhow::bail!("expected a time and an event");
+    };
+    let t: f64 = t.parse()?;
+    let event = match (*name, rest) {
+        ("mouse", ["none"]) => InputEvent::Mouse(None),
+        ("mouse", [x, y]) => InputEvent::Mouse(Some([x.parse()?, y.parse()?])),
+        ("mouse", _) => anyhow::bail!("expected a mouse position or none"),
+        (name, []) => InputEvent::Action(
+            Action::from_name(name).ok_or(anyhow::anyhow!("unknown action {name:?}"))?,
+        ),
+        _ => anyhow::bail!("unexpected values after {name:?}"),
+    };
+    Ok((t, event))
+}
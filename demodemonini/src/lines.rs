@@ -0,0 +1,99 @@
+//! Turning polylines into triangles for [`LinePipeline`][super::pipelines::LinePipeline].
+
+use super::pipelines::LineVertex;
+
+// miters at sharp corners are cut short so they don't stick out
+// further than this many times the line's half width
+const MITER_LIMIT: f32 = 4.;
+
+/// A point on a polyline, with the line's properties at that point.
+/// Width and color are interpolated between points.
+#[derive(Clone, Copy, Debug)]
+pub struct LinePoint {
+    /// Position in clip space.
+    pub pos: [f32; 2],
+    /// Thickness of the line in clip space units.
+    pub width: f32,
+    pub color: [f32; 4],
+}
+
+/// Tessellate a polyline into a triangle list, appending the vertices to `out`.
+///
+/// Corners are joined with miters, shortened if they're very sharp.
+/// Lines with less than two points produce nothing.
+pub fn tessellate_polyline(points: &[LinePoint], depth: f32, out: &mut Vec<LineVertex>) {
+    if points.len() < 2 {
+        return;
+    }
+
+    // left and right side of the line at every point
+    let mut sides: Vec<([f32; 2], [f32; 2])> = Vec::with_capacity(points.len());
+    let mut prev_offset = [0., 0.];
+    for (idx, point) in points.iter().enumerate() {
+        let dir_in = idx
+            .checked_sub(1)
+            .and_then(|prev| direction(points[prev].pos, point.pos));
+        let dir_out = points
+            .get(idx + 1)
+            .and_then(|next| direction(point.pos, next.pos));
+        let half_width = point.width / 2.;
+
+        let offset = match (dir_in, dir_out) {
+            (Some(a), Some(b)) => {
+                let tangent = normalize([a[0] + b[0], a[1] + b[1]]).unwrap_or(a);
+                let miter = perp(tangent);
+                // how much longer the miter is than the line is wide
+                let stretch = 1. / dot(miter, perp(a)).max(1. / MITER_LIMIT);
+                scale(miter, half_width * stretch)
+            }
+            (Some(dir), None) | (None, Some(dir)) => scale(perp(dir), half_width),
+            // repeated points, no direction to go from
+            (None, None) => prev_offset,
+        };
+        prev_offset = offset;
+        let [x, y] = point.pos;
+        sides.push((
+            [x + offset[0], y + offset[1]],
+            [x - offset[0], y - offset[1]],
+        ));
+    }
+
+    let vert = |pos: [f32; 2], point: &LinePoint, edge: f32| LineVertex {
+        pos: [pos[0], pos[1], depth],
+        col: point.color,
+        edge,
+    };
+    for (idx, pair) in points.windows(2).enumerate() {
+        let (l0, r0) = sides[idx];
+        let (l1, r1) = sides[idx + 1];
+        out.extend([
+            vert(l0, &pair[0], -1.),
+            vert(r0, &pair[0], 1.),
+            vert(r1, &pair[1], 1.),
+            vert(l0, &pair[0], -1.),
+            vert(r1, &pair[1], 1.),
+            vert(l1, &pair[1], -1.),
+        ]);
+    }
+}
+
+fn direction(from: [f32; 2], to: [f32; 2]) -> Option<[f32; 2]> {
+    normalize([to[0] - from[0], to[1] - from[1]])
+}
+
+fn normalize(v: [f32; 2]) -> Option<[f32; 2]> {
+    let len = dot(v, v).sqrt();
+    (len > f32::EPSILON).then(|| scale(v, 1. / len))
+}
+
+fn perp(v: [f32; 2]) -> [f32; 2] {
+    [-v[1], v[0]]
+}
+
+fn dot(a: [f32; 2], b: [f32; 2]) -> f32 {
+    a[0] * b[0] + a[1] * b[1]
+}
+
+fn scale(v: [f32; 2], s: f32) -> [f32; 2] {
+    [v[0] * s, v[1] * s]
+}
@@ -1,22 +1,104 @@
 use instant::Instant;
 
-use wgpu::util::DeviceExt;
 use winit::{
-    event::{Event, VirtualKeyCode, WindowEvent},
+    event::{Event, WindowEvent},
     event_loop::EventLoop,
     window::WindowBuilder,
 };
 
+#[cfg(target_arch = "wasm32")]
+use winit::platform::web::WindowExtWebSys;
+
 //
 
+mod assets;
+mod fullscreen;
 mod pipelines;
-use pipelines::{load_png_texture, PostprocessPipeline, TexturePipeline, VertexColorPipeline};
+mod textures;
 
-mod fire;
-use fire::Fire;
+mod atlas;
 
+mod embers;
+mod fire;
+mod lines;
+mod parallax;
 mod triangle_grid;
-use triangle_grid::TriangleGrid;
+
+mod scene;
+use scene::Scene;
+
+mod preprocess;
+mod shaders;
+#[cfg(not(target_arch = "wasm32"))]
+use shaders::{Shader, ShaderWatcher};
+
+mod text;
+#[cfg(not(target_arch = "wasm32"))]
+use text::Corner;
+use text::TextOverlay;
+
+mod errors;
+use errors::ErrorLog;
+
+mod input;
+use input::Action;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod capture;
+#[cfg(not(target_arch = "wasm32"))]
+use capture::{CaptureTarget, FrameCapture, Recorder, SequenceRecorder, VideoRecorder};
+#[cfg(not(target_arch = "wasm32"))]
+use input_log::{InputEvent, InputPlayback, InputRecorder};
+
+#[cfg(target_arch = "wasm32")]
+mod web_args;
+#[cfg(target_arch = "wasm32")]
+mod web_fullscreen;
+#[cfg(target_arch = "wasm32")]
+mod web_layout;
+#[cfg(target_arch = "wasm32")]
+mod web_loading;
+#[cfg(target_arch = "wasm32")]
+mod web_quality;
+#[cfg(all(target_arch = "wasm32", feature = "recording"))]
+mod web_recording;
+#[cfg(target_arch = "wasm32")]
+mod web_storage;
+#[cfg(target_arch = "wasm32")]
+mod web_visibility;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod args;
+#[cfg(not(target_arch = "wasm32"))]
+mod audio;
+#[cfg(not(target_arch = "wasm32"))]
+mod bench;
+#[cfg(not(target_arch = "wasm32"))]
+mod chat;
+#[cfg(not(target_arch = "wasm32"))]
+mod feed;
+#[cfg(not(target_arch = "wasm32"))]
+mod gamepad;
+#[cfg(not(target_arch = "wasm32"))]
+mod headless;
+#[cfg(not(target_arch = "wasm32"))]
+mod input_log;
+#[cfg(not(target_arch = "wasm32"))]
+mod ndi;
+#[cfg(not(target_arch = "wasm32"))]
+mod remote;
+#[cfg(not(target_arch = "wasm32"))]
+mod soundtrack;
+#[cfg(not(target_arch = "wasm32"))]
+mod theme;
+#[cfg(not(target_arch = "wasm32"))]
+mod timeline;
+#[cfg(not(target_arch = "wasm32"))]
+mod video;
+#[cfg(not(target_arch = "wasm32"))]
+mod wallpaper;
+#[cfg(not(target_arch = "wasm32"))]
+mod webcam;
 
 // constants for quick globally accessible configuration
 
@@ -33,9 +115,41 @@ const MULTISAMPLE_STATE: wgpu::MultisampleState = wgpu::MultisampleState {
     alpha_to_coverage_enabled: false,
 };
 
+// depth buffer for explicit draw order, set to None to just draw in order of draw calls
+const DEPTH_FORMAT: Option<wgpu::TextureFormat> = Some(wgpu::TextureFormat::Depth32Float);
+fn depth_stencil_state() -> Option<wgpu::DepthStencilState> {
+    DEPTH_FORMAT.map(|format| wgpu::DepthStencilState {
+        format,
+        depth_write_enabled: true,
+        // equal so that later draws at the same depth still go on top
+        depth_compare: wgpu::CompareFunction::LessEqual,
+        stencil: wgpu::StencilState::default(),
+        bias: wgpu::DepthBiasState::default(),
+    })
+}
+
+// how far the soundtrack jumps when seeking, in seconds
+#[cfg(not(target_arch = "wasm32"))]
+const SEEK_STEP: f64 = 5.;
+// longer gaps between frames than this are taken as the soundtrack being seeked
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_FRAME_TIME: f64 = 0.25;
+// brightness of the fire's glow at silence and how much a full signal adds to it
+#[cfg(not(target_arch = "wasm32"))]
+const AUDIO_GLOW_MIN: f32 = 0.5;
+#[cfg(not(target_arch = "wasm32"))]
+const AUDIO_GLOW_RANGE: f32 = 2.;
+
 #[cfg(not(target_arch = "wasm32"))]
 fn main() -> anyhow::Result<()> {
-    futures::executor::block_on(main_async())
+    let args = args::Args::parse()?;
+    if let Some(t) = args.frame_at {
+        futures::executor::block_on(headless::render_frame_at(&args, t))
+    } else if args.headless {
+        futures::executor::block_on(headless::render(&args))
+    } else {
+        futures::executor::block_on(main_async(args))
+    }
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -48,36 +162,109 @@ async fn main_async_unwrap() {
     main_async().await.unwrap();
 }
 
-async fn main_async() -> anyhow::Result<()> {
+async fn main_async(#[cfg(not(target_arch = "wasm32"))] args: args::Args) -> anyhow::Result<()> {
     //
     // winit & wgpu setup
     //
 
     let event_loop = EventLoop::new();
 
-    let window = WindowBuilder::new()
+    let window_builder = WindowBuilder::new()
         .with_title("demodemonini")
         .with_inner_size(winit::dpi::LogicalSize {
             width: 1080 * 4 / 3,
             height: 1080,
-        })
-        .build(&event_loop)?;
+        });
+    // exact sizes are for recordings, which can't change size in the middle
+    #[cfg(not(target_arch = "wasm32"))]
+    let window_builder = match args.size {
+        Some((width, height)) => window_builder
+            .with_inner_size(winit::dpi::PhysicalSize { width, height })
+            .with_resizable(false),
+        None => window_builder,
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    let window_builder = if args.wallpaper {
+        wallpaper::window_builder(window_builder, &event_loop)?
+    } else {
+        window_builder
+    };
+    let window = window_builder.build(&event_loop)?;
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut wallpaper = args
+        .wallpaper
+        .then(|| wallpaper::Wallpaper::attach(&window))
+        .transpose()?;
     #[cfg(target_arch = "wasm32")]
-    {
+    let canvas_parent = {
         std::panic::set_hook(Box::new(console_error_panic_hook::hook));
         console_log::init().expect("Failed to initialize console logger");
-        use winit::platform::web::WindowExtWebSys;
+        #[cfg(feature = "recording")]
+        web_recording::set_canvas(window.canvas());
+        // inline elements leave a gap under them that would make the parent grow
+        let _ = window.canvas().style().set_property("display", "block");
         let canvas = web_sys::Element::from(window.canvas());
-        web_sys::window()
+        let document = web_sys::window()
             .and_then(|win| win.document())
-            // made-up convention for putting the game in a specific spot in the DOM
-            .and_then(|doc| match doc.get_element_by_id("wgpu-canvas") {
-                Some(parent) => parent.append_child(&canvas).ok(),
-                None => doc.body().and_then(|body| body.append_child(&canvas).ok()),
-            })
-            .expect("couldn't append canvas to document body")
+            .expect("couldn't get the document");
+        // made-up convention for putting the game in a specific spot in the DOM
+        match document.get_element_by_id("wgpu-canvas") {
+            Some(parent) => {
+                parent
+                    .append_child(&canvas)
+                    .expect("couldn't append canvas to #wgpu-canvas");
+                Some(parent)
+            }
+            None => {
+                document
+                    .body()
+                    .and_then(|body| body.append_child(&canvas).ok())
+                    .expect("couldn't append canvas to document body");
+                None
+            }
+        }
     };
+    // parsed after setting up logging so invalid options can be reported
+    #[cfg(target_arch = "wasm32")]
+    let web_args = web_args::WebArgs::parse();
+    // fill the spot given in the page layout
+    #[cfg(target_arch = "wasm32")]
+    let parent_observer = canvas_parent
+        .as_ref()
+        .map(web_layout::ParentSizeObserver::new)
+        .transpose()
+        .map_err(|err| anyhow::anyhow!("Failed to observe canvas size: {err:?}"))?;
+    #[cfg(target_arch = "wasm32")]
+    let mut fullscreen = web_fullscreen::Fullscreen::new(window.canvas())
+        .map_err(|err| anyhow::anyhow!("Failed to set up fullscreen: {err:?}"))?;
+    // shown until everything is ready to draw
+    #[cfg(target_arch = "wasm32")]
+    let loading = window.canvas().parent_element().and_then(|parent| {
+        web_loading::LoadingIndicator::show(&parent)
+            .map_err(|err| log::warn!("Failed to show loading indicator: {err:?}"))
+            .ok()
+    });
+    #[cfg(target_arch = "wasm32")]
+    let set_loading_status = |text: &str| {
+        if let Some(loading) = &loading {
+            loading.set_status(text);
+        }
+    };
+
+    // a WebGPU build can't fall back to WebGL by itself,
+    // so fail with a clear message that the page can react to by loading the WebGL build
+    #[cfg(all(target_arch = "wasm32", not(feature = "webgl")))]
+    {
+        let has_webgpu = web_sys::window().map_or(false, |win| {
+            js_sys::Reflect::has(&win.navigator(), &"gpu".into()).unwrap_or(false)
+        });
+        if !has_webgpu {
+            anyhow::bail!("This browser doesn't support WebGPU, use the WebGL build instead");
+        }
+    }
 
+    #[cfg(target_arch = "wasm32")]
+    set_loading_status("Starting up the GPU");
     let instance = wgpu::Instance::default();
     let surface = unsafe { instance.create_surface(&window)? };
 
@@ -90,275 +277,740 @@ async fn main_async() -> anyhow::Result<()> {
         .await
         .ok_or(anyhow::anyhow!("Adapter request failed"))?;
 
-    #[cfg(not(target_arch = "wasm32"))]
-    let limits = wgpu::Limits::default();
-    #[cfg(target_arch = "wasm32")]
-    let limits = wgpu::Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits());
+    let (device, queue) = request_device(&adapter).await?;
 
-    let (device, queue) = adapter
-        .request_device(
-            &wgpu::DeviceDescriptor {
-                features: wgpu::Features::empty(),
-                limits,
-                label: None,
-            },
-            None,
-        )
-        .await
-        // for some reason this map is needed on wasm
-        .map_err(|e| anyhow::anyhow!("Failed to get device: {e}"))?;
+    // log errors and show them on screen instead of crashing
+    let error_log = ErrorLog::new();
+    error_log.capture_uncaptured(&device);
 
+    #[cfg(not(target_arch = "wasm32"))]
     let initial_window_size = window.inner_size();
+    // on the web, the resolution is picked here on top of winit's sizing
+    #[cfg(target_arch = "wasm32")]
+    let mut pixel_ratio_cap = web_layout::PixelRatioCap::new(
+        canvas_parent.as_ref(),
+        web_args.scale,
+        device.limits().max_texture_dimension_2d,
+    );
+    // returning viewers start from the resolution that worked for them last time
+    #[cfg(target_arch = "wasm32")]
+    let mut auto_quality = web_args
+        .auto_quality
+        .then(|| web_quality::AutoQuality::starting_at(web_args.quality.unwrap_or(1.)));
+    #[cfg(target_arch = "wasm32")]
+    if let Some(auto_quality) = &auto_quality {
+        pixel_ratio_cap.set_quality(auto_quality.scale());
+    }
+    #[cfg(target_arch = "wasm32")]
+    let initial_window_size = pixel_ratio_cap.apply(&window.canvas());
 
     let swapchain_capabilities = surface.get_capabilities(&adapter);
 
+    // benchmarks measure how fast frames can be drawn, not the display's refresh rate
+    #[cfg(not(target_arch = "wasm32"))]
+    let present_mode = if args.bench.is_some() {
+        wgpu::PresentMode::AutoNoVsync
+    } else {
+        wgpu::PresentMode::AutoVsync
+    };
+    #[cfg(target_arch = "wasm32")]
+    let present_mode = wgpu::PresentMode::AutoVsync;
+
+    // WebGPU canvases can't be sRGB, only viewed as sRGB
+    let (surface_format, view_formats) =
+        if swapchain_capabilities.formats.contains(&SWAPCHAIN_FORMAT) {
+            (SWAPCHAIN_FORMAT, vec![])
+        } else {
+            (
+                SWAPCHAIN_FORMAT.remove_srgb_suffix(),
+                vec![SWAPCHAIN_FORMAT],
+            )
+        };
+
     let mut surface_config = wgpu::SurfaceConfiguration {
-        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-        format: SWAPCHAIN_FORMAT,
+        // copying out of the swapchain is needed to capture frames, if it's possible
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+            | (swapchain_capabilities.usages & wgpu::TextureUsages::COPY_SRC),
+        format: surface_format,
         width: initial_window_size.width,
         height: initial_window_size.height,
-        present_mode: wgpu::PresentMode::AutoVsync,
+        present_mode,
         alpha_mode: swapchain_capabilities.alpha_modes[0],
-        view_formats: vec![],
+        view_formats,
     };
     surface.configure(&device, &surface_config);
 
-    fn create_screen_texture(
-        device: &wgpu::Device,
-        window_size: winit::dpi::PhysicalSize<u32>,
-    ) -> wgpu::Texture {
-        device.create_texture(&wgpu::TextureDescriptor {
-            label: None,
-            size: wgpu::Extent3d {
-                width: window_size.width,
-                height: window_size.height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: SWAPCHAIN_FORMAT,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT,
-            view_formats: &[],
-        })
-    }
-
-    // main image is draw into a gbuffer for postprocessing
-    let mut gbuffer = create_screen_texture(&device, initial_window_size);
-
     //
     // pipelines and textures
     //
 
-    let color_pl = VertexColorPipeline::new(&device);
-    let mut background_grid = TriangleGrid::generate(&device);
-
-    let tex_pl = TexturePipeline::new(&device);
-    let characters_tex = load_png_texture(&device, &queue, include_bytes!("../characters.png"))?;
-    let characters_tex_view = characters_tex.create_view(&wgpu::TextureViewDescriptor::default());
-    let filtering_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-        mag_filter: wgpu::FilterMode::Linear,
-        min_filter: wgpu::FilterMode::Linear,
-        ..Default::default()
-    });
-    let characters_bind_group =
-        tex_pl.create_bind_group(&device, &characters_tex_view, &filtering_sampler);
-
-    // fullscreen quad for the main image
-    let characters_verts = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: None,
-        contents: bytemuck::cast_slice(&[
-            // position    tex_coords
-            [[-1f32, -1.], [0., 1.]],
-            [[1., -1.], [1., 1.]],
-            [[1., 1.], [1., 0.]],
-            [[-1., -1.], [0., 1.]],
-            [[1., 1.], [1., 0.]],
-            [[-1., 1.], [0., 0.]],
-        ]),
-        usage: wgpu::BufferUsages::VERTEX,
-    });
+    #[cfg(not(target_arch = "wasm32"))]
+    let seed = args.seed.unwrap_or_else(rand::random);
+    #[cfg(target_arch = "wasm32")]
+    let seed = web_args.seed.unwrap_or_else(rand::random);
 
-    let mut fire = Fire::new(250, 150, 1. / 120.);
-    let fire_tex = fire.create_texture(&device);
-    let fire_tex_view = fire_tex.create_view(&wgpu::TextureViewDescriptor::default());
-    let nearest_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-        mag_filter: wgpu::FilterMode::Nearest,
-        min_filter: wgpu::FilterMode::Nearest,
-        ..Default::default()
-    });
-    let fire_bind_group = tex_pl.create_bind_group(&device, &fire_tex_view, &nearest_sampler);
-
-    // rectangular quad for the fire
-    let fire_base_y = -0.5;
-    // height that makes square pixels at 4:3 aspect ratio
-    let fire_height = (2. / fire.width as f32) * fire.height as f32 * 4. / 3.;
-    let fire_top_y = fire_base_y + fire_height;
-    let fire_verts = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: None,
-        contents: bytemuck::cast_slice(&[
-            // position         tex_coords
-            [[-1., fire_base_y], [0., 1.]],
-            [[1., fire_base_y], [1., 1.]],
-            [[1., fire_top_y], [1., 0.]],
-            [[-1., fire_base_y], [0., 1.]],
-            [[1., fire_top_y], [1., 0.]],
-            [[-1., fire_top_y], [0., 0.]],
-        ]),
-        usage: wgpu::BufferUsages::VERTEX,
-    });
+    #[cfg(target_arch = "wasm32")]
+    set_loading_status("Loading images");
+    // fetched on the web, which can take a while on a slow connection
+    let characters_png = assets::load("characters.png").await?;
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let mut scene = Scene::new(
+        &device,
+        &queue,
+        &characters_png,
+        initial_window_size.width,
+        initial_window_size.height,
+        seed,
+    )?;
+    error_log.pop_scope(&device, "Creating resources");
+    #[cfg(target_arch = "wasm32")]
+    drop(loading);
+    #[cfg(target_arch = "wasm32")]
+    {
+        scene.draw_fire = web_args.fire;
+        scene.draw_characters = web_args.characters;
+        scene.draw_postprocess = web_args.postprocess;
+    }
 
-    // reflection squished to look in perspective and smoothed by a filtering sampler
-    let fire_reflection_bind_group =
-        tex_pl.create_bind_group(&device, &fire_tex_view, &filtering_sampler);
-
-    let refl_bottom_y = fire_base_y - 0.4 * fire_height;
-    let fire_reflection_verts = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: None,
-        contents: bytemuck::cast_slice(&[
-            // position         tex_coords
-            [[-1., fire_base_y], [0., 1.]],
-            [[1., fire_base_y], [1., 1.]],
-            [[1., refl_bottom_y], [1., 0.]],
-            [[-1., fire_base_y], [0., 1.]],
-            [[1., refl_bottom_y], [1., 0.]],
-            [[-1., refl_bottom_y], [0., 0.]],
-        ]),
-        usage: wgpu::BufferUsages::VERTEX,
+    // on-screen messages, e.g. shader compilation errors
+    let mut overlay = TextOverlay::new(initial_window_size);
+    // signature and timecode stamped on recordings
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut watermark = args.has_watermark().then(|| {
+        TextOverlay::with_style(
+            initial_window_size,
+            Corner::BottomRight,
+            [255, 255, 255, 220],
+        )
     });
 
-    let fire_dt = 1. / 20.;
-
-    let postprocess_pl = PostprocessPipeline::new(&device);
+    // recompile shaders when they're edited
+    #[cfg(not(target_arch = "wasm32"))]
+    let shader_watcher = ShaderWatcher::new();
+    // reload the painting when it's exported again
+    #[cfg(not(target_arch = "wasm32"))]
+    let asset_watcher = assets::AssetWatcher::new();
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut shader_errors: std::collections::BTreeMap<Shader, String> = Default::default();
+    // the latest of everything in the error log, kept to show alongside shader errors
+    let mut logged_errors: Vec<String> = Vec::new();
 
     //
     // run event loop
     //
 
-    // interactive controls to toggle parts of the picture, just for fun
-    let mut draw_characters = true;
-    let mut draw_fire = true;
-    let mut draw_postprocess = true;
+    let mut modifiers = winit::event::ModifiersState::empty();
+    // input is collected from events and gamepads and acted on at the start of the next frame
+    let mut pending_actions: Vec<Action> = Vec::new();
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut gamepads = gamepad::Gamepads::new();
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut input_recorder = args
+        .record_input
+        .as_deref()
+        .map(InputRecorder::create)
+        .transpose()?;
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut input_playback = args
+        .play_input
+        .as_deref()
+        .map(InputPlayback::load)
+        .transpose()?;
+    #[cfg(not(target_arch = "wasm32"))]
+    let chat = args
+        .twitch
+        .as_deref()
+        .map(|channel| chat::ChatControl::join(channel, args.chat_commands.as_deref()))
+        .transpose()?;
+    #[cfg(not(target_arch = "wasm32"))]
+    let remote = args
+        .remote
+        .as_deref()
+        .map(remote::RemoteControl::start)
+        .transpose()?;
 
-    // frame timing for the fire simulation
+    // frame captures are requested with keys and done at the end of the next frame
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut pending_capture: Option<CaptureTarget> = None;
+    // the buffer frames are captured into, kept between frames until the surface changes
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut frame_capture: Option<FrameCapture> = None;
+    // created on first use and kept alive so the copied image stays on the clipboard
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut clipboard: Option<arboard::Clipboard> = None;
+    // every frame is saved while this is running
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut recorder: Option<Recorder> = match &args.record {
+        Some(path) => {
+            if !surface_config.usage.contains(wgpu::TextureUsages::COPY_SRC) {
+                anyhow::bail!("Can't record, the surface doesn't support copying from it");
+            }
+            let video =
+                VideoRecorder::new(path, surface_config.width, surface_config.height, args.fps)?;
+            println!("Recording to {}", path.display());
+            Some(Recorder::Video(video))
+        }
+        None => None,
+    };
+    // every frame is also sent over the network while this exists
+    #[cfg(not(target_arch = "wasm32"))]
+    let ndi = match &args.ndi {
+        Some(name) => {
+            if !surface_config.usage.contains(wgpu::TextureUsages::COPY_SRC) {
+                anyhow::bail!("Can't send over NDI, the surface doesn't support copying from it");
+            }
+            Some(ndi::NdiSender::new(name, args.fps)?)
+        }
+        None => None,
+    };
+
+    // time-driven effects start from --start along with the music
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut soundtrack = match &args.music {
+        Some(path) => Some(soundtrack::Soundtrack::play(path.clone(), args.start)?),
+        None => None,
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        scene.t = args.start;
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(path) = &args.video {
+        scene.add_video(&device, path, args.video_placement)?;
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    let webcam = args.camera.map(webcam::Webcam::open).transpose()?;
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut audio = match args.audio {
+        Some(signal) => Some((audio::AudioInput::start(&args.audio_source)?, signal)),
+        None => None,
+    };
+    // outside data driving parameters of the picture
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut signal_sources: Vec<Box<dyn feed::SignalSource>> = Vec::new();
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(path) = &args.feed {
+        signal_sources.push(Box::new(feed::HttpFeed::start(path)?));
+    }
+    // colors picked by a feed take over from the clock
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut feed_hour: Option<f32> = None;
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut timeline = args
+        .timeline
+        .as_deref()
+        .map(timeline::Timeline::load)
+        .transpose()?;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut bench = args.bench.map(|frames| {
+        scene.measure_fire_steps();
+        bench::Bench::new(
+            frames,
+            (surface_config.width, surface_config.height),
+            adapter.get_info(),
+        )
+    });
+
+    // stop drawing in background tabs
+    #[cfg(target_arch = "wasm32")]
+    let page_visibility = web_visibility::PageVisibility::new(event_loop.create_proxy())
+        .map_err(|err| anyhow::anyhow!("Failed to watch page visibility: {err:?}"))?;
+    #[cfg(target_arch = "wasm32")]
+    let mut paused = false;
+
+    let mut resize_pending = false;
+
+    // frame timing for the simulations
     let mut frame_start_t = Instant::now();
-    let mut time_in_frame = 0.;
-    // global time for time-dependent effects
-    let start_t = Instant::now();
     event_loop.run(move |event, _, control_flow| {
         control_flow.set_poll();
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(wallpaper) = &wallpaper {
+            control_flow.set_wait_until(wallpaper.next_frame());
+        }
         match event {
             //
             // render loop
             //
             Event::MainEventsCleared => {
-                // simulate fire
-
-                let since_last_draw = frame_start_t.elapsed().as_secs_f64();
-                time_in_frame += since_last_draw;
-                let mut fire_updated = false;
-                // limit maximum steps per frame to avoid spiral of death
-                for _ in 0..4 {
-                    if time_in_frame < fire_dt {
-                        break;
+                // wait for the page to be shown again without drawing anything,
+                // then carry on from where we left off instead of catching up
+                #[cfg(target_arch = "wasm32")]
+                if page_visibility.is_hidden() {
+                    paused = true;
+                    control_flow.set_wait();
+                    return;
+                } else if std::mem::take(&mut paused) {
+                    frame_start_t = Instant::now();
+                    if let Some(auto_quality) = &mut auto_quality {
+                        auto_quality.restart_window();
                     }
-                    fire.propagate();
-                    fire_updated = true;
-                    time_in_frame -= fire_dt;
                 }
 
-                frame_start_t = Instant::now();
+                // wallpapers skip frames to keep their rate down
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(wallpaper) = &mut wallpaper {
+                    if !wallpaper.frame_due() {
+                        return;
+                    }
+                }
 
-                // setup
+                // act on input
 
-                let surface_tex = surface
-                    .get_current_texture()
-                    .expect("Failed to get swapchain texture");
-                let surface_view = surface_tex
-                    .texture
-                    .create_view(&wgpu::TextureViewDescriptor::default());
-                let gbuf_view = gbuffer.create_view(&wgpu::TextureViewDescriptor::default());
-                let gbuf_bind_group =
-                    postprocess_pl.create_bind_group(&device, &gbuf_view, &filtering_sampler);
-                let mut encoder =
-                    device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
-
-                let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                        view: if draw_postprocess {
-                            &gbuf_view
-                        } else {
-                            &surface_view
-                        },
-                        resolve_target: None,
-                        ops: wgpu::Operations {
-                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                            store: wgpu::StoreOp::Store,
-                        },
-                    })],
-                    ..Default::default()
-                });
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(gamepads) = &mut gamepads {
+                    gamepads.poll(&mut pending_actions);
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(remote) = &remote {
+                    remote.poll(&mut pending_actions);
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(chat) = &chat {
+                    chat.poll(&mut pending_actions);
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(playback) = &mut input_playback {
+                    for event in playback.take_until(scene.t) {
+                        match event {
+                            InputEvent::Action(action) => pending_actions.push(action),
+                            InputEvent::Mouse(pos) => scene.mouse_pos = pos,
+                        }
+                    }
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(rec) = &mut input_recorder {
+                    let result = pending_actions
+                        .iter()
+                        .try_for_each(|&action| rec.record_action(scene.t, action))
+                        .and_then(|()| rec.record_mouse(scene.t, scene.mouse_pos));
+                    if let Err(err) = result {
+                        error_log.push("Recording input", err);
+                        input_recorder = None;
+                    }
+                }
+                for action in std::mem::take(&mut pending_actions) {
+                    match action {
+                        Action::Quit => {
+                            control_flow.set_exit();
+                            return;
+                        }
+                        Action::ToggleFire => {
+                            scene.draw_fire = !scene.draw_fire;
+                        }
+                        Action::ToggleCharacters => {
+                            scene.draw_characters = !scene.draw_characters;
+                        }
+                        Action::TogglePostprocess => {
+                            scene.draw_postprocess = !scene.draw_postprocess;
+                        }
+                        #[cfg(not(target_arch = "wasm32"))]
+                        Action::SaveStill => {
+                            device.push_error_scope(wgpu::ErrorFilter::Validation);
+                            let result = headless::render_still(
+                                &mut scene,
+                                &device,
+                                &queue,
+                                (surface_config.width, surface_config.height),
+                                args.still_scale,
+                            );
+                            error_log.pop_scope(&device, "Rendering still");
+                            match result {
+                                Ok(path) => println!("Saved still to {path}"),
+                                Err(err) => error_log.push("Rendering still", err),
+                            }
+                        }
+                        #[cfg(not(target_arch = "wasm32"))]
+                        Action::Capture(target) => {
+                            if surface_config.usage.contains(wgpu::TextureUsages::COPY_SRC) {
+                                pending_capture = Some(target);
+                            } else {
+                                error_log.push(
+                                    "Capturing frame",
+                                    "the surface doesn't support copying from it",
+                                );
+                            }
+                        }
+                        #[cfg(not(target_arch = "wasm32"))]
+                        Action::SeekBackward | Action::SeekForward => {
+                            if let Some(music) = &mut soundtrack {
+                                let step = if action == Action::SeekForward {
+                                    SEEK_STEP
+                                } else {
+                                    -SEEK_STEP
+                                };
+                                if let Err(err) = music.seek(music.time() + step) {
+                                    error_log.push("Seeking", err);
+                                }
+                            }
+                        }
+                        #[cfg(not(target_arch = "wasm32"))]
+                        Action::ToggleRecording => {
+                            if recorder.is_some() {
+                                stop_recording(&mut recorder, &error_log);
+                            } else if !surface_config.usage.contains(wgpu::TextureUsages::COPY_SRC)
+                            {
+                                error_log.push(
+                                    "Recording",
+                                    "the surface doesn't support copying from it",
+                                );
+                            } else {
+                                match SequenceRecorder::new() {
+                                    Ok(rec) => {
+                                        println!("Recording to {}", rec.dir().display());
+                                        recorder = Some(Recorder::Sequence(rec));
+                                    }
+                                    Err(err) => error_log.push("Starting recording", err),
+                                }
+                            }
+                        }
+                    }
+                    // remember the layer toggles for the next visit
+                    #[cfg(target_arch = "wasm32")]
+                    if matches!(
+                        action,
+                        Action::ToggleFire | Action::ToggleCharacters | Action::TogglePostprocess
+                    ) {
+                        let quality = auto_quality.as_ref().map_or(1., |q| q.scale());
+                        web_storage::save(&scene, quality);
+                    }
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(remote) = &remote {
+                    remote.set_state(
+                        scene.draw_fire,
+                        scene.draw_characters,
+                        scene.draw_postprocess,
+                        recorder.is_some(),
+                    );
+                }
 
-                // draw
+                // follow the size of the element the canvas is in,
+                // which gets handled below once the window gets the resize event
+                #[cfg(target_arch = "wasm32")]
+                if let Some(size) = parent_observer.as_ref().and_then(|p| p.take_new_size()) {
+                    window.set_inner_size(size);
+                }
+                #[cfg(target_arch = "wasm32")]
+                if let Some(size) = fullscreen.take_new_size() {
+                    window.set_inner_size(size);
+                }
 
-                let t = start_t.elapsed().as_secs_f32();
-                postprocess_pl.upload_time(&queue, t);
+                // lower the resolution if frames are taking too long
+                #[cfg(target_arch = "wasm32")]
+                if let Some(quality) = auto_quality.as_mut().and_then(|q| q.frame()) {
+                    pixel_ratio_cap.set_quality(quality);
+                    resize_pending = true;
+                    web_storage::save(&scene, quality);
+                }
 
-                if fire_updated {
-                    fire.write_texture(&queue, &fire_tex);
+                // resize here rather than in the event
+                // so that other changes to the resolution can use the same path
+                if std::mem::take(&mut resize_pending) {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    let new_size = window.inner_size();
+                    #[cfg(target_arch = "wasm32")]
+                    let new_size = pixel_ratio_cap.apply(&window.canvas());
+                    surface_config.width = new_size.width;
+                    surface_config.height = new_size.height;
+                    surface.configure(&device, &surface_config);
+                    device.push_error_scope(wgpu::ErrorFilter::Validation);
+                    scene.resize(&device, new_size.width, new_size.height);
+                    overlay.resize(
+                        &device,
+                        &queue,
+                        &scene.tex_pl,
+                        &scene.nearest_sampler,
+                        new_size,
+                    );
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if let Some(watermark) = &mut watermark {
+                        watermark.resize(
+                            &device,
+                            &queue,
+                            &scene.tex_pl,
+                            &scene.nearest_sampler,
+                            new_size,
+                        );
+                    }
+                    error_log.pop_scope(&device, "Resizing");
                 }
 
-                background_grid.update(&queue, t);
+                let mut messages_changed = false;
 
-                pass.set_pipeline(&color_pl.pipeline);
-                pass.set_vertex_buffer(0, background_grid.vertex_buf.slice(..));
-                pass.draw(0..background_grid.vertex_count, 0..1);
+                // reload changed shaders
 
-                pass.set_pipeline(&tex_pl.pipeline);
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(watcher) = &shader_watcher {
+                    let changed = watcher.changed_shaders();
+                    for &shader in &changed {
+                        let result = watcher
+                            .read_source(shader, &device)
+                            .map_err(|err| err.to_string())
+                            .and_then(|source| {
+                                scene
+                                    .reload_shader(&device, shader, &source)
+                                    .map_err(|err| err.to_string())
+                            });
+                        match result {
+                            Ok(()) => {
+                                println!("Reloaded {}", shader.file_name());
+                                shader_errors.remove(&shader);
+                            }
+                            Err(err) => {
+                                eprintln!("Error in {}:\n{err}", shader.file_name());
+                                shader_errors.insert(shader, err);
+                            }
+                        }
+                    }
+                    messages_changed |= !changed.is_empty();
+                }
 
-                if draw_fire {
-                    pass.set_bind_group(0, &fire_bind_group, &[]);
-                    pass.set_vertex_buffer(0, fire_verts.slice(..));
-                    pass.draw(0..6, 0..1);
+                // reload changed images
 
-                    pass.set_bind_group(0, &fire_reflection_bind_group, &[]);
-                    pass.set_vertex_buffer(0, fire_reflection_verts.slice(..));
-                    pass.draw(0..6, 0..1);
+                #[cfg(not(target_arch = "wasm32"))]
+                if asset_watcher.as_ref().is_some_and(|w| w.images_changed()) {
+                    device.push_error_scope(wgpu::ErrorFilter::Validation);
+                    let result = scene.reload_characters(&device, &queue);
+                    error_log.pop_scope(&device, "Reloading characters");
+                    match result {
+                        Ok(()) => println!("Reloaded characters"),
+                        Err(err) => error_log.push("Reloading characters", err),
+                    }
+                }
+
+                // show errors on screen
+
+                if let Some(errors) = error_log.take_changed() {
+                    logged_errors = errors;
+                    messages_changed = true;
+                }
+                if messages_changed {
+                    let mut message = String::new();
+                    #[cfg(not(target_arch = "wasm32"))]
+                    for (shader, err) in &shader_errors {
+                        message += &format!("{}:\n{err}\n", shader.file_name());
+                    }
+                    for err in &logged_errors {
+                        message += &format!("{err}\n");
+                    }
+                    overlay.set_text(
+                        &device,
+                        &queue,
+                        &scene.tex_pl,
+                        &scene.nearest_sampler,
+                        &message,
+                    );
+                }
+
+                // simulate
+
+                let frame_time = frame_start_t.elapsed();
+                frame_start_t = Instant::now();
+                let since_last_draw = frame_time.as_secs_f64();
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(bench) = &mut bench {
+                    if bench.record_frame(frame_time) {
+                        println!("{}", bench.report(scene.fire_step_times()));
+                        control_flow.set_exit();
+                        return;
+                    }
+                }
+                // fixed time step while recording or benchmarking
+                // so the result plays smoothly no matter how slowly the frames are saved,
+                // and benchmarks draw the same frames every time
+                #[cfg(not(target_arch = "wasm32"))]
+                let since_last_draw = if recorder.is_some() || bench.is_some() {
+                    1. / args.fps as f64
+                } else {
+                    since_last_draw
+                };
+                // otherwise follow the music, jumping along when it's seeked
+                // instead of running the simulations through the skipped part
+                #[cfg(not(target_arch = "wasm32"))]
+                let since_last_draw = match &soundtrack {
+                    Some(music) if recorder.is_none() && bench.is_none() => {
+                        let music_t = music.time();
+                        let dt = music_t - scene.t;
+                        if (0. ..MAX_FRAME_TIME).contains(&dt) {
+                            dt
+                        } else {
+                            scene.t = music_t;
+                            0.
+                        }
+                    }
+                    _ => since_last_draw,
+                };
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(duration) = args.duration {
+                    // half a step of leeway for rounding errors in the sum
+                    if scene.t + 0.5 * since_last_draw > duration {
+                        stop_recording(&mut recorder, &error_log);
+                        control_flow.set_exit();
+                        return;
+                    }
                 }
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(timeline) = &mut timeline {
+                    let t = scene.t + since_last_draw;
+                    timeline.apply(&mut scene, t);
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(frame) = webcam.as_ref().and_then(|w| w.take_frame()) {
+                    scene.burn_into_fire(&frame);
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                let remote_params = remote.iter().flat_map(|r| r.poll_params());
+                for (param, value) in signal_sources
+                    .iter_mut()
+                    .flat_map(|s| s.poll())
+                    .chain(remote_params)
+                {
+                    if let Some(hour) = param.apply(&mut scene, value) {
+                        feed_hour = Some(hour);
+                    }
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(hour) = feed_hour.or_else(|| args.theme_hour()) {
+                    scene.set_theme(&queue, &theme::Theme::at_hour(hour));
+                }
+                // audio reactivity takes over the glow from the timeline
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some((audio, signal)) = &mut audio {
+                    audio.update(since_last_draw);
+                    scene.glow = AUDIO_GLOW_MIN + AUDIO_GLOW_RANGE * audio.get(*signal);
+                }
+                scene.update(&queue, since_last_draw);
+
+                // setup
+
+                let surface_tex = match surface.get_current_texture() {
+                    Ok(tex) => tex,
+                    // these happen e.g. when the window is resized or minimized,
+                    // reconfigure and try again next frame
+                    Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                        surface.configure(&device, &surface_config);
+                        return;
+                    }
+                    Err(wgpu::SurfaceError::Timeout) => return,
+                    Err(err @ wgpu::SurfaceError::OutOfMemory) => {
+                        error_log.push("Getting swapchain texture", err);
+                        control_flow.set_exit();
+                        return;
+                    }
+                };
+
+                device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+                let surface_view = surface_tex
+                    .texture
+                    .create_view(&wgpu::TextureViewDescriptor {
+                        format: Some(SWAPCHAIN_FORMAT),
+                        ..Default::default()
+                    });
+                let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("frame"),
+                });
+
+                // draw
 
-                if draw_characters {
-                    pass.set_bind_group(0, &characters_bind_group, &[]);
-                    pass.set_vertex_buffer(0, characters_verts.slice(..));
-                    pass.draw(0..6, 0..1);
+                scene.render(&mut encoder, &surface_view);
+
+                // the watermark goes in recordings, and so also in screenshots
+                // taken while recording since they share the frame capture
+
+                #[cfg(not(target_arch = "wasm32"))]
+                if let (Some(watermark), Some(rec)) = (&mut watermark, &recorder) {
+                    watermark.set_text(
+                        &device,
+                        &queue,
+                        &scene.tex_pl,
+                        &scene.nearest_sampler,
+                        &args.watermark_text(rec.frame_count()),
+                    );
+                    let mut watermark_pass = scene.begin_pass_on_top(&mut encoder, &surface_view);
+                    watermark.draw(&mut watermark_pass);
                 }
 
-                drop(pass);
+                // capture before the overlay so error messages don't end up in screenshots
 
-                // postprocessing pass
+                #[cfg(not(target_arch = "wasm32"))]
+                let capture = (pending_capture.is_some() || recorder.is_some() || ndi.is_some())
+                    .then(|| {
+                        let (format, width, height) = (
+                            surface_config.format,
+                            surface_config.width,
+                            surface_config.height,
+                        );
+                        let capture = match frame_capture.take() {
+                            Some(capture) if capture.fits(format, width, height) => capture,
+                            _ => FrameCapture::new(&device, format, width, height),
+                        };
+                        capture.copy_from(&mut encoder, &surface_tex.texture);
+                        capture
+                    });
 
-                if draw_postprocess {
-                    let mut postprocess_pass =
-                        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                                view: &surface_view,
-                                resolve_target: None,
-                                ops: wgpu::Operations {
-                                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                                    store: wgpu::StoreOp::Store,
-                                },
-                            })],
-                            ..Default::default()
-                        });
+                // text overlay on top of everything, unaffected by postprocessing
 
-                    postprocess_pass.set_pipeline(&postprocess_pl.pipeline);
-                    postprocess_pass.set_bind_group(0, &gbuf_bind_group, &[]);
-                    postprocess_pass.set_bind_group(1, &postprocess_pl.time_bind_group, &[]);
-                    postprocess_pass.draw(0..3, 0..1);
+                if !overlay.is_empty() {
+                    let mut overlay_pass = scene.begin_pass_on_top(&mut encoder, &surface_view);
+                    overlay.draw(&mut overlay_pass);
                 }
 
                 // finalize
 
                 queue.submit(Some(encoder.finish()));
+                error_log.pop_scope(&device, "Rendering");
+
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(capture) = capture {
+                    match capture.read(&device) {
+                        Ok(frame) => {
+                            if let Some(ndi) = &ndi {
+                                ndi.send(&frame);
+                            }
+                            if let Some(rec) = &mut recorder {
+                                if let Err(err) = rec.save(&frame) {
+                                    // stop instead of failing every frame from now on
+                                    error_log.push("Recording frame", err);
+                                    stop_recording(&mut recorder, &error_log);
+                                }
+                            }
+                            let result = match pending_capture.take() {
+                                Some(CaptureTarget::File) => {
+                                    capture::save_to_file(&frame).map(|path| {
+                                        println!("Saved frame to {path}");
+                                    })
+                                }
+                                Some(CaptureTarget::Clipboard) => {
+                                    let clipboard = match &mut clipboard {
+                                        Some(clipboard) => Ok(clipboard),
+                                        None => arboard::Clipboard::new()
+                                            .map(|new| clipboard.insert(new))
+                                            .map_err(anyhow::Error::from),
+                                    };
+                                    clipboard
+                                        .and_then(|clipboard| {
+                                            capture::copy_to_clipboard(clipboard, &frame)
+                                        })
+                                        .map(|()| println!("Copied frame to clipboard"))
+                                }
+                                None => Ok(()),
+                            };
+                            if let Err(err) = result {
+                                error_log.push("Capturing frame", err);
+                            }
+                        }
+                        Err(err) => {
+                            error_log.push("Capturing frame", err);
+                            pending_capture = None;
+                            stop_recording(&mut recorder, &error_log);
+                        }
+                    }
+                    frame_capture = Some(capture);
+                }
+
                 surface_tex.present();
             }
             //
@@ -368,11 +1020,62 @@ async fn main_async() -> anyhow::Result<()> {
                 WindowEvent::CloseRequested => {
                     control_flow.set_exit();
                 }
-                WindowEvent::Resized(new_size) => {
-                    surface_config.width = new_size.width;
-                    surface_config.height = new_size.height;
-                    surface.configure(&device, &surface_config);
-                    gbuffer = create_screen_texture(&device, new_size);
+                WindowEvent::Resized(_) => {
+                    resize_pending = true;
+                }
+                WindowEvent::CursorMoved { position, .. } => {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    let size = window.inner_size();
+                    // the cursor is in device pixels but the canvas resolution may be capped
+                    #[cfg(target_arch = "wasm32")]
+                    let size = web_layout::css_size(&window.canvas())
+                        .to_physical::<f64>(window.scale_factor());
+                    scene.mouse_pos = Some([
+                        2. * position.x as f32 / size.width as f32 - 1.,
+                        1. - 2. * position.y as f32 / size.height as f32,
+                    ]);
+                }
+                // winit computes the new size from the canvas resolution,
+                // which is wrong if it was capped, so give it the size on the page instead
+                #[cfg(target_arch = "wasm32")]
+                WindowEvent::ScaleFactorChanged {
+                    scale_factor,
+                    new_inner_size,
+                } => {
+                    *new_inner_size =
+                        web_layout::css_size(&window.canvas()).to_physical(scale_factor);
+                }
+                // double click or tap for fullscreen
+                #[cfg(target_arch = "wasm32")]
+                WindowEvent::MouseInput {
+                    state: winit::event::ElementState::Released,
+                    button: winit::event::MouseButton::Left,
+                    ..
+                }
+                | WindowEvent::Touch(winit::event::Touch {
+                    phase: winit::event::TouchPhase::Ended,
+                    ..
+                }) => {
+                    fullscreen.click();
+                }
+                // preview other artwork in place of the characters by dropping it on the window
+                #[cfg(not(target_arch = "wasm32"))]
+                WindowEvent::DroppedFile(path) => {
+                    device.push_error_scope(wgpu::ErrorFilter::Validation);
+                    let result = std::fs::read(&path)
+                        .map_err(anyhow::Error::from)
+                        .and_then(|bytes| scene.replace_characters(&device, &queue, &bytes));
+                    error_log.pop_scope(&device, "Replacing characters");
+                    match result {
+                        Ok(()) => println!("Replaced characters with {}", path.display()),
+                        Err(err) => error_log.push("Replacing characters", err),
+                    }
+                }
+                WindowEvent::CursorLeft { .. } => {
+                    scene.mouse_pos = None;
+                }
+                WindowEvent::ModifiersChanged(new_modifiers) => {
+                    modifiers = new_modifiers;
                 }
                 WindowEvent::KeyboardInput {
                     input:
@@ -383,26 +1086,76 @@ async fn main_async() -> anyhow::Result<()> {
                         },
                     ..
                 } => {
-                    use VirtualKeyCode::*;
-                    match key {
-                        Q => {
-                            control_flow.set_exit();
-                        }
-                        F => {
-                            draw_fire = !draw_fire;
-                        }
-                        C => {
-                            draw_characters = !draw_characters;
-                        }
-                        P => {
-                            draw_postprocess = !draw_postprocess;
-                        }
-                        _ => {}
+                    if let Some(action) = input::key_action(key, modifiers) {
+                        pending_actions.push(action);
                     }
                 }
                 _ => {}
             },
+            // make sure ffmpeg gets to finish the video when quitting mid-recording
+            #[cfg(not(target_arch = "wasm32"))]
+            Event::LoopDestroyed => {
+                stop_recording(&mut recorder, &error_log);
+            }
             _ => {}
         };
     });
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+fn stop_recording(recorder: &mut Option<Recorder>, error_log: &ErrorLog) {
+    if let Some(rec) = recorder.take() {
+        match rec.finish() {
+            Ok(summary) => println!("{summary}"),
+            Err(err) => error_log.push("Finishing recording", err),
+        }
+    }
+}
+
+/// Get a device with the optional features the pipelines can make use of.
+async fn request_device(adapter: &wgpu::Adapter) -> anyhow::Result<(wgpu::Device, wgpu::Queue)> {
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut limits = wgpu::Limits::default();
+    #[cfg(target_arch = "wasm32")]
+    let mut limits = if adapter.get_info().backend == wgpu::Backend::Gl {
+        wgpu::Limits::downlevel_webgl2_defaults()
+    } else {
+        wgpu::Limits::default()
+    }
+    .using_resolution(adapter.limits());
+
+    // compressed texture formats are used if the adapter has them,
+    // otherwise we fall back to uncompressed images.
+    // same for push constants, which replace some small uniform buffers.
+    // the GL backend emulates those with uniforms and panics on some of our layouts,
+    // so they're left out there
+    let mut features = adapter.features()
+        & (wgpu::Features::TEXTURE_COMPRESSION_BC
+            | wgpu::Features::TEXTURE_COMPRESSION_ETC2
+            | wgpu::Features::PUSH_CONSTANTS);
+    if adapter.get_info().backend == wgpu::Backend::Gl {
+        features.remove(wgpu::Features::PUSH_CONSTANTS);
+    }
+    // as big as possible for rendering high resolution stills
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        limits.max_texture_dimension_2d = adapter.limits().max_texture_dimension_2d;
+        limits.max_buffer_size = adapter.limits().max_buffer_size;
+    }
+    if features.contains(wgpu::Features::PUSH_CONSTANTS) {
+        limits.max_push_constant_size = adapter.limits().max_push_constant_size;
+    }
+
+    adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                features,
+                limits,
+                label: Some("demodemonini"),
+            },
+            None,
+        )
+        .await
+        // for some reason this map is needed on wasm
+        .map_err(|e| anyhow::anyhow!("Failed to get device: {e}"))
+}
@@ -0,0 +1,140 @@
+//! Streaming rendered frames over the local network with NDI,
+//! so a media server elsewhere in the venue can pick them up without a capture card.
+//!
+//! The NDI runtime is loaded when needed instead of linked,
+//! so it only has to be installed on machines that actually send.
+
+use std::ffi::{c_char, c_void, CString};
+
+// NDI_LIB_FOURCC('R', 'G', 'B', 'X'), RGBA with alpha ignored
+const FOURCC_RGBX: u32 = u32::from_le_bytes(*b"RGBX");
+const FRAME_FORMAT_PROGRESSIVE: i32 = 1;
+// lets the SDK fill in timecodes itself
+const TIMECODE_SYNTHESIZE: i64 = i64::MAX;
+
+#[repr(C)]
+struct SendCreate {
+    ndi_name: *const c_char,
+    groups: *const c_char,
+    clock_video: bool,
+    clock_audio: bool,
+}
+
+#[repr(C)]
+struct VideoFrame {
+    xres: i32,
+    yres: i32,
+    fourcc: u32,
+    frame_rate_n: i32,
+    frame_rate_d: i32,
+    picture_aspect_ratio: f32,
+    frame_format_type: i32,
+    timecode: i64,
+    data: *const u8,
+    line_stride_in_bytes: i32,
+    metadata: *const c_char,
+    timestamp: i64,
+}
+
+type SendInstance = *mut c_void;
+
+/// A source other machines on the network can receive frames from.
+pub struct NdiSender {
+    send_video: unsafe extern "C" fn(SendInstance, *const VideoFrame),
+    destroy: unsafe extern "C" fn(SendInstance),
+    instance: SendInstance,
+    fps: u32,
+    // the functions above point into the library, so it has to outlive them
+    _lib: libloading::Library,
+}
+
+impl NdiSender {
+    /// Start sending as a source with the given name,
+    /// announced to receivers at the given frame rate.
+    pub fn new(name: &str, fps: u32) -> anyhow::Result<Self> {
+        let lib = load_runtime()?;
+        // SAFETY: the signatures match the NDI SDK's headers
+        // and the function pointers are kept no longer than the library
+        unsafe {
+            let initialize = *lib.get::<unsafe extern "C" fn() -> bool>(b"NDIlib_initialize\0")?;
+            let create = *lib.get::<unsafe extern "C" fn(*const SendCreate) -> SendInstance>(
+                b"NDIlib_send_create\0",
+            )?;
+            let send_video = *lib.get(b"NDIlib_send_send_video_v2\0")?;
+            let destroy = *lib.get(b"NDIlib_send_destroy\0")?;
+
+            if !initialize() {
+                anyhow::bail!("NDI isn't supported on this CPU");
+            }
+            let name = CString::new(name)?;
+            let settings = SendCreate {
+                ndi_name: name.as_ptr(),
+                groups: std::ptr::null(),
+                clock_video: false,
+                clock_audio: false,
+            };
+            let instance = create(&settings);
+            if instance.is_null() {
+                anyhow::bail!("Failed to create NDI source {name:?}");
+            }
+            println!("Sending frames over NDI as {name:?}");
+            Ok(Self {
+                send_video,
+                destroy,
+                instance,
+                fps,
+                _lib: lib,
+            })
+        }
+    }
+
+    /// Send a frame to everyone receiving from this source.
+    pub fn send(&self, frame: &image::RgbaImage) {
+        let video_frame = VideoFrame {
+            xres: frame.width() as i32,
+            yres: frame.height() as i32,
+            fourcc: FOURCC_RGBX,
+            frame_rate_n: self.fps as i32,
+            frame_rate_d: 1,
+            picture_aspect_ratio: frame.width() as f32 / frame.height() as f32,
+            frame_format_type: FRAME_FORMAT_PROGRESSIVE,
+            timecode: TIMECODE_SYNTHESIZE,
+            data: frame.as_ptr(),
+            line_stride_in_bytes: 4 * frame.width() as i32,
+            metadata: std::ptr::null(),
+            timestamp: 0,
+        };
+        // SAFETY: the synchronous send is done with the data when it returns
+        unsafe { (self.send_video)(self.instance, &video_frame) };
+    }
+}
+
+impl Drop for NdiSender {
+    fn drop(&mut self) {
+        // SAFETY: the instance was created by this library and isn't used after this
+        unsafe { (self.destroy)(self.instance) };
+    }
+}
+
+fn load_runtime() -> anyhow::Result<libloading::Library> {
+    #[cfg(target_os = "windows")]
+    const LIB_NAME: &str = "Processing.NDI.Lib.x64.dll";
+    #[cfg(target_os = "macos")]
+    const LIB_NAME: &str = "libndi.dylib";
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    const LIB_NAME: &str = "libndi.so.5";
+
+    // the runtime installer points this at where it put the library,
+    // otherwise rely on the system's search path
+    let path = match std::env::var_os("NDI_RUNTIME_DIR_V5") {
+        Some(dir) => std::path::Path::new(&dir).join(LIB_NAME),
+        None => LIB_NAME.into(),
+    };
+    // SAFETY: the NDI runtime has no initialization routines with preconditions
+    unsafe { libloading::Library::new(&path) }.map_err(|err| {
+        anyhow::anyhow!(
+            "Failed to load the NDI runtime from {}, is it installed? ({err})",
+            path.display()
+        )
+    })
+}
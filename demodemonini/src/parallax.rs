@@ -0,0 +1,121 @@
+use wgpu::util::DeviceExt;
+
+use super::{
+    atlas::UvRect,
+    pipelines::{TexturePipeline, TexturedVertex, UvEffects},
+};
+
+// how far the nearest layer moves at most, in clip space units.
+// layers are drawn larger than the screen by how far they can move
+// so their edges never show
+const MAX_OFFSET: f32 = 0.02;
+// gap in the depth buffer between consecutive layers
+const LAYER_DEPTH_STEP: f32 = 0.001;
+
+/// One image in a stack of parallax layers.
+pub struct LayerSource {
+    pub name: &'static str,
+    pub texture: wgpu::Texture,
+    /// How much the layer moves relative to the others,
+    /// 0 for stationary and 1 for the nearest layer.
+    pub parallax: f32,
+}
+
+struct Layer {
+    parallax: f32,
+    // position in the depth buffer
+    depth: f32,
+    bind_group: wgpu::BindGroup,
+    verts: wgpu::Buffer,
+    // kept around for as long as the bind group uses it
+    _texture: wgpu::Texture,
+}
+
+/// A fullscreen painting split into layers at different depths,
+/// drawn with small independent offsets to give it a bit of depth.
+pub struct ParallaxLayers {
+    layers: Vec<Layer>,
+}
+
+impl ParallaxLayers {
+    /// Set up drawing for the given layers, ordered back to front.
+    ///
+    /// The backmost layer goes at `depth` in the depth buffer
+    /// and the others just in front of it.
+    pub fn new(
+        device: &wgpu::Device,
+        pipeline: &TexturePipeline,
+        sampler: &wgpu::Sampler,
+        sources: Vec<LayerSource>,
+        depth: f32,
+        effects: UvEffects,
+    ) -> Self {
+        let layers = sources
+            .into_iter()
+            .enumerate()
+            .map(|(idx, src)| {
+                let depth = depth - idx as f32 * LAYER_DEPTH_STEP;
+                let view = src
+                    .texture
+                    .create_view(&wgpu::TextureViewDescriptor::default());
+                let bind_group = pipeline
+                    .create_bind_group_with_effects(device, src.name, &view, sampler, effects);
+                let verts = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some(src.name),
+                    contents: bytemuck::cast_slice(&Self::layer_vertices(
+                        src.parallax,
+                        depth,
+                        [0., 0.],
+                    )),
+                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                });
+                Layer {
+                    parallax: src.parallax,
+                    depth,
+                    bind_group,
+                    verts,
+                    _texture: src.texture,
+                }
+            })
+            .collect();
+        Self { layers }
+    }
+
+    /// Move the layers according to the time and the mouse position
+    /// (in the range [-1, 1] on both axes, `None` if the mouse isn't over the window).
+    pub fn update(&self, queue: &wgpu::Queue, t: f32, mouse: Option<[f32; 2]>) {
+        // slow drifting figure-eight when the mouse isn't there to steer
+        let drift = [(0.13 * t).sin(), (0.26 * t).sin() * 0.5];
+        let offset = match mouse {
+            Some([x, y]) => [0.3 * drift[0] + 0.7 * x, 0.3 * drift[1] + 0.7 * y],
+            None => drift,
+        };
+        for layer in &self.layers {
+            queue.write_buffer(
+                &layer.verts,
+                0,
+                bytemuck::cast_slice(&Self::layer_vertices(layer.parallax, layer.depth, offset)),
+            );
+        }
+    }
+
+    /// Draw all the layers, expecting the texture pipeline to be set already.
+    pub fn draw<'pass>(&'pass self, pass: &mut wgpu::RenderPass<'pass>) {
+        for layer in &self.layers {
+            pass.set_bind_group(0, &layer.bind_group, &[]);
+            pass.set_vertex_buffer(0, layer.verts.slice(..));
+            pass.draw(0..6, 0..1);
+        }
+    }
+
+    fn layer_vertices(parallax: f32, depth: f32, offset: [f32; 2]) -> [TexturedVertex; 6] {
+        let size = 1. + parallax * MAX_OFFSET;
+        let [dx, dy] = offset.map(|o| o.clamp(-1., 1.) * parallax * MAX_OFFSET);
+        TexturePipeline::quad_vertices(
+            [-size + dx, -size + dy],
+            [size + dx, size + dy],
+            depth,
+            UvRect::FULL,
+        )
+    }
+}
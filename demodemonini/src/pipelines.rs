@@ -1,52 +1,346 @@
 use std::borrow::Cow;
-
 use wgpu::util::DeviceExt;
 
-pub fn load_png_texture(
+use super::{
+    atlas::UvRect,
+    fullscreen,
+    shaders::{self, Shader},
+};
+
+/// Run a closure creating GPU resources inside a validation error scope,
+/// returning the error instead of letting wgpu panic on it.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn catch_validation_error<T>(
     device: &wgpu::Device,
-    queue: &wgpu::Queue,
-    bytes: &[u8],
-) -> anyhow::Result<wgpu::Texture> {
-    let decoder = png::Decoder::new(bytes);
-    let mut reader = decoder.read_info()?;
-    let mut buf = vec![0; reader.output_buffer_size()];
-    let info = reader.next_frame(&mut buf)?;
-    let image_bytes = &buf[..info.buffer_size()];
-
-    Ok(device.create_texture_with_data(
-        queue,
-        &wgpu::TextureDescriptor {
-            label: None,
-            size: wgpu::Extent3d {
-                width: info.width,
-                height: info.height,
-                depth_or_array_layers: 1,
+    create: impl FnOnce() -> T,
+) -> Result<T, wgpu::Error> {
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let created = create();
+    // native error scopes resolve immediately, no need to actually wait for anything
+    match futures::executor::block_on(device.pop_error_scope()) {
+        Some(err) => Err(err),
+        None => Ok(created),
+    }
+}
+
+/// The part of the whole picture that a render target covers,
+/// for rendering pictures too big for one texture in tiles.
+///
+/// Everything is drawn in clip space, so this is applied to clip space positions
+/// as `position * scale + offset` before they go to the rasterizer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ViewRegion {
+    pub scale: [f32; 2],
+    pub offset: [f32; 2],
+}
+
+impl ViewRegion {
+    /// The whole picture.
+    pub const FULL: Self = Self {
+        scale: [1., 1.],
+        offset: [0., 0.],
+    };
+
+    /// The rectangle between two corners in the whole picture's clip space.
+    pub fn between(min: [f32; 2], max: [f32; 2]) -> Self {
+        let scale = [2. / (max[0] - min[0]), 2. / (max[1] - min[1])];
+        Self {
+            scale,
+            offset: [
+                -0.5 * (min[0] + max[0]) * scale[0],
+                -0.5 * (min[1] + max[1]) * scale[1],
+            ],
+        }
+    }
+}
+
+/// The global time given to a shader along with the [`ViewRegion`] being drawn,
+/// as push constants if the device supports them and otherwise in a uniform buffer.
+///
+/// Shaders get them with `#include "common/time.wgsl"`,
+/// which puts the uniform buffer in bind group 1 unless `TIME_GROUP` is defined.
+pub struct TimeUniform {
+    stages: wgpu::ShaderStages,
+    group: u32,
+    t: f32,
+    view: ViewRegion,
+    // only used without push constants
+    buffer: Option<UniformBuffer>,
+}
+
+struct UniformBuffer {
+    buffer: wgpu::Buffer,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+}
+
+impl TimeUniform {
+    /// Set up the time for shaders in the given stages,
+    /// placed in bind group number `group` if a uniform buffer is needed.
+    pub fn new(device: &wgpu::Device, label: &str, stages: wgpu::ShaderStages, group: u32) -> Self {
+        let buffer = (!shaders::use_push_constants(device)).then(|| {
+            let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+                size: shaders::PUSH_CONSTANT_SIZE as u64,
+                mapped_at_creation: false,
+            });
+            let bind_group_layout =
+                device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some(label),
+                    entries: &[wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: stages,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            min_binding_size: wgpu::BufferSize::new(
+                                shaders::PUSH_CONSTANT_SIZE as u64,
+                            ),
+                            has_dynamic_offset: false,
+                        },
+                        count: None,
+                    }],
+                });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(buffer.as_entire_buffer_binding()),
+                }],
+            });
+            UniformBuffer {
+                buffer,
+                bind_group_layout,
+                bind_group,
+            }
+        });
+
+        Self {
+            stages,
+            group,
+            t: 0.,
+            view: ViewRegion::FULL,
+            buffer,
+        }
+    }
+
+    /// The pipeline's other bind group layouts,
+    /// plus the uniform buffer's layout if there is one.
+    /// The other layouts must fill all the groups before this one.
+    pub fn with_bind_group_layout<'a>(
+        &'a self,
+        others: &[&'a wgpu::BindGroupLayout],
+    ) -> Vec<&'a wgpu::BindGroupLayout> {
+        let mut layouts = others.to_vec();
+        if let Some(buf) = &self.buffer {
+            debug_assert_eq!(layouts.len(), self.group as usize);
+            layouts.push(&buf.bind_group_layout);
+        }
+        layouts
+    }
+
+    /// Push constant ranges to put in the pipeline layout.
+    pub fn push_constant_ranges(&self) -> Vec<wgpu::PushConstantRange> {
+        match self.buffer {
+            Some(_) => Vec::new(),
+            None => vec![wgpu::PushConstantRange {
+                stages: self.stages,
+                range: 0..shaders::PUSH_CONSTANT_SIZE,
+            }],
+        }
+    }
+
+    /// Set the time for the next frame.
+    pub fn update(&mut self, queue: &wgpu::Queue, t: f32) {
+        self.t = t;
+        self.write(queue);
+    }
+
+    /// Set the part of the picture the next frames are drawn into.
+    pub fn set_view(&mut self, queue: &wgpu::Queue, view: ViewRegion) {
+        self.view = view;
+        self.write(queue);
+    }
+
+    fn write(&self, queue: &wgpu::Queue) {
+        if let Some(buf) = &self.buffer {
+            queue.write_buffer(&buf.buffer, 0, bytemuck::cast_slice(&self.contents()));
+        }
+    }
+
+    // laid out like the struct in time.wgsl
+    fn contents(&self) -> [f32; 8] {
+        let ViewRegion { scale, offset } = self.view;
+        [self.t, 0., 0., 0., scale[0], scale[1], offset[0], offset[1]]
+    }
+
+    /// Give the time to the pipeline currently set on the render pass.
+    pub fn bind<'pass>(&'pass self, pass: &mut wgpu::RenderPass<'pass>) {
+        match &self.buffer {
+            Some(buf) => pass.set_bind_group(self.group, &buf.bind_group, &[]),
+            None => pass.set_push_constants(self.stages, 0, bytemuck::cast_slice(&self.contents())),
+        }
+    }
+}
+
+/// How a draw's colors are combined with what's already been drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlendMode {
+    /// Regular transparency.
+    #[default]
+    Alpha,
+    /// Colors are added on top, brightening what's underneath.
+    /// Good for light-like things such as fire.
+    Additive,
+    /// Transparency for textures whose colors have already been multiplied by alpha,
+    /// like ones rendered into with alpha blending.
+    Premultiplied,
+}
+
+impl BlendMode {
+    pub const COUNT: usize = 3;
+    pub const ALL: [BlendMode; Self::COUNT] = [
+        BlendMode::Alpha,
+        BlendMode::Additive,
+        BlendMode::Premultiplied,
+    ];
+
+    pub fn blend_state(self) -> wgpu::BlendState {
+        match self {
+            BlendMode::Alpha => wgpu::BlendState::ALPHA_BLENDING,
+            BlendMode::Additive => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Zero,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
             },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8Unorm,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING,
-            view_formats: &[],
-        },
-        image_bytes,
-    ))
+            BlendMode::Premultiplied => wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+        }
+    }
+}
+
+/// All the pipelines used by the demo.
+pub struct Pipelines {
+    pub vert_colors: VertexColorPipeline,
+    pub texture: TexturePipeline,
+    pub instanced_texture: InstancedTexturePipeline,
+    pub reflection: ReflectionPipeline,
+    pub lines: LinePipeline,
+    pub gradient: GradientPipeline,
+    pub postprocess: PostprocessPipeline,
+}
+
+impl Pipelines {
+    /// Compile all the pipelines.
+    ///
+    /// On native they're compiled on separate threads at the same time,
+    /// since shader compilation in the driver is most of the startup time.
+    /// wgpu doesn't give access to the drivers' pipeline caches yet,
+    /// so this is the best we can do to make startup fast.
+    pub fn new(device: &wgpu::Device) -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            std::thread::scope(|s| {
+                let vert_colors = s.spawn(|| VertexColorPipeline::new(device));
+                let lines = s.spawn(|| LinePipeline::new(device));
+                let gradient = s.spawn(|| GradientPipeline::new(device));
+                let postprocess = s.spawn(|| PostprocessPipeline::new(device));
+                // these need the texture pipeline's bind group layouts
+                let textures = s.spawn(|| {
+                    let texture = TexturePipeline::new(device);
+                    let instanced_texture = InstancedTexturePipeline::new(device, &texture);
+                    let reflection = ReflectionPipeline::new(device, &texture, BlendMode::Additive);
+                    (texture, instanced_texture, reflection)
+                });
+                let join = "Pipeline compilation thread panicked";
+                let (texture, instanced_texture, reflection) = textures.join().expect(join);
+                Self {
+                    vert_colors: vert_colors.join().expect(join),
+                    texture,
+                    instanced_texture,
+                    reflection,
+                    lines: lines.join().expect(join),
+                    gradient: gradient.join().expect(join),
+                    postprocess: postprocess.join().expect(join),
+                }
+            })
+        }
+        // no threads on the web
+        #[cfg(target_arch = "wasm32")]
+        {
+            let texture = TexturePipeline::new(device);
+            Self {
+                vert_colors: VertexColorPipeline::new(device),
+                instanced_texture: InstancedTexturePipeline::new(device, &texture),
+                reflection: ReflectionPipeline::new(device, &texture, BlendMode::Additive),
+                texture,
+                lines: LinePipeline::new(device),
+                gradient: GradientPipeline::new(device),
+                postprocess: PostprocessPipeline::new(device),
+            }
+        }
+    }
 }
 
 pub struct TexturePipeline {
-    pub pipeline: wgpu::RenderPipeline,
+    // one for each blend mode
+    pipelines: [wgpu::RenderPipeline; BlendMode::COUNT],
     pub bind_group_layout: wgpu::BindGroupLayout,
+    pub time: TimeUniform,
+    pipeline_layout: wgpu::PipelineLayout,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct TexturedVertex {
+    pub pos: [f32; 3],
+    pub tex_coords: [f32; 2],
+}
+
+impl TexturedVertex {
+    const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<TexturedVertex>() as u64,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &[
+            // position
+            wgpu::VertexAttribute {
+                format: wgpu::VertexFormat::Float32x3,
+                offset: 0,
+                shader_location: 0,
+            },
+            // texture coords
+            wgpu::VertexAttribute {
+                format: wgpu::VertexFormat::Float32x2,
+                offset: 4 * 3,
+                shader_location: 1,
+            },
+        ],
+    };
+}
+
+/// Subtle animated effects done in texture space by [`TexturePipeline`],
+/// set per bind group.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct UvEffects {
+    /// Texture coordinates moved per second.
+    pub scroll: [f32; 2],
+    /// How much the image slowly zooms in and out, as a fraction of its size.
+    pub breathe: f32,
+    /// Strength of a wavy horizontal distortion, like a reflection on water.
+    pub shimmer: f32,
 }
 
 impl TexturePipeline {
     pub fn new(device: &wgpu::Device) -> Self {
         let label = Some("texture");
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label,
-            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!(
-                "./shaders/textured.wgsl"
-            ))),
-        });
 
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label,
@@ -67,45 +361,249 @@ impl TexturePipeline {
                     ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                     count: None,
                 },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<UvEffects>() as u64
+                        ),
+                        has_dynamic_offset: false,
+                    },
+                    count: None,
+                },
             ],
         });
 
+        let time = TimeUniform::new(
+            device,
+            "texture global time",
+            wgpu::ShaderStages::VERTEX_FRAGMENT,
+            1,
+        );
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label,
+            bind_group_layouts: &time.with_bind_group_layout(&[&bind_group_layout]),
+            push_constant_ranges: &time.push_constant_ranges(),
+        });
+        let pipelines = Self::create_pipelines(
+            device,
+            &pipeline_layout,
+            &Shader::Textured.embedded_source(device),
+        );
+
+        Self {
+            pipelines,
+            bind_group_layout,
+            time,
+            pipeline_layout,
+        }
+    }
+
+    /// Recompile the pipeline from new shader source.
+    /// On error the old pipeline is kept.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reload(&mut self, device: &wgpu::Device, source: &str) -> Result<(), wgpu::Error> {
+        self.pipelines = catch_validation_error(device, || {
+            Self::create_pipelines(device, &self.pipeline_layout, source)
+        })?;
+        Ok(())
+    }
+
+    fn create_pipelines(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        source: &str,
+    ) -> [wgpu::RenderPipeline; BlendMode::COUNT] {
+        let label = Some("texture");
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label,
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
+        });
+
+        BlendMode::ALL.map(|blend| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label,
+                layout: Some(layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[TexturedVertex::LAYOUT],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: super::SWAPCHAIN_FORMAT,
+                        blend: Some(blend.blend_state()),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: None,
+                    ..Default::default()
+                },
+                depth_stencil: super::depth_stencil_state(),
+                multisample: super::MULTISAMPLE_STATE,
+                multiview: None,
+            })
+        })
+    }
+
+    /// The pipeline variant drawing with the given blend mode.
+    pub fn pipeline(&self, blend: BlendMode) -> &wgpu::RenderPipeline {
+        &self.pipelines[blend as usize]
+    }
+
+    /// Vertices for a quad between two corners in clip space
+    /// showing the given region of a texture,
+    /// to be drawn as a triangle list.
+    ///
+    /// `depth` is between 0 (nearest) and 1 (furthest) and decides what's drawn on top
+    /// when a depth buffer is in use. Without one, the latest draw call wins.
+    ///
+    /// The top of the texture region goes at the `top_right` end,
+    /// so passing a `top_right` below `bottom_left` flips the image vertically.
+    pub fn quad_vertices(
+        bottom_left: [f32; 2],
+        top_right: [f32; 2],
+        depth: f32,
+        uv: UvRect,
+    ) -> [TexturedVertex; 6] {
+        let [l, b] = bottom_left;
+        let [r, t] = top_right;
+        let vert = |x, y, u, v| TexturedVertex {
+            pos: [x, y, depth],
+            tex_coords: [u, v],
+        };
+        [
+            vert(l, b, uv.min[0], uv.max[1]),
+            vert(r, b, uv.max[0], uv.max[1]),
+            vert(r, t, uv.max[0], uv.min[1]),
+            vert(l, b, uv.min[0], uv.max[1]),
+            vert(r, t, uv.max[0], uv.min[1]),
+            vert(l, t, uv.min[0], uv.min[1]),
+        ]
+    }
+
+    /// Create a bind group with a texture and a sampler
+    /// compatible with this pipeline.
+    pub fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        label: &str,
+        view: &wgpu::TextureView,
+        sampler: &wgpu::Sampler,
+    ) -> wgpu::BindGroup {
+        self.create_bind_group_with_effects(device, label, view, sampler, UvEffects::default())
+    }
+
+    /// Create a bind group that draws the texture with some animated effects.
+    pub fn create_bind_group_with_effects(
+        &self,
+        device: &wgpu::Device,
+        label: &str,
+        view: &wgpu::TextureView,
+        sampler: &wgpu::Sampler,
+        effects: UvEffects,
+    ) -> wgpu::BindGroup {
+        let effects_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(label),
+            contents: bytemuck::bytes_of(&effects),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(label),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: effects_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+}
+
+/// Draws a texture as a reflection on rippling water,
+/// using the same vertices and bind groups as [`TexturePipeline`].
+pub struct ReflectionPipeline {
+    pub pipeline: wgpu::RenderPipeline,
+    blend: BlendMode,
+    pipeline_layout: wgpu::PipelineLayout,
+}
+
+impl ReflectionPipeline {
+    pub fn new(device: &wgpu::Device, texture_pl: &TexturePipeline, blend: BlendMode) -> Self {
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("reflection"),
+            bind_group_layouts: &texture_pl
+                .time
+                .with_bind_group_layout(&[&texture_pl.bind_group_layout]),
+            push_constant_ranges: &texture_pl.time.push_constant_ranges(),
+        });
+        let pipeline = Self::create_pipeline(
+            device,
+            &pipeline_layout,
+            &Shader::Reflection.embedded_source(device),
+            blend,
+        );
+
+        Self {
+            pipeline,
+            blend,
+            pipeline_layout,
+        }
+    }
+
+    /// Recompile the pipeline from new shader source.
+    /// On error the old pipeline is kept.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reload(&mut self, device: &wgpu::Device, source: &str) -> Result<(), wgpu::Error> {
+        self.pipeline = catch_validation_error(device, || {
+            Self::create_pipeline(device, &self.pipeline_layout, source, self.blend)
+        })?;
+        Ok(())
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        source: &str,
+        blend: BlendMode,
+    ) -> wgpu::RenderPipeline {
+        let label = Some("reflection");
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label,
-            bind_group_layouts: &[&bind_group_layout],
-            push_constant_ranges: &[],
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
         });
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label,
-            layout: Some(&pipeline_layout),
+            layout: Some(layout),
             vertex: wgpu::VertexState {
                 module: &shader,
                 entry_point: "vs_main",
-                buffers: &[wgpu::VertexBufferLayout {
-                    array_stride: 4 * 2 * 2,
-                    step_mode: wgpu::VertexStepMode::Vertex,
-                    attributes: &[
-                        // position
-                        wgpu::VertexAttribute {
-                            format: wgpu::VertexFormat::Float32x2,
-                            offset: 0,
-                            shader_location: 0,
-                        },
-                        // texture coords
-                        wgpu::VertexAttribute {
-                            format: wgpu::VertexFormat::Float32x2,
-                            offset: 4 * 2,
-                            shader_location: 1,
-                        },
-                    ],
-                }],
+                buffers: &[TexturedVertex::LAYOUT],
             },
             fragment: Some(wgpu::FragmentState {
                 module: &shader,
                 entry_point: "fs_main",
                 targets: &[Some(wgpu::ColorTargetState {
                     format: super::SWAPCHAIN_FORMAT,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    blend: Some(blend.blend_state()),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
             }),
@@ -115,44 +613,154 @@ impl TexturePipeline {
                 cull_mode: None,
                 ..Default::default()
             },
-            depth_stencil: None,
+            depth_stencil: super::depth_stencil_state(),
             multisample: super::MULTISAMPLE_STATE,
             multiview: None,
+        })
+    }
+}
+
+/// Draws many textured quads sharing one texture in a single draw call,
+/// each with its own position, size, rotation, texture region and tint.
+///
+/// Uses the same bind groups and time as [`TexturePipeline`].
+pub struct InstancedTexturePipeline {
+    // one for each blend mode
+    pipelines: [wgpu::RenderPipeline; BlendMode::COUNT],
+    pipeline_layout: wgpu::PipelineLayout,
+}
+
+/// One quad drawn by [`InstancedTexturePipeline`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct QuadInstance {
+    /// Center of the quad in clip space.
+    pub center: [f32; 2],
+    pub half_size: [f32; 2],
+    /// Counterclockwise rotation around the center in radians.
+    pub rotation: f32,
+    /// Position in the depth buffer, see [`TexturePipeline::quad_vertices`].
+    pub depth: f32,
+    pub uv_min: [f32; 2],
+    pub uv_max: [f32; 2],
+    /// Color multiplied with the texture.
+    pub tint: [f32; 4],
+}
+
+impl QuadInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 7] = wgpu::vertex_attr_array![
+        0 => Float32x2,
+        1 => Float32x2,
+        2 => Float32,
+        3 => Float32,
+        4 => Float32x2,
+        5 => Float32x2,
+        6 => Float32x4,
+    ];
+}
+
+impl InstancedTexturePipeline {
+    pub fn new(device: &wgpu::Device, texture_pl: &TexturePipeline) -> Self {
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("instanced texture"),
+            bind_group_layouts: &texture_pl
+                .time
+                .with_bind_group_layout(&[&texture_pl.bind_group_layout]),
+            push_constant_ranges: &texture_pl.time.push_constant_ranges(),
         });
+        let pipelines = Self::create_pipelines(
+            device,
+            &pipeline_layout,
+            &Shader::TexturedInstanced.embedded_source(device),
+        );
 
         Self {
-            pipeline,
-            bind_group_layout,
+            pipelines,
+            pipeline_layout,
         }
     }
 
-    /// Create a bind group with a texture and a sampler
-    /// compatible with this pipeline.
-    pub fn create_bind_group(
-        &self,
+    /// Recompile the pipeline from new shader source.
+    /// On error the old pipeline is kept.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reload(&mut self, device: &wgpu::Device, source: &str) -> Result<(), wgpu::Error> {
+        self.pipelines = catch_validation_error(device, || {
+            Self::create_pipelines(device, &self.pipeline_layout, source)
+        })?;
+        Ok(())
+    }
+
+    fn create_pipelines(
         device: &wgpu::Device,
-        view: &wgpu::TextureView,
-        sampler: &wgpu::Sampler,
-    ) -> wgpu::BindGroup {
-        device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: None,
-            layout: &self.bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(view),
+        layout: &wgpu::PipelineLayout,
+        source: &str,
+    ) -> [wgpu::RenderPipeline; BlendMode::COUNT] {
+        let label = Some("instanced texture");
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label,
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
+        });
+
+        BlendMode::ALL.map(|blend| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label,
+                layout: Some(layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[wgpu::VertexBufferLayout {
+                        array_stride: std::mem::size_of::<QuadInstance>() as u64,
+                        step_mode: wgpu::VertexStepMode::Instance,
+                        attributes: &QuadInstance::ATTRIBUTES,
+                    }],
                 },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(sampler),
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: super::SWAPCHAIN_FORMAT,
+                        blend: Some(blend.blend_state()),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: None,
+                    ..Default::default()
                 },
-            ],
+                depth_stencil: super::depth_stencil_state(),
+                multisample: super::MULTISAMPLE_STATE,
+                multiview: None,
+            })
+        })
+    }
+
+    /// The pipeline variant drawing with the given blend mode.
+    pub fn pipeline(&self, blend: BlendMode) -> &wgpu::RenderPipeline {
+        &self.pipelines[blend as usize]
+    }
+
+    /// Create a buffer with room for `capacity` instances,
+    /// to be filled with [`wgpu::Queue::write_buffer`].
+    pub fn create_instance_buffer(
+        device: &wgpu::Device,
+        label: &str,
+        capacity: usize,
+    ) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: (capacity * std::mem::size_of::<QuadInstance>()) as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
         })
     }
 }
 
 pub struct VertexColorPipeline {
     pub pipeline: wgpu::RenderPipeline,
+    pub time: TimeUniform,
+    pipeline_layout: wgpu::PipelineLayout,
 }
 
 #[repr(C)]
@@ -164,23 +772,55 @@ pub struct ColoredVertex {
 
 impl VertexColorPipeline {
     pub fn new(device: &wgpu::Device) -> Self {
-        let label = Some("vertex colors");
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label,
-            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!(
-                "./shaders/vert_colors.wgsl"
-            ))),
+        // no other bind groups, so the time goes first
+        let time = TimeUniform::new(
+            device,
+            "vertex colors global time",
+            wgpu::ShaderStages::VERTEX,
+            0,
+        );
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("vertex colors"),
+            bind_group_layouts: &time.with_bind_group_layout(&[]),
+            push_constant_ranges: &time.push_constant_ranges(),
         });
+        let pipeline = Self::create_pipeline(
+            device,
+            &pipeline_layout,
+            &Shader::VertColors.embedded_source(device),
+        );
 
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        Self {
+            pipeline,
+            time,
+            pipeline_layout,
+        }
+    }
+
+    /// Recompile the pipeline from new shader source.
+    /// On error the old pipeline is kept.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reload(&mut self, device: &wgpu::Device, source: &str) -> Result<(), wgpu::Error> {
+        self.pipeline = catch_validation_error(device, || {
+            Self::create_pipeline(device, &self.pipeline_layout, source)
+        })?;
+        Ok(())
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        source: &str,
+    ) -> wgpu::RenderPipeline {
+        let label = Some("vertex colors");
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label,
-            bind_group_layouts: &[],
-            push_constant_ranges: &[],
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
         });
 
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label,
-            layout: Some(&pipeline_layout),
+            layout: Some(layout),
             vertex: wgpu::VertexState {
                 module: &shader,
                 entry_point: "vs_main",
@@ -218,95 +858,259 @@ impl VertexColorPipeline {
                 cull_mode: None,
                 ..Default::default()
             },
-            depth_stencil: None,
+            depth_stencil: super::depth_stencil_state(),
             multisample: super::MULTISAMPLE_STATE,
             multiview: None,
-        });
-
-        Self { pipeline }
+        })
     }
 }
 
-pub struct PostprocessPipeline {
+/// Draws thick antialiased lines tessellated by [`super::lines`].
+pub struct LinePipeline {
     pub pipeline: wgpu::RenderPipeline,
-    pub gbuf_bind_group_layout: wgpu::BindGroupLayout,
-    pub time_buffer: wgpu::Buffer,
-    pub time_bind_group: wgpu::BindGroup,
+    pub time: TimeUniform,
+    pipeline_layout: wgpu::PipelineLayout,
 }
 
-impl PostprocessPipeline {
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LineVertex {
+    pub pos: [f32; 3],
+    pub col: [f32; 4],
+    /// Which side of the line this vertex is on, -1 or 1.
+    pub edge: f32,
+}
+
+impl LinePipeline {
     pub fn new(device: &wgpu::Device) -> Self {
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("postprocess"),
-            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!(
-                "./shaders/postprocess.wgsl"
-            ))),
+        // no other bind groups, so the time goes first
+        let time = TimeUniform::new(device, "lines global time", wgpu::ShaderStages::VERTEX, 0);
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("lines"),
+            bind_group_layouts: &time.with_bind_group_layout(&[]),
+            push_constant_ranges: &time.push_constant_ranges(),
         });
+        let pipeline = Self::create_pipeline(
+            device,
+            &pipeline_layout,
+            &Shader::Lines.embedded_source(device),
+        );
 
-        let gbuf_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("postprocess gbuffer binding"),
-                entries: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Texture {
-                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                            view_dimension: wgpu::TextureViewDimension::D2,
-                            multisampled: false,
-                        },
-                        count: None,
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                        count: None,
-                    },
-                ],
-            });
+        Self {
+            pipeline,
+            time,
+            pipeline_layout,
+        }
+    }
 
-        let time_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("global time"),
-            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
-            // on webgl, buffers must be 16 byte aligned
-            size: 16,
-            mapped_at_creation: false,
+    /// Recompile the pipeline from new shader source.
+    /// On error the old pipeline is kept.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reload(&mut self, device: &wgpu::Device, source: &str) -> Result<(), wgpu::Error> {
+        self.pipeline = catch_validation_error(device, || {
+            Self::create_pipeline(device, &self.pipeline_layout, source)
+        })?;
+        Ok(())
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        source: &str,
+    ) -> wgpu::RenderPipeline {
+        let label = Some("lines");
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label,
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
         });
 
-        let time_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("postprocess uniforms"),
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        min_binding_size: wgpu::BufferSize::new(16),
-                        has_dynamic_offset: false,
-                    },
-                    count: None,
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label,
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<LineVertex>() as u64,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![
+                        0 => Float32x3,
+                        1 => Float32x4,
+                        2 => Float32,
+                    ],
                 }],
-            });
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: super::SWAPCHAIN_FORMAT,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: super::depth_stencil_state(),
+            multisample: super::MULTISAMPLE_STATE,
+            multiview: None,
+        })
+    }
+}
 
-        let time_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("postprocess uniforms"),
-            layout: &time_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
+/// Fills quads with linear gradients of up to four color stops,
+/// each quad with its own uniform buffer.
+pub struct GradientPipeline {
+    pub pipeline: wgpu::RenderPipeline,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub time: TimeUniform,
+    pipeline_layout: wgpu::PipelineLayout,
+}
+
+/// A gradient-filled rectangle drawn by [`GradientPipeline`].
+#[derive(Clone, Debug)]
+pub struct Gradient {
+    /// Bottom left corner in clip space.
+    pub min: [f32; 2],
+    /// Top right corner in clip space.
+    pub max: [f32; 2],
+    /// Where along the rectangle the gradient starts and ends,
+    /// (0, 0) being the bottom left corner and (1, 1) the top right.
+    pub start: [f32; 2],
+    pub end: [f32; 2],
+    /// Positions between 0 (start) and 1 (end) with linear RGBA colors, 2 to 4 of them.
+    pub stops: Vec<(f32, [f32; 4])>,
+    pub depth: f32,
+}
+
+// matches the uniform struct in gradient.wgsl
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct GradientUniforms {
+    rect: [f32; 4],
+    axis: [f32; 4],
+    stops: [f32; 4],
+    colors: [[f32; 4]; 4],
+    depth: f32,
+    stop_count: u32,
+    _pad: [f32; 2],
+}
+
+impl Gradient {
+    fn uniforms(&self) -> GradientUniforms {
+        assert!(
+            (2..=4).contains(&self.stops.len()),
+            "Gradients must have 2 to 4 stops"
+        );
+        let mut stops = [0.; 4];
+        let mut colors = [[0.; 4]; 4];
+        for (idx, (stop, color)) in self.stops.iter().enumerate() {
+            stops[idx] = *stop;
+            colors[idx] = *color;
+        }
+        GradientUniforms {
+            rect: [self.min[0], self.min[1], self.max[0], self.max[1]],
+            axis: [self.start[0], self.start[1], self.end[0], self.end[1]],
+            stops,
+            colors,
+            depth: self.depth,
+            stop_count: self.stops.len() as u32,
+            _pad: [0.; 2],
+        }
+    }
+}
+
+/// GPU resources for drawing one [`Gradient`].
+pub struct GradientQuad {
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl GradientQuad {
+    /// Change the gradient's shape or colors.
+    pub fn update(&self, queue: &wgpu::Queue, gradient: &Gradient) {
+        queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(&gradient.uniforms()));
+    }
+
+    /// Draw the gradient, expecting the gradient pipeline to be set already.
+    pub fn draw<'pass>(&'pass self, pass: &mut wgpu::RenderPass<'pass>) {
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.draw(0..6, 0..1);
+    }
+}
+
+impl GradientPipeline {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("gradient"),
+            entries: &[wgpu::BindGroupLayoutEntry {
                 binding: 0,
-                resource: wgpu::BindingResource::Buffer(time_buffer.as_entire_buffer_binding()),
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    min_binding_size: wgpu::BufferSize::new(
+                        std::mem::size_of::<GradientUniforms>() as u64,
+                    ),
+                    has_dynamic_offset: false,
+                },
+                count: None,
             }],
         });
 
+        let time = TimeUniform::new(
+            device,
+            "gradient global time",
+            wgpu::ShaderStages::VERTEX,
+            1,
+        );
+
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("postprocess"),
-            bind_group_layouts: &[&gbuf_bind_group_layout, &time_bind_group_layout],
-            push_constant_ranges: &[],
+            label: Some("gradient"),
+            bind_group_layouts: &time.with_bind_group_layout(&[&bind_group_layout]),
+            push_constant_ranges: &time.push_constant_ranges(),
         });
+        let pipeline = Self::create_pipeline(
+            device,
+            &pipeline_layout,
+            &Shader::Gradient.embedded_source(device),
+        );
 
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("postprocess"),
-            layout: Some(&pipeline_layout),
+        Self {
+            pipeline,
+            bind_group_layout,
+            time,
+            pipeline_layout,
+        }
+    }
+
+    /// Recompile the pipeline from new shader source.
+    /// On error the old pipeline is kept.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reload(&mut self, device: &wgpu::Device, source: &str) -> Result<(), wgpu::Error> {
+        self.pipeline = catch_validation_error(device, || {
+            Self::create_pipeline(device, &self.pipeline_layout, source)
+        })?;
+        Ok(())
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        source: &str,
+    ) -> wgpu::RenderPipeline {
+        let label = Some("gradient");
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label,
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label,
+            layout: Some(layout),
             vertex: wgpu::VertexState {
                 module: &shader,
                 entry_point: "vs_main",
@@ -327,26 +1131,131 @@ impl PostprocessPipeline {
                 cull_mode: None,
                 ..Default::default()
             },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
+            depth_stencil: super::depth_stencil_state(),
+            multisample: super::MULTISAMPLE_STATE,
             multiview: None,
+        })
+    }
+
+    /// Create the buffer and bind group for drawing a gradient.
+    pub fn create_quad(
+        &self,
+        device: &wgpu::Device,
+        label: &str,
+        gradient: &Gradient,
+    ) -> GradientQuad {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(label),
+            contents: bytemuck::bytes_of(&gradient.uniforms()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(label),
+            layout: &self.bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+        GradientQuad { buffer, bind_group }
+    }
+}
+
+pub struct PostprocessPipeline {
+    pub pipeline: wgpu::RenderPipeline,
+    pub gbuf_bind_group_layout: wgpu::BindGroupLayout,
+    pub time: TimeUniform,
+    // color multiplied onto the picture, bound along with the gbuffer
+    tint: wgpu::Buffer,
+    pipeline_layout: wgpu::PipelineLayout,
+}
+
+impl PostprocessPipeline {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let [texture_entry, sampler_entry] = fullscreen::input_layout_entries();
+        let gbuf_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("postprocess gbuffer binding"),
+                entries: &[
+                    texture_entry,
+                    sampler_entry,
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            min_binding_size: wgpu::BufferSize::new(16),
+                            has_dynamic_offset: false,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+        let tint = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("postprocess tint"),
+            contents: bytemuck::bytes_of(&[1f32; 4]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let time = TimeUniform::new(
+            device,
+            "postprocess global time",
+            wgpu::ShaderStages::FRAGMENT,
+            1,
+        );
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("postprocess"),
+            bind_group_layouts: &time.with_bind_group_layout(&[&gbuf_bind_group_layout]),
+            push_constant_ranges: &time.push_constant_ranges(),
+        });
+        let pipeline = Self::create_pipeline(
+            device,
+            &pipeline_layout,
+            &Shader::Postprocess.embedded_source(device),
+        );
 
         Self {
             pipeline,
             gbuf_bind_group_layout,
-            time_buffer,
-            time_bind_group,
+            time,
+            tint,
+            pipeline_layout,
         }
     }
 
-    pub fn upload_time(&self, queue: &wgpu::Queue, t: f32) {
-        // pad to 16 bytes
-        queue.write_buffer(&self.time_buffer, 0, bytemuck::cast_slice(&[t, 0., 0., 0.]));
+    /// Set the linear color multiplied onto the whole picture, white for no change.
+    pub fn set_tint(&self, queue: &wgpu::Queue, tint: [f32; 3]) {
+        let [r, g, b] = tint;
+        queue.write_buffer(&self.tint, 0, bytemuck::bytes_of(&[r, g, b, 1.]));
+    }
+
+    /// Recompile the pipeline from new shader source.
+    /// On error the old pipeline is kept.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reload(&mut self, device: &wgpu::Device, source: &str) -> Result<(), wgpu::Error> {
+        self.pipeline = catch_validation_error(device, || {
+            Self::create_pipeline(device, &self.pipeline_layout, source)
+        })?;
+        Ok(())
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        source: &str,
+    ) -> wgpu::RenderPipeline {
+        fullscreen::create_pipeline(
+            device,
+            "postprocess",
+            layout,
+            source,
+            wgpu::ColorTargetState {
+                format: super::SWAPCHAIN_FORMAT,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            },
+        )
     }
 
     /// Create a bind group with a texture and a sampler
@@ -357,17 +1266,16 @@ impl PostprocessPipeline {
         view: &wgpu::TextureView,
         sampler: &wgpu::Sampler,
     ) -> wgpu::BindGroup {
+        let [texture_entry, sampler_entry] = fullscreen::input_entries(view, sampler);
         device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: None,
+            label: Some("postprocess gbuffer"),
             layout: &self.gbuf_bind_group_layout,
             entries: &[
+                texture_entry,
+                sampler_entry,
                 wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(sampler),
+                    binding: 2,
+                    resource: self.tint.as_entire_binding(),
                 },
             ],
         })
@@ -0,0 +1,201 @@
+//! A tiny preprocessor for WGSL sources.
+//!
+//! Supported directives, each on its own line:
+//! - `#include "path/to/file.wgsl"` pastes the contents of another file in place.
+//!   Every file is only included once, so shared files can include each other freely.
+//! - `#define NAME value` replaces every later occurrence of the identifier `NAME` with `value`.
+//! - `#ifdef NAME`, `#ifndef NAME`, `#else` and `#endif` include lines only if `NAME` is
+//!   (or isn't) defined, either with `#define` or by the caller, e.g. for optional GPU features.
+//!
+//! This file only depends on std so that the build script can use it too.
+//! The binary and the build script each only use some of the functions here.
+#![allow(dead_code)]
+
+use std::{collections::HashSet, fmt};
+
+#[derive(Clone, Debug)]
+pub struct PreprocessError {
+    pub file: String,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for PreprocessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.message)
+    }
+}
+
+impl std::error::Error for PreprocessError {}
+
+/// Expand all the directives in a shader.
+///
+/// `flags` are names that count as defined from the start, for use with `#ifdef`.
+/// `load` is called to get the contents of included files
+/// by their path relative to the shader directory.
+pub fn preprocess(
+    file_name: &str,
+    source: &str,
+    flags: &[&str],
+    load: &mut dyn FnMut(&str) -> Result<String, String>,
+) -> Result<String, PreprocessError> {
+    preprocess_with_origins(file_name, source, flags, load).map(|p| p.source)
+}
+
+/// Output of the preprocessor along with
+/// the file and line number each line of it came from.
+pub struct Preprocessed {
+    pub source: String,
+    /// File name and 1-based line number for every line in `source`.
+    pub origins: Vec<(String, usize)>,
+}
+
+impl Preprocessed {
+    /// Find where a 1-based line number in the output came from.
+    pub fn origin(&self, line: usize) -> Option<(&str, usize)> {
+        let (file, line) = self.origins.get(line.checked_sub(1)?)?;
+        Some((file, *line))
+    }
+}
+
+/// Same as [`preprocess`], but keep track of where each line came from
+/// so errors in the output can be traced back to the original files.
+pub fn preprocess_with_origins(
+    file_name: &str,
+    source: &str,
+    flags: &[&str],
+    load: &mut dyn FnMut(&str) -> Result<String, String>,
+) -> Result<Preprocessed, PreprocessError> {
+    let mut state = State {
+        load,
+        included: HashSet::new(),
+        flags: flags.iter().map(|f| f.to_string()).collect(),
+        defines: Vec::new(),
+        output: String::with_capacity(source.len()),
+        origins: Vec::new(),
+    };
+    state.included.insert(file_name.to_string());
+    state.process(file_name, source)?;
+    Ok(Preprocessed {
+        source: state.output,
+        origins: state.origins,
+    })
+}
+
+struct State<'a> {
+    load: &'a mut dyn FnMut(&str) -> Result<String, String>,
+    included: HashSet<String>,
+    flags: HashSet<String>,
+    defines: Vec<(String, String)>,
+    output: String,
+    origins: Vec<(String, usize)>,
+}
+
+impl<'a> State<'a> {
+    fn process(&mut self, file_name: &str, source: &str) -> Result<(), PreprocessError> {
+        // whether each enclosing #ifdef/#ifndef block is active, innermost last.
+        // conditionals don't carry over into or out of included files
+        let mut conditions: Vec<bool> = Vec::new();
+        let mut last_line = 0;
+
+        for (line_idx, line) in source.lines().enumerate() {
+            last_line = line_idx + 1;
+            let err = |message: String| PreprocessError {
+                file: file_name.to_string(),
+                line: line_idx + 1,
+                message,
+            };
+
+            let trimmed = line.trim_start();
+            let active = conditions.iter().all(|&c| c);
+            if let Some((rest, want_defined)) = trimmed
+                .strip_prefix("#ifdef")
+                .map(|r| (r, true))
+                .or_else(|| trimmed.strip_prefix("#ifndef").map(|r| (r, false)))
+            {
+                let name = rest.trim();
+                if name.is_empty() {
+                    return Err(err("expected a name after #ifdef".into()));
+                }
+                conditions.push(self.is_defined(name) == want_defined);
+            } else if trimmed.starts_with("#else") {
+                let cond = conditions
+                    .last_mut()
+                    .ok_or_else(|| err("#else without #ifdef".into()))?;
+                *cond = !*cond;
+            } else if trimmed.starts_with("#endif") {
+                conditions
+                    .pop()
+                    .ok_or_else(|| err("#endif without #ifdef".into()))?;
+            } else if !active {
+                continue;
+            } else if let Some(rest) = trimmed.strip_prefix("#include") {
+                let path = rest
+                    .trim()
+                    .strip_prefix('"')
+                    .and_then(|r| r.strip_suffix('"'))
+                    .ok_or_else(|| err("expected a quoted path after #include".into()))?;
+                if self.included.insert(path.to_string()) {
+                    let included_source = (self.load)(path)
+                        .map_err(|e| err(format!("failed to include {path}: {e}")))?;
+                    self.process(path, &included_source)?;
+                }
+            } else if let Some(rest) = trimmed.strip_prefix("#define") {
+                let mut parts = rest.trim().splitn(2, char::is_whitespace);
+                let name = parts
+                    .next()
+                    .filter(|n| !n.is_empty() && n.chars().all(is_ident_char))
+                    .ok_or_else(|| err("expected a name after #define".into()))?;
+                let value = parts.next().unwrap_or("").trim();
+                self.defines.push((name.to_string(), value.to_string()));
+            } else if trimmed.starts_with('#') {
+                return Err(err(format!("unknown directive {trimmed}")));
+            } else {
+                let expanded = self.substitute(line);
+                self.output.push_str(&expanded);
+                self.output.push('\n');
+                self.origins.push((file_name.to_string(), line_idx + 1));
+            }
+        }
+
+        if !conditions.is_empty() {
+            return Err(PreprocessError {
+                file: file_name.to_string(),
+                line: last_line,
+                message: "missing #endif".into(),
+            });
+        }
+        Ok(())
+    }
+
+    fn is_defined(&self, name: &str) -> bool {
+        self.flags.contains(name) || self.defines.iter().any(|(n, _)| n == name)
+    }
+
+    /// Replace defined identifiers in a line, leaving partial matches alone.
+    fn substitute(&self, line: &str) -> String {
+        if self.defines.is_empty() {
+            return line.to_string();
+        }
+
+        let mut out = String::with_capacity(line.len());
+        let mut rest = line;
+        while let Some(start) = rest.find(is_ident_char) {
+            out.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let end = rest.find(|c| !is_ident_char(c)).unwrap_or(rest.len());
+            let ident = &rest[..end];
+            match self.defines.iter().rev().find(|(name, _)| name == ident) {
+                Some((_, value)) => out.push_str(value),
+                None => out.push_str(ident),
+            }
+            rest = &rest[end..];
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
@@ -0,0 +1,130 @@
+//! Remote control over WebSocket, so a phone or another browser can drive the demo
+//! while it's running fullscreen on a machine nobody is sitting at.
+//!
+//! Every text message is a command: the name of an action, e.g. `fire` or `capture`,
+//! a parameter and a value between 0 and 1 for it like in a feed, e.g. `wind 0.7`,
+//! or `state` to get the current settings back as a query string
+//! in the same format the web build reads, e.g. `fire=on&characters=off&recording=off`.
+//!
+//! There's no authentication, so quitting is left out
+//! for anyone on the network not to be able to stop the demo.
+
+use std::{
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, Arc, Mutex},
+};
+
+use tungstenite::Message;
+
+use super::{feed::Param, input::Action};
+
+pub struct RemoteControl {
+    actions: mpsc::Receiver<Action>,
+    params: mpsc::Receiver<(Param, f32)>,
+    state: Arc<Mutex<String>>,
+}
+
+impl RemoteControl {
+    /// Start listening for connections on the given address, e.g. `0.0.0.0:9001`.
+    pub fn start(addr: &str) -> anyhow::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        println!(
+            "Listening for remote control on ws://{}",
+            listener.local_addr()?
+        );
+        let (send_action, actions) = mpsc::channel();
+        let (send_param, params) = mpsc::channel();
+        let state = Arc::new(Mutex::new(String::new()));
+        std::thread::spawn({
+            let state = state.clone();
+            move || {
+                for stream in listener.incoming() {
+                    let Ok(stream) = stream else {
+                        continue;
+                    };
+                    let senders = (send_action.clone(), send_param.clone());
+                    let state = state.clone();
+                    std::thread::spawn(move || {
+                        if let Err(err) = serve(stream, &senders.0, &senders.1, &state) {
+                            eprintln!("Remote control connection closed: {err}");
+                        }
+                    });
+                }
+            }
+        });
+        Ok(Self {
+            actions,
+            params,
+            state,
+        })
+    }
+
+    /// Add the actions received since the last call to `actions`.
+    pub fn poll(&self, actions: &mut Vec<Action>) {
+        actions.extend(self.actions.try_iter());
+    }
+
+    /// Parameter values received since the last call, between 0 and 1.
+    pub fn poll_params(&self) -> Vec<(Param, f32)> {
+        self.params.try_iter().collect()
+    }
+
+    /// Update what's sent back to `state` queries.
+    pub fn set_state(&self, fire: bool, characters: bool, postprocess: bool, recording: bool) {
+        let on_off = |toggle: bool| if toggle { "on" } else { "off" };
+        let state = format!(
+            "fire={}&characters={}&postprocess={}&recording={}",
+            on_off(fire),
+            on_off(characters),
+            on_off(postprocess),
+            on_off(recording),
+        );
+        *self.state.lock().unwrap() = state;
+    }
+}
+
+fn serve(
+    stream: TcpStream,
+    actions: &mpsc::Sender<Action>,
+    params: &mpsc::Sender<(Param, f32)>,
+    state: &Mutex<String>,
+) -> anyhow::Result<()> {
+    let mut socket = tungstenite::accept(stream)?;
+    loop {
+        let command = match socket.read()? {
+            Message::Text(command) => command,
+            Message::Close(_) => return Ok(()),
+            _ => continue,
+        };
+        let command = command.trim();
+        let unknown = || format!("unknown command {command:?}");
+        // the receivers are only gone when the demo is shutting down
+        let reply = match command.split_whitespace().collect::<Vec<_>>()[..] {
+            ["state"] => Some(state.lock().unwrap().clone()),
+            [name] => match Action::from_name(name) {
+                Some(Action::Quit) => Some("quitting isn't allowed remotely".to_string()),
+                Some(action) => {
+                    if actions.send(action).is_err() {
+                        return Ok(());
+                    }
+                    None
+                }
+                None => Some(unknown()),
+            },
+            [name, value] => match (Param::from_name(name), value.parse::<f32>()) {
+                (Some(param), Ok(value)) if value.is_finite() => {
+                    if params.send((param, value.clamp(0., 1.))).is_err() {
+                        return Ok(());
+                    }
+                    None
+                }
+                (Some(_), _) => Some(format!("expected a value between 0 and 1, got {value:?}")),
+                (None, _) => Some(unknown()),
+            },
+            _ => Some(unknown()),
+        };
+        if let Some(reply) = reply {
+            socket.send(Message::Text(reply))?;
+        }
+    }
+}
@@ -0,0 +1,748 @@
+//! Everything in the picture, independent of whether it's drawn into a window or offscreen.
+
+use rand::{rngs::StdRng, SeedableRng};
+use wgpu::util::DeviceExt;
+
+#[cfg(not(target_arch = "wasm32"))]
+use super::{
+    assets,
+    shaders::Shader,
+    textures,
+    theme::Theme,
+    video::{Placement, VideoLayer},
+};
+use super::{
+    atlas::UvRect,
+    embers::Embers,
+    fire::Fire,
+    parallax::{LayerSource, ParallaxLayers},
+    pipelines::{
+        BlendMode, Gradient, GradientPipeline, GradientQuad, InstancedTexturePipeline,
+        LinePipeline, Pipelines, PostprocessPipeline, ReflectionPipeline, TexturePipeline,
+        TimeUniform, UvEffects, VertexColorPipeline, ViewRegion,
+    },
+    textures::{load_texture_prefer_compressed, ColorSpace},
+    triangle_grid::TriangleGrid,
+    DEPTH_FORMAT, MSAA_SAMPLES, SWAPCHAIN_FORMAT,
+};
+
+// optional layers of the characters painting, back to front, with their parallax amounts
+#[cfg(not(target_arch = "wasm32"))]
+const CHARACTER_LAYERS: [(&str, f32); 3] =
+    [("background", 0.2), ("midground", 0.6), ("foreground", 1.)];
+
+// depths of the parts of the picture, between 0 (front) and 1 (back)
+const CHARACTERS_DEPTH: f32 = 0.5;
+const FIRE_DEPTH: f32 = 0.6;
+const EMBERS_DEPTH: f32 = 0.55;
+const FIRE_GLOW_DEPTH: f32 = 0.65;
+
+const FIRE_DT: f64 = 1. / 20.;
+const FIRE_COOLING_RATE: f32 = 1. / 120.;
+const FIRE_BASE_Y: f32 = -0.5;
+// camera pixels darker than this don't heat the fire at all
+#[cfg(not(target_arch = "wasm32"))]
+const CAMERA_FUEL_THRESHOLD: f32 = 0.5;
+
+pub struct Scene {
+    color_pl: VertexColorPipeline,
+    // public for drawing other things like the text overlay with the same pipeline
+    pub tex_pl: TexturePipeline,
+    instanced_pl: InstancedTexturePipeline,
+    reflection_pl: ReflectionPipeline,
+    line_pl: LinePipeline,
+    gradient_pl: GradientPipeline,
+    postprocess_pl: PostprocessPipeline,
+
+    filtering_sampler: wgpu::Sampler,
+    pub nearest_sampler: wgpu::Sampler,
+    screen: ScreenTextures,
+
+    background_grid: TriangleGrid,
+    characters: ParallaxLayers,
+    fire: Fire,
+    fire_tex: wgpu::Texture,
+    fire_bind_group: wgpu::BindGroup,
+    fire_verts: wgpu::Buffer,
+    fire_reflection_bind_group: wgpu::BindGroup,
+    fire_reflection_verts: wgpu::Buffer,
+    fire_glow_quad: GradientQuad,
+    embers: Embers,
+    #[cfg(not(target_arch = "wasm32"))]
+    video: Option<VideoLayer>,
+
+    // all randomness comes from here so that seeded renders are reproducible
+    rng: StdRng,
+    // time accumulated towards the next step of the fire simulation
+    time_in_frame: f64,
+    // how long each fire step took, only measured when benchmarking
+    #[cfg(not(target_arch = "wasm32"))]
+    fire_step_times: Option<Vec<std::time::Duration>>,
+    /// Global time for time-dependent effects.
+    pub t: f64,
+
+    // interactive controls to toggle parts of the picture, just for fun
+    pub draw_characters: bool,
+    pub draw_fire: bool,
+    pub draw_postprocess: bool,
+    /// Multiplier on the brightness of the fire's glow.
+    pub glow: f32,
+    /// Position of the mouse in clip space for the parallax effect.
+    pub mouse_pos: Option<[f32; 2]>,
+}
+
+impl Scene {
+    /// Load everything and prepare to draw into targets of the given size.
+    /// `characters_png` is the painting as loaded with [`crate::assets::load`].
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        characters_png: &[u8],
+        width: u32,
+        height: u32,
+        seed: u64,
+    ) -> anyhow::Result<Self> {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let Pipelines {
+            vert_colors: color_pl,
+            texture: tex_pl,
+            instanced_texture: instanced_pl,
+            reflection: reflection_pl,
+            lines: line_pl,
+            gradient: gradient_pl,
+            postprocess: postprocess_pl,
+        } = Pipelines::new(device);
+
+        let background_grid = TriangleGrid::generate(device, &mut rng);
+
+        let filtering_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("filtering"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let characters =
+            load_characters(device, queue, characters_png, &tex_pl, &filtering_sampler)?;
+
+        let fire = Fire::new(250, 150, FIRE_COOLING_RATE);
+        let fire_tex = fire.create_texture(device);
+        let fire_tex_view = fire_tex.create_view(&wgpu::TextureViewDescriptor::default());
+        let nearest_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("nearest"),
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        let fire_bind_group =
+            tex_pl.create_bind_group(device, "fire", &fire_tex_view, &nearest_sampler);
+
+        // rectangular quad for the fire
+        // height that makes square pixels at 4:3 aspect ratio
+        let fire_height = (2. / fire.width as f32) * fire.height as f32 * 4. / 3.;
+        let fire_top_y = FIRE_BASE_Y + fire_height;
+        let fire_verts = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("fire quad"),
+            contents: bytemuck::cast_slice(&TexturePipeline::quad_vertices(
+                [-1., FIRE_BASE_Y],
+                [1., fire_top_y],
+                FIRE_DEPTH,
+                UvRect::FULL,
+            )),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        // reflection squished to look in perspective and smoothed by a filtering sampler
+        let fire_reflection_bind_group = tex_pl.create_bind_group(
+            device,
+            "fire reflection",
+            &fire_tex_view,
+            &filtering_sampler,
+        );
+
+        let refl_bottom_y = FIRE_BASE_Y - 0.4 * fire_height;
+        let fire_reflection_verts = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("fire reflection quad"),
+            contents: bytemuck::cast_slice(&TexturePipeline::quad_vertices(
+                [-1., FIRE_BASE_Y],
+                [1., refl_bottom_y],
+                FIRE_DEPTH,
+                UvRect::FULL,
+            )),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let fire_glow_quad = gradient_pl.create_quad(device, "fire glow", &fire_glow(1.));
+        let embers = Embers::new(
+            device,
+            queue,
+            &tex_pl,
+            &filtering_sampler,
+            [-0.8, 0.8],
+            fire_top_y - 0.5 * fire_height,
+            EMBERS_DEPTH,
+        );
+
+        let screen =
+            ScreenTextures::new(device, &postprocess_pl, &filtering_sampler, width, height);
+
+        Ok(Self {
+            color_pl,
+            tex_pl,
+            instanced_pl,
+            reflection_pl,
+            line_pl,
+            gradient_pl,
+            postprocess_pl,
+            screen,
+            filtering_sampler,
+            nearest_sampler,
+            background_grid,
+            characters,
+            fire,
+            fire_tex,
+            fire_bind_group,
+            fire_verts,
+            fire_reflection_bind_group,
+            fire_reflection_verts,
+            fire_glow_quad,
+            embers,
+            #[cfg(not(target_arch = "wasm32"))]
+            video: None,
+            rng,
+            time_in_frame: 0.,
+            #[cfg(not(target_arch = "wasm32"))]
+            fire_step_times: None,
+            t: 0.,
+            draw_characters: true,
+            draw_fire: true,
+            draw_postprocess: true,
+            glow: 1.,
+            mouse_pos: None,
+        })
+    }
+
+    /// Start measuring how long each step of the fire simulation takes.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn measure_fire_steps(&mut self) {
+        self.fire_step_times.get_or_insert_with(Vec::new);
+    }
+
+    /// Durations of the fire simulation steps taken since [`Self::measure_fire_steps`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn fire_step_times(&self) -> &[std::time::Duration] {
+        self.fire_step_times.as_deref().unwrap_or_default()
+    }
+
+    /// Load the characters again from the files in the source tree,
+    /// e.g. after they've been exported again from a painting program.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reload_characters(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> anyhow::Result<()> {
+        let characters_png = std::fs::read(assets::source_path("characters.png"))?;
+        self.characters = load_characters(
+            device,
+            queue,
+            &characters_png,
+            &self.tex_pl,
+            &self.filtering_sampler,
+        )?;
+        Ok(())
+    }
+
+    /// Replace the characters painting with another image,
+    /// e.g. to preview how other artwork looks in the same frame.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn replace_characters(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image_bytes: &[u8],
+    ) -> anyhow::Result<()> {
+        let texture =
+            textures::load_texture(device, queue, "characters", image_bytes, ColorSpace::Srgb)?;
+        self.characters = ParallaxLayers::new(
+            device,
+            &self.tex_pl,
+            &self.filtering_sampler,
+            vec![LayerSource {
+                name: "characters",
+                texture,
+                parallax: 0.,
+            }],
+            CHARACTERS_DEPTH,
+            characters_effects(),
+        );
+        Ok(())
+    }
+
+    /// Play a video file in a loop as a layer of the picture.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn add_video(
+        &mut self,
+        device: &wgpu::Device,
+        path: &std::path::Path,
+        placement: Placement,
+    ) -> anyhow::Result<()> {
+        self.video = Some(VideoLayer::new(
+            path,
+            placement,
+            device,
+            &self.tex_pl,
+            &self.filtering_sampler,
+        )?);
+        Ok(())
+    }
+
+    /// Blow the fire sideways, from 0 for still air to 1 for a strong wind to the right.
+    /// Negative values blow to the left.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_wind(&mut self, wind: f32) {
+        self.fire.wind = wind.clamp(-1., 1.);
+    }
+
+    /// Make the fire burn lower or taller, from 0 to 1 with the default height at 0.5.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_heat(&mut self, heat: f32) {
+        self.fire.cooling_rate = FIRE_COOLING_RATE * 2f32.powf(1. - 2. * heat.clamp(0., 1.));
+    }
+
+    /// Recolor the background, the fire and the whole picture with a theme.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_theme(&mut self, queue: &wgpu::Queue, theme: &Theme) {
+        self.background_grid
+            .set_palette(&theme.grid.map(|c| c.into_linear()));
+        self.fire.set_palette(&theme.fire.map(|c| c.into_linear()));
+        let tint: palette::LinSrgb = theme.tint.into_linear();
+        self.postprocess_pl
+            .set_tint(queue, [tint.red, tint.green, tint.blue]);
+    }
+
+    /// Make the bright parts of a camera image burn in the fire,
+    /// mirrored so that people in front of the camera see themselves like in a mirror.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn burn_into_fire(&mut self, frame: &image::RgbImage) {
+        let luma = image::imageops::grayscale(frame);
+        let small = image::imageops::resize(
+            &luma,
+            self.fire.width as u32,
+            self.fire.height as u32,
+            image::imageops::FilterType::Triangle,
+        );
+        let small = image::imageops::flip_horizontal(&small);
+        let fuel = small
+            .pixels()
+            .map(|p| {
+                let brightness = p.0[0] as f32 / 255.;
+                ((brightness - CAMERA_FUEL_THRESHOLD) / (1. - CAMERA_FUEL_THRESHOLD)).clamp(0., 1.)
+            })
+            .collect();
+        self.fire.set_fuel(Some(fuel));
+    }
+
+    /// Recreate the size-dependent textures to draw into targets of a new size.
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.screen = ScreenTextures::new(
+            device,
+            &self.postprocess_pl,
+            &self.filtering_sampler,
+            width,
+            height,
+        );
+    }
+
+    /// Draw only the given part of the picture into the render target from now on,
+    /// [`ViewRegion::FULL`] to go back to drawing the whole thing.
+    pub fn set_view(&mut self, queue: &wgpu::Queue, view: ViewRegion) {
+        for time in self.time_uniforms() {
+            time.set_view(queue, view);
+        }
+    }
+
+    // the instanced and reflection pipelines use the texture pipeline's
+    fn time_uniforms(&mut self) -> [&mut TimeUniform; 5] {
+        [
+            &mut self.color_pl.time,
+            &mut self.tex_pl.time,
+            &mut self.line_pl.time,
+            &mut self.gradient_pl.time,
+            &mut self.postprocess_pl.time,
+        ]
+    }
+
+    /// Replace the pipelines using a shader with ones compiled from new source.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reload_shader(
+        &mut self,
+        device: &wgpu::Device,
+        shader: Shader,
+        source: &str,
+    ) -> Result<(), wgpu::Error> {
+        match shader {
+            Shader::Textured => self.tex_pl.reload(device, source),
+            Shader::TexturedInstanced => self.instanced_pl.reload(device, source),
+            Shader::VertColors => self.color_pl.reload(device, source),
+            Shader::Lines => self.line_pl.reload(device, source),
+            Shader::Gradient => self.gradient_pl.reload(device, source),
+            Shader::Reflection => self.reflection_pl.reload(device, source),
+            Shader::Postprocess => self.postprocess_pl.reload(device, source),
+        }
+    }
+
+    /// Move everything forward in time by `dt` seconds and upload the changes to the GPU.
+    pub fn update(&mut self, queue: &wgpu::Queue, dt: f64) {
+        self.t += dt;
+        let t = self.t as f32;
+
+        // simulate fire
+        self.time_in_frame += dt;
+        let mut fire_updated = false;
+        // limit maximum steps per frame to avoid spiral of death
+        for _ in 0..4 {
+            if self.time_in_frame < FIRE_DT {
+                break;
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            let step_start = std::time::Instant::now();
+            self.fire.propagate(&mut self.rng);
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(times) = &mut self.fire_step_times {
+                times.push(step_start.elapsed());
+            }
+            fire_updated = true;
+            self.time_in_frame -= FIRE_DT;
+        }
+        if fire_updated {
+            self.fire.write_texture(queue, &self.fire_tex);
+        }
+
+        for time in self.time_uniforms() {
+            time.update(queue, t);
+        }
+
+        self.background_grid.update(queue, t);
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(video) = &self.video {
+            video.update(queue);
+        }
+        if self.draw_fire {
+            self.embers.update(queue, dt as f32, &mut self.rng);
+            // flicker along with the fire
+            let flicker = 0.85 + 0.1 * (7. * t).sin() + 0.05 * (17. * t).sin();
+            self.fire_glow_quad
+                .update(queue, &fire_glow(self.glow * flicker));
+        }
+        self.characters.update(queue, t, self.mouse_pos);
+    }
+
+    /// Draw the picture into `target`, which must be the size given in `new` or `resize`
+    /// and have the format [`SWAPCHAIN_FORMAT`].
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, target: &wgpu::TextureView) {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("main"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: if self.draw_postprocess {
+                    &self.screen.gbuf_view
+                } else {
+                    target
+                },
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: self.screen.depth_attachment(),
+            ..Default::default()
+        });
+
+        pass.set_pipeline(&self.color_pl.pipeline);
+        self.color_pl.time.bind(&mut pass);
+        pass.set_vertex_buffer(0, self.background_grid.vertex_buf.slice(..));
+        pass.draw(0..self.background_grid.vertex_count, 0..1);
+
+        // drawn early, the depth buffer puts it in its place
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(video) = &self.video {
+            pass.set_pipeline(self.tex_pl.pipeline(BlendMode::Alpha));
+            self.tex_pl.time.bind(&mut pass);
+            video.draw(&mut pass);
+        }
+
+        if self.draw_fire {
+            pass.set_pipeline(&self.gradient_pl.pipeline);
+            self.gradient_pl.time.bind(&mut pass);
+            self.fire_glow_quad.draw(&mut pass);
+        }
+
+        if self.draw_fire {
+            // fire is light, so it brightens what's under it
+            pass.set_pipeline(self.tex_pl.pipeline(BlendMode::Additive));
+            self.tex_pl.time.bind(&mut pass);
+            pass.set_bind_group(0, &self.fire_bind_group, &[]);
+            pass.set_vertex_buffer(0, self.fire_verts.slice(..));
+            pass.draw(0..6, 0..1);
+
+            pass.set_pipeline(&self.reflection_pl.pipeline);
+            self.tex_pl.time.bind(&mut pass);
+            pass.set_bind_group(0, &self.fire_reflection_bind_group, &[]);
+            pass.set_vertex_buffer(0, self.fire_reflection_verts.slice(..));
+            pass.draw(0..6, 0..1);
+        }
+
+        if self.draw_characters {
+            pass.set_pipeline(self.tex_pl.pipeline(BlendMode::Alpha));
+            self.tex_pl.time.bind(&mut pass);
+            self.characters.draw(&mut pass);
+        }
+
+        if self.draw_fire {
+            pass.set_pipeline(&self.line_pl.pipeline);
+            self.line_pl.time.bind(&mut pass);
+            self.embers.draw_trails(&mut pass);
+            pass.set_pipeline(self.instanced_pl.pipeline(BlendMode::Additive));
+            self.tex_pl.time.bind(&mut pass);
+            self.embers.draw(&mut pass);
+        }
+
+        drop(pass);
+
+        // postprocessing pass
+
+        if self.draw_postprocess {
+            let mut postprocess_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("postprocess"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                ..Default::default()
+            });
+
+            postprocess_pass.set_pipeline(&self.postprocess_pl.pipeline);
+            postprocess_pass.set_bind_group(0, &self.screen.gbuf_bind_group, &[]);
+            self.postprocess_pl.time.bind(&mut postprocess_pass);
+            postprocess_pass.draw(0..3, 0..1);
+        }
+    }
+
+    /// Begin a pass on top of an already rendered `target`
+    /// with the alpha-blended texture pipeline set,
+    /// for drawing things that shouldn't be affected by postprocessing.
+    pub fn begin_pass_on_top<'a>(
+        &'a self,
+        encoder: &'a mut wgpu::CommandEncoder,
+        target: &'a wgpu::TextureView,
+    ) -> wgpu::RenderPass<'a> {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("on top"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: self.screen.depth_attachment(),
+            ..Default::default()
+        });
+        pass.set_pipeline(self.tex_pl.pipeline(BlendMode::Alpha));
+        self.tex_pl.time.bind(&mut pass);
+        pass
+    }
+}
+
+/// Intermediate render targets that depend on the size of the final target.
+struct ScreenTextures {
+    // main image is drawn into a gbuffer for postprocessing
+    _gbuffer: wgpu::Texture,
+    gbuf_view: wgpu::TextureView,
+    gbuf_bind_group: wgpu::BindGroup,
+    _depth_buffer: Option<wgpu::Texture>,
+    depth_view: Option<wgpu::TextureView>,
+}
+
+impl ScreenTextures {
+    fn new(
+        device: &wgpu::Device,
+        postprocess_pl: &PostprocessPipeline,
+        sampler: &wgpu::Sampler,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let gbuffer = create_screen_texture(device, width, height);
+        let gbuf_view = gbuffer.create_view(&wgpu::TextureViewDescriptor::default());
+        let gbuf_bind_group = postprocess_pl.create_bind_group(device, &gbuf_view, sampler);
+        let depth_buffer =
+            DEPTH_FORMAT.map(|format| create_depth_texture(device, width, height, format));
+        let depth_view = depth_buffer
+            .as_ref()
+            .map(|tex| tex.create_view(&wgpu::TextureViewDescriptor::default()));
+        Self {
+            _gbuffer: gbuffer,
+            gbuf_view,
+            gbuf_bind_group,
+            _depth_buffer: depth_buffer,
+            depth_view,
+        }
+    }
+
+    // every pass drawing with depth-tested pipelines starts from a clear depth buffer
+    fn depth_attachment(&self) -> Option<wgpu::RenderPassDepthStencilAttachment<'_>> {
+        self.depth_view
+            .as_ref()
+            .map(|view| wgpu::RenderPassDepthStencilAttachment {
+                view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.),
+                    store: wgpu::StoreOp::Discard,
+                }),
+                stencil_ops: None,
+            })
+    }
+}
+
+/// Load the characters painting, split into layers if the layer files exist.
+fn load_characters(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    characters_png: &[u8],
+    tex_pl: &TexturePipeline,
+    sampler: &wgpu::Sampler,
+) -> anyhow::Result<ParallaxLayers> {
+    // a BC7 or ETC2 compressed version of the characters can be put next to the png
+    // to save VRAM; it's not bundled into the binary because webgl can't use it anyway
+    #[cfg(not(target_arch = "wasm32"))]
+    let characters_ktx2 = read_optional(&assets::source_path("characters.ktx2"));
+    #[cfg(target_arch = "wasm32")]
+    let characters_ktx2: Option<Vec<u8>> = None;
+    let characters_tex = load_texture_prefer_compressed(
+        device,
+        queue,
+        "characters",
+        characters_ktx2.as_deref(),
+        characters_png,
+        ColorSpace::Srgb,
+    )?;
+
+    // the painting can also be split into layers that move independently.
+    // these are optional files, without them the whole painting stays still
+    #[cfg(not(target_arch = "wasm32"))]
+    let character_layers: Option<Vec<LayerSource>> = CHARACTER_LAYERS
+        .iter()
+        .map(|&(name, parallax)| {
+            let bytes = read_optional(&assets::source_path(&format!("characters/{name}.png")))?;
+            match textures::load_texture(device, queue, name, &bytes, ColorSpace::Srgb) {
+                Ok(texture) => Some(LayerSource {
+                    name,
+                    texture,
+                    parallax,
+                }),
+                Err(err) => {
+                    eprintln!("Failed to load character layer {name}: {err}");
+                    None
+                }
+            }
+        })
+        .collect();
+    #[cfg(target_arch = "wasm32")]
+    let character_layers: Option<Vec<LayerSource>> = None;
+    Ok(ParallaxLayers::new(
+        device,
+        tex_pl,
+        sampler,
+        character_layers.unwrap_or_else(|| {
+            vec![LayerSource {
+                name: "characters",
+                texture: characters_tex,
+                parallax: 0.,
+            }]
+        }),
+        CHARACTERS_DEPTH,
+        characters_effects(),
+    ))
+}
+
+/// Read a file that's fine to leave out, printing any error other than it not existing.
+#[cfg(not(target_arch = "wasm32"))]
+fn read_optional(path: &std::path::Path) -> Option<Vec<u8>> {
+    match std::fs::read(path) {
+        Ok(bytes) => Some(bytes),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => {
+            eprintln!("Failed to read {}: {err}", path.display());
+            None
+        }
+    }
+}
+
+// barely noticeable, like the characters are breathing
+fn characters_effects() -> UvEffects {
+    UvEffects {
+        breathe: 0.006,
+        ..Default::default()
+    }
+}
+
+// warm light from the fire on the water below it
+fn fire_glow(strength: f32) -> Gradient {
+    Gradient {
+        min: [-1., -1.],
+        max: [1., FIRE_BASE_Y],
+        start: [0., 1.],
+        end: [0., 0.],
+        stops: vec![
+            (0., [0.9, 0.35, 0.08, 0.18 * strength]),
+            (0.4, [0.6, 0.15, 0.05, 0.06 * strength]),
+            (1., [0., 0., 0., 0.]),
+        ],
+        depth: FIRE_GLOW_DEPTH,
+    }
+}
+
+fn create_screen_texture(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("gbuffer"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: SWAPCHAIN_FORMAT,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    })
+}
+
+fn create_depth_texture(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("depth"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: MSAA_SAMPLES,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    })
+}
@@ -0,0 +1,197 @@
+use super::preprocess::preprocess;
+
+/// All the WGSL shaders used by the demo.
+///
+/// Sources are embedded in the binary at compile time,
+/// but on native builds they can also be reloaded from disk while the demo is running.
+/// Either way they're run through the [preprocessor][super::preprocess]
+/// so they can `#include` shared code from `shaders/common`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Shader {
+    Textured,
+    TexturedInstanced,
+    VertColors,
+    Lines,
+    Gradient,
+    Reflection,
+    Postprocess,
+}
+
+impl Shader {
+    pub const ALL: [Shader; 7] = [
+        Shader::Textured,
+        Shader::TexturedInstanced,
+        Shader::VertColors,
+        Shader::Lines,
+        Shader::Gradient,
+        Shader::Reflection,
+        Shader::Postprocess,
+    ];
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Shader::Textured => "textured.wgsl",
+            Shader::TexturedInstanced => "textured_instanced.wgsl",
+            Shader::VertColors => "vert_colors.wgsl",
+            Shader::Lines => "lines.wgsl",
+            Shader::Gradient => "gradient.wgsl",
+            Shader::Reflection => "reflection.wgsl",
+            Shader::Postprocess => "postprocess.wgsl",
+        }
+    }
+
+    /// The preprocessed version of the source that was compiled into the binary,
+    /// configured for the features of the given device.
+    pub fn embedded_source(self, device: &wgpu::Device) -> String {
+        let source = embedded_file(self.file_name()).unwrap();
+        preprocess(self.file_name(), source, flags(device), &mut |path| {
+            embedded_file(path)
+                .map(str::to_string)
+                .ok_or_else(|| "no such file".to_string())
+        })
+        .expect("Embedded shader failed to preprocess")
+    }
+}
+
+/// Whether small per-frame data like the global time
+/// is passed with push constants instead of uniform buffers.
+/// Push constants aren't available on the web.
+pub fn use_push_constants(device: &wgpu::Device) -> bool {
+    device.features().contains(wgpu::Features::PUSH_CONSTANTS)
+        && device.limits().max_push_constant_size >= PUSH_CONSTANT_SIZE
+}
+
+/// Size of the push constants used by any shader.
+pub const PUSH_CONSTANT_SIZE: u32 = 32;
+
+/// Preprocessor flags for optional features the shaders can use on this device,
+/// checked in the shaders with `#ifdef`.
+fn flags(device: &wgpu::Device) -> &'static [&'static str] {
+    if use_push_constants(device) {
+        &["PUSH_CONSTANTS"]
+    } else {
+        &[]
+    }
+}
+
+/// Get a file from the shader directory as it was at compile time.
+fn embedded_file(path: &str) -> Option<&'static str> {
+    Some(match path {
+        "textured.wgsl" => include_str!("./shaders/textured.wgsl"),
+        "textured_instanced.wgsl" => include_str!("./shaders/textured_instanced.wgsl"),
+        "vert_colors.wgsl" => include_str!("./shaders/vert_colors.wgsl"),
+        "lines.wgsl" => include_str!("./shaders/lines.wgsl"),
+        "gradient.wgsl" => include_str!("./shaders/gradient.wgsl"),
+        "reflection.wgsl" => include_str!("./shaders/reflection.wgsl"),
+        "postprocess.wgsl" => include_str!("./shaders/postprocess.wgsl"),
+        "common/noise.wgsl" => include_str!("./shaders/common/noise.wgsl"),
+        "common/fullscreen.wgsl" => include_str!("./shaders/common/fullscreen.wgsl"),
+        "common/time.wgsl" => include_str!("./shaders/common/time.wgsl"),
+        _ => return None,
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use watcher::ShaderWatcher;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod watcher {
+    use super::Shader;
+    use crate::preprocess::{preprocess, PreprocessError};
+    use notify::Watcher;
+    use std::{collections::BTreeSet, path::PathBuf, sync::mpsc};
+
+    /// Watches the shader source directory for changes.
+    pub struct ShaderWatcher {
+        dir: PathBuf,
+        // kept alive for as long as we want to receive events
+        _watcher: notify::RecommendedWatcher,
+        events: mpsc::Receiver<notify::Result<notify::Event>>,
+    }
+
+    impl ShaderWatcher {
+        /// Start watching the shader directory in the source tree.
+        ///
+        /// Returns `None` if the source tree isn't available,
+        /// e.g. when the binary has been moved to another machine.
+        pub fn new() -> Option<Self> {
+            let dir = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/src/shaders"));
+            if !dir.is_dir() {
+                return None;
+            }
+
+            let (sender, events) = mpsc::channel();
+            let mut watcher = match notify::recommended_watcher(sender) {
+                Ok(w) => w,
+                Err(err) => {
+                    eprintln!("Failed to start shader watcher: {err}");
+                    return None;
+                }
+            };
+            if let Err(err) = watcher.watch(&dir, notify::RecursiveMode::Recursive) {
+                eprintln!("Failed to watch {}: {err}", dir.display());
+                return None;
+            }
+
+            Some(Self {
+                dir,
+                _watcher: watcher,
+                events,
+            })
+        }
+
+        /// Get the shaders whose files have changed since the last call.
+        ///
+        /// A change in any shared file counts as a change in every shader,
+        /// since we don't keep track of who includes what.
+        pub fn changed_shaders(&self) -> BTreeSet<Shader> {
+            let mut changed = BTreeSet::new();
+            let changed_paths = self
+                .events
+                .try_iter()
+                .filter_map(|ev| ev.ok())
+                // editors often save by writing a new file and renaming it over the old one,
+                // so look at every kind of event except plain reads
+                .filter(|ev| !ev.kind.is_access())
+                .flat_map(|ev| ev.paths);
+            for path in changed_paths {
+                if path.extension().and_then(|ext| ext.to_str()) != Some("wgsl") {
+                    continue;
+                }
+                let name = path.file_name().and_then(|n| n.to_str());
+                match Shader::ALL
+                    .into_iter()
+                    .find(|s| Some(s.file_name()) == name)
+                {
+                    Some(shader) if path.parent() == Some(&self.dir) => {
+                        changed.insert(shader);
+                    }
+                    _ => changed.extend(Shader::ALL),
+                }
+            }
+            changed
+        }
+
+        /// Read and preprocess the current version of a shader from disk.
+        pub fn read_source(
+            &self,
+            shader: Shader,
+            device: &wgpu::Device,
+        ) -> Result<String, PreprocessError> {
+            let read = |path: &str| {
+                std::fs::read_to_string(self.dir.join(path)).map_err(|err| err.to_string())
+            };
+            let source = read(shader.file_name()).map_err(|message| PreprocessError {
+                file: shader.file_name().to_string(),
+                line: 0,
+                message,
+            })?;
+            preprocess(
+                shader.file_name(),
+                &source,
+                super::flags(device),
+                &mut |path| read(path),
+            )
+        }
+    }
+}
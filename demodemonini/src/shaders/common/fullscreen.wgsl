@@ -0,0 +1,21 @@
+// vertex shader for effects covering the whole render target.
+// draws a single full-screen triangle using just vertex indices, call with 3 vertices
+// source: https://www.saschawillems.de/blog/2016/08/13/vulkan-tutorial-on-rendering-a-fullscreen-quad-without-buffers/
+// (y flipped for wgpu)
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vert_idx: u32,
+) -> VertexOutput {
+    var out: VertexOutput;
+
+    out.uv = vec2<f32>(f32((vert_idx << 1u) & 2u), f32(vert_idx & 2u));
+    out.clip_position = vec4<f32>(out.uv.x * 2.0 - 1.0, out.uv.y * -2.0 + 1.0, 0., 1.);
+
+    return out;
+}
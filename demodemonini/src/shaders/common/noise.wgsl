@@ -0,0 +1,21 @@
+// cheap hash-based pseudorandom noise,
+// good enough for glitches and flicker
+
+fn noise(x: vec2<f32>) -> f32 {
+    return fract(sin(dot(x, vec2<f32>(12.9898, 78.233))) * 43758.5453);
+}
+
+fn noise_1d(x: f32) -> f32 {
+    return noise(vec2<f32>(x, 0.));
+}
+
+// smoothly varying noise in the range [0, 1] with features about one unit apart,
+// made by interpolating the hash noise above between integer points
+fn value_noise(x: vec2<f32>) -> f32 {
+    let cell = floor(x);
+    let f = fract(x);
+    let smooth_f = f * f * (3. - 2. * f);
+    let bottom = mix(noise(cell), noise(cell + vec2<f32>(1., 0.)), smooth_f.x);
+    let top = mix(noise(cell + vec2<f32>(0., 1.)), noise(cell + vec2<f32>(1., 1.)), smooth_f.x);
+    return mix(bottom, top, smooth_f.y);
+}
@@ -0,0 +1,26 @@
+// global time in seconds and the part of the picture being drawn,
+// see `TimeUniform` and `ViewRegion` in pipelines.rs.
+// without push constants it's in bind group 1, or TIME_GROUP if that's defined first
+
+struct Uniforms {
+    @align(16)
+    t: f32,
+    // scale in xy and offset in zw
+    view: vec4<f32>,
+}
+
+#ifdef PUSH_CONSTANTS
+var<push_constant> unif: Uniforms;
+#else
+#ifndef TIME_GROUP
+#define TIME_GROUP 1
+#endif
+@group(TIME_GROUP) @binding(0)
+var<uniform> unif: Uniforms;
+#endif
+
+// move a position in the whole picture's clip space
+// to where it is in the part of the picture being drawn
+fn to_view(clip: vec2<f32>) -> vec2<f32> {
+    return clip * unif.view.xy + unif.view.zw;
+}
@@ -0,0 +1,61 @@
+struct Gradient {
+    // min corner in xy, max corner in zw, in clip space
+    rect: vec4<f32>,
+    // start point in xy and end point in zw, in coordinates local to the quad
+    // with (0, 0) at the bottom left and (1, 1) at the top right
+    axis: vec4<f32>,
+    // position of each stop along the axis, only the first `stop_count` are used
+    stops: vec4<f32>,
+    colors: array<vec4<f32>, 4>,
+    depth: f32,
+    stop_count: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> grad: Gradient;
+
+#include "common/time.wgsl"
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) local: vec2<f32>,
+}
+
+var<private> CORNERS: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+    vec2<f32>(0., 0.),
+    vec2<f32>(1., 0.),
+    vec2<f32>(1., 1.),
+    vec2<f32>(0., 0.),
+    vec2<f32>(1., 1.),
+    vec2<f32>(0., 1.),
+);
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vert_idx: u32,
+) -> VertexOutput {
+    let corner = CORNERS[vert_idx];
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(to_view(mix(grad.rect.xy, grad.rect.zw, corner)), grad.depth, 1.);
+    out.local = corner;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let axis = grad.axis.zw - grad.axis.xy;
+    let t = dot(in.local - grad.axis.xy, axis) / max(dot(axis, axis), 0.00001);
+
+    var color = grad.colors[0];
+    for (var i = 1u; i < grad.stop_count; i++) {
+        let stop_start = grad.stops[i - 1u];
+        let stop_end = grad.stops[i];
+        let along = clamp((t - stop_start) / max(stop_end - stop_start, 0.00001), 0., 1.);
+        color = mix(color, grad.colors[i], along);
+    }
+    // see textured.wgsl
+    if color.a < 0.01 {
+        discard;
+    }
+    return color;
+}
@@ -0,0 +1,36 @@
+// no other bind groups, so the time goes first
+#define TIME_GROUP 0
+#include "common/time.wgsl"
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    // -1 on one side of the line and 1 on the other
+    @location(1) edge: f32,
+}
+
+@vertex
+fn vs_main(
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) edge: f32,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(to_view(position.xy), position.z, 1.);
+    out.color = color;
+    out.edge = edge;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // fade out over about a pixel at the sides for antialiasing
+    let fade_width = 1.5 * fwidth(in.edge);
+    let coverage = clamp((1. - abs(in.edge)) / max(fade_width, 0.0001), 0., 1.);
+    let alpha = in.color.a * coverage;
+    // see textured.wgsl
+    if alpha < 0.01 {
+        discard;
+    }
+    return vec4<f32>(in.color.rgb, alpha);
+}
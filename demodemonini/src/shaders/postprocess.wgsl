@@ -1,38 +1,17 @@
+#include "common/noise.wgsl"
+#include "common/fullscreen.wgsl"
+#include "common/time.wgsl"
+
 @group(0) @binding(0)
 var gbuf_tex: texture_2d<f32>;
 @group(0) @binding(1)
 var gbuf_samp: sampler;
-
-struct Uniforms {
-    @align(16)
-    t: f32,
-}
-
-@group(1) @binding(0)
-var<uniform> unif: Uniforms;
+// multiplied onto the picture, white unless a theme sets it
+@group(0) @binding(2)
+var<uniform> tint: vec4<f32>;
 
 const PI: f32 = 3.14159;
 
-struct VertexOutput {
-    @builtin(position) clip_position: vec4<f32>,
-    @location(0) uv: vec2<f32>,
-}
-
-// vertex shader draws a single full-screen triangle using just vertex indices
-// source: https://www.saschawillems.de/blog/2016/08/13/vulkan-tutorial-on-rendering-a-fullscreen-quad-without-buffers/
-// (y flipped for wgpu)
-@vertex
-fn vs_main(
-    @builtin(vertex_index) vert_idx: u32,
-) -> VertexOutput {
-    var out: VertexOutput;
-
-    out.uv = vec2<f32>(f32((vert_idx << 1u) & 2u), f32(vert_idx & 2u));
-    out.clip_position = vec4<f32>(out.uv.x * 2.0 - 1.0, out.uv.y * -2.0 + 1.0, 0., 1.);
-
-    return out;
-}
-
 // CRT postprocessing effect based on
 // https://babylonjs.medium.com/retro-crt-shader-a-post-processing-effect-study-1cb3f783afbc
 // plus chromatic aberration and some other personal touches
@@ -48,6 +27,18 @@ fn distort_uv(in_uv: vec2<f32>) -> vec2<f32> {
     return uv;
 }
 
+// uv in the whole picture from uv in the part of it being drawn, see to_view in time.wgsl
+fn from_view_uv(uv: vec2<f32>) -> vec2<f32> {
+    let clip = vec2<f32>(2. * uv.x - 1., 1. - 2. * uv.y);
+    let whole = (clip - unif.view.zw) / unif.view.xy;
+    return vec2<f32>(0.5 * whole.x + 0.5, 0.5 - 0.5 * whole.y);
+}
+
+fn to_view_uv(uv: vec2<f32>) -> vec2<f32> {
+    let clip = to_view(vec2<f32>(2. * uv.x - 1., 1. - 2. * uv.y));
+    return vec2<f32>(0.5 * clip.x + 0.5, 0.5 - 0.5 * clip.y);
+}
+
 fn scanline_coef(uv: vec2<f32>, y_resolution: f32) -> f32 {
     // slightly higher opacity patterns moving in waves along the edges
     let x_modulator = 4. * pow(uv.x - 0.5, 2.);
@@ -63,21 +54,15 @@ fn vignette_coef(uv: vec2<f32>, screen_size: vec2<f32>) -> f32 {
     return saturate(intensity);
 }
 
-fn noise(x: vec2<f32>) -> f32 {
-    return fract(sin(dot(x, vec2<f32>(12.9898, 78.233))) * 43758.5453);
-}
-
-fn noise_1d(x: f32) -> f32 {
-    return noise(vec2<f32>(x, 0.));
-}
-
 @fragment
 fn fs_main(
     in: VertexOutput
 ) -> @location(0) vec4<f32> {
     let t = unif.t;
-    let screen_size = vec2<f32>(textureDimensions(gbuf_tex));
-    let distorted_uv = distort_uv(in.uv);
+    // the gbuffer covers the same part of the picture as the render target
+    let screen_size = vec2<f32>(textureDimensions(gbuf_tex)) * unif.view.xy;
+    let uv = from_view_uv(in.uv);
+    let distorted_uv = distort_uv(uv);
 
     // different channels offset slightly for chromatic aberration
     // plus a "glitch" effect randomly every now and then for funsies
@@ -91,9 +76,9 @@ fn fs_main(
     let blue_uv = distorted_uv + aberration_intensity * vec2<f32>(-0.8, -0.6);
 
     let screen_color = vec4<f32>(
-    	textureSample(gbuf_tex, gbuf_samp, red_uv).r,
-    	textureSample(gbuf_tex, gbuf_samp, green_uv).g,
-    	textureSample(gbuf_tex, gbuf_samp, blue_uv).b,
+    	textureSample(gbuf_tex, gbuf_samp, to_view_uv(red_uv)).r,
+    	textureSample(gbuf_tex, gbuf_samp, to_view_uv(green_uv)).g,
+    	textureSample(gbuf_tex, gbuf_samp, to_view_uv(blue_uv)).b,
 	1.,
     );
 
@@ -102,9 +87,9 @@ fn fs_main(
     }
 
     let scanline = scanline_coef(distorted_uv, screen_size.y / 8.);
-    let vignette = vignette_coef(in.uv, screen_size);
+    let vignette = vignette_coef(uv, screen_size);
     let brightness_boost = 1.5 + 0.1 * noise_1d(round(20. * t));
 
-    let dimmed_color = vec4<f32>(brightness_boost * scanline * vignette * screen_color.rgb, 1.);
+    let dimmed_color = vec4<f32>(brightness_boost * scanline * vignette * tint.rgb * screen_color.rgb, 1.);
     return dimmed_color;
 }
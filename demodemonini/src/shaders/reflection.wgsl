@@ -0,0 +1,51 @@
+#include "common/noise.wgsl"
+#include "common/time.wgsl"
+
+// same bindings as textured.wgsl so the same bind groups work,
+// though the UV effects aren't used here
+@group(0) @binding(0)
+var tex: texture_2d<f32>;
+@group(0) @binding(1)
+var samp: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(to_view(position.xy), position.z, 1.);
+    out.tex_coords = tex_coords;
+    return out;
+}
+
+// reflection of a texture on rippling water.
+// expects the quad to be flipped so that texture y = 1 is at the waterline
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // 0 at the waterline, 1 at the far edge of the reflection
+    let dist = 1. - in.tex_coords.y;
+
+    // two layers of noise scrolling towards the viewer, stretched horizontally like ripples
+    let ripple_coords = vec2<f32>(in.tex_coords.x * 6., dist * 40. - unif.t * 2.);
+    let ripple = value_noise(ripple_coords) + 0.5 * value_noise(ripple_coords * 2.3 + 17.);
+    // ripples get stronger further from the waterline
+    let strength = 0.005 + 0.03 * dist;
+    var uv = in.tex_coords;
+    uv.x += (ripple - 0.75) * strength;
+    uv.y += (ripple - 0.75) * strength * 0.5;
+
+    var color = textureSample(tex, samp, uv);
+    color.a *= 0.8 * pow(1. - dist, 1.5);
+    // see textured.wgsl
+    if color.a < 0.01 {
+        discard;
+    }
+    return color;
+}
@@ -3,6 +3,17 @@ var tex: texture_2d<f32>;
 @group(0) @binding(1)
 var samp: sampler;
 
+struct UvEffects {
+    scroll: vec2<f32>,
+    breathe: f32,
+    shimmer: f32,
+}
+
+@group(0) @binding(2)
+var<uniform> effects: UvEffects;
+
+#include "common/time.wgsl"
+
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) tex_coords: vec2<f32>,
@@ -10,17 +21,31 @@ struct VertexOutput {
 
 @vertex
 fn vs_main(
-    @location(0) position: vec2<f32>,
+    @location(0) position: vec3<f32>,
     @location(1) tex_coords: vec2<f32>,
 ) -> VertexOutput {
     var out: VertexOutput;
     // we're just drawing straight into clip space here
-    out.clip_position = vec4<f32>(position, 0., 1.);
-    out.tex_coords = tex_coords;
+    out.clip_position = vec4<f32>(to_view(position.xy), position.z, 1.);
+
+    // zoom slowly in and out around the center
+    let zoom = 1. - effects.breathe * (0.5 + 0.5 * sin(0.8 * unif.t));
+    out.tex_coords = (tex_coords - 0.5) * zoom + 0.5 + effects.scroll * unif.t;
     return out;
 }
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(tex, samp, in.tex_coords);
+    // skip the wave computation entirely for the common case of no shimmer
+    var uv = in.tex_coords;
+    if effects.shimmer != 0. {
+        uv.x += effects.shimmer * sin(50. * uv.y + 3. * unif.t) * sin(7. * uv.y - 1.3 * unif.t);
+    }
+    let color = textureSample(tex, samp, uv);
+    // fully transparent parts mustn't write to the depth buffer
+    // or they'd hide things behind them that are drawn later
+    if color.a < 0.01 {
+        discard;
+    }
+    return color;
 }
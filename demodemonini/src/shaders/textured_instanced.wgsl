@@ -0,0 +1,63 @@
+#include "common/time.wgsl"
+
+@group(0) @binding(0)
+var tex: texture_2d<f32>;
+@group(0) @binding(1)
+var samp: sampler;
+
+struct Instance {
+    @location(0) center: vec2<f32>,
+    @location(1) half_size: vec2<f32>,
+    @location(2) rotation: f32,
+    @location(3) depth: f32,
+    @location(4) uv_min: vec2<f32>,
+    @location(5) uv_max: vec2<f32>,
+    @location(6) tint: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) tint: vec4<f32>,
+}
+
+// corners of a quad as two triangles, with (0, 0) at the bottom left
+var<private> CORNERS: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+    vec2<f32>(0., 0.),
+    vec2<f32>(1., 0.),
+    vec2<f32>(1., 1.),
+    vec2<f32>(0., 0.),
+    vec2<f32>(1., 1.),
+    vec2<f32>(0., 1.),
+);
+
+// no vertex buffer, quad corners come from the vertex index
+// and everything else from the instance
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vert_idx: u32,
+    inst: Instance,
+) -> VertexOutput {
+    let corner = CORNERS[vert_idx];
+    let local = (2. * corner - 1.) * inst.half_size;
+    let c = cos(inst.rotation);
+    let s = sin(inst.rotation);
+    let rotated = vec2<f32>(c * local.x - s * local.y, s * local.x + c * local.y);
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(to_view(inst.center + rotated), inst.depth, 1.);
+    // texture y goes down while clip space y goes up
+    out.tex_coords = mix(inst.uv_min, inst.uv_max, vec2<f32>(corner.x, 1. - corner.y));
+    out.tint = inst.tint;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(tex, samp, in.tex_coords) * in.tint;
+    // see textured.wgsl
+    if color.a < 0.01 {
+        discard;
+    }
+    return color;
+}
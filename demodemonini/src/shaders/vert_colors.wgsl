@@ -1,3 +1,7 @@
+// no other bind groups, so the time goes first
+#define TIME_GROUP 0
+#include "common/time.wgsl"
+
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec4<f32>,
@@ -9,7 +13,9 @@ fn vs_main(
     @location(1) color: vec4<f32>,
 ) -> VertexOutput {
     var out: VertexOutput;
-    out.clip_position = vec4<f32>(position, 0., 1.);
+    // vertex-colored shapes are only used for the background,
+    // so they go at the very back of the depth buffer
+    out.clip_position = vec4<f32>(to_view(position), 1., 1.);
     out.color = color;
     return out;
 }
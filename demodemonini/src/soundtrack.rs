@@ -0,0 +1,60 @@
+//! Music playback that the rest of the demo follows,
+//! so effects that depend on time stay locked to the track.
+
+use std::{cell::Cell, fs::File, io::BufReader, path::PathBuf, time::Duration};
+
+use instant::Instant;
+
+pub struct Soundtrack {
+    path: PathBuf,
+    // playback stops when the stream is dropped
+    _stream: rodio::OutputStream,
+    sink: rodio::Sink,
+    // when the track was noticed to have ended, to keep the time going from there
+    ended: Cell<Option<Instant>>,
+}
+
+impl Soundtrack {
+    /// Start playing the file at `path` from `start_t` seconds in.
+    pub fn play(path: PathBuf, start_t: f64) -> anyhow::Result<Self> {
+        let (stream, handle) = rodio::OutputStream::try_default()?;
+        let sink = rodio::Sink::try_new(&handle)?;
+        let mut soundtrack = Self {
+            path,
+            _stream: stream,
+            sink,
+            ended: Cell::new(None),
+        };
+        soundtrack.seek(start_t)?;
+        Ok(soundtrack)
+    }
+
+    /// Position of the playhead in seconds.
+    /// Keeps counting after the track ends.
+    pub fn time(&self) -> f64 {
+        let pos = self.sink.get_pos().as_secs_f64();
+        if !self.sink.empty() {
+            return pos;
+        }
+        // the playhead stays at the end of the track, but the picture goes on
+        let ended = self.ended.get().unwrap_or_else(|| {
+            let now = Instant::now();
+            self.ended.set(Some(now));
+            now
+        });
+        pos + ended.elapsed().as_secs_f64()
+    }
+
+    /// Continue playing from `t` seconds into the track.
+    pub fn seek(&mut self, t: f64) -> anyhow::Result<()> {
+        // the track is gone from the sink once it's ended, so queue it up again
+        if self.sink.empty() {
+            let decoder = rodio::Decoder::new(BufReader::new(File::open(&self.path)?))?;
+            self.sink.append(decoder);
+            self.ended.set(None);
+        }
+        self.sink
+            .try_seek(Duration::from_secs_f64(t.max(0.)))
+            .map_err(|err| anyhow::anyhow!("{err}"))
+    }
+}
@@ -0,0 +1,289 @@
+use wgpu::util::DeviceExt;
+
+use super::{atlas::UvRect, pipelines::TexturePipeline};
+
+// tiny built-in bitmap font so we can show messages on screen
+// without pulling in a font rendering library
+
+const GLYPH_WIDTH: usize = 5;
+const GLYPH_HEIGHT: usize = 7;
+// glyph size plus spacing between characters and lines
+const CELL_WIDTH: usize = GLYPH_WIDTH + 1;
+const CELL_HEIGHT: usize = GLYPH_HEIGHT + 2;
+// padding around the whole block of text
+const PADDING: usize = 2;
+
+/// Get the bitmap for a character as rows of 5 bits, top row first.
+/// Lowercase letters are drawn as uppercase
+/// and unsupported characters as a question mark.
+#[rustfmt::skip]
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c.to_ascii_uppercase() {
+        ' ' => [0; GLYPH_HEIGHT],
+        '0' => [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110],
+        '1' => [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        '2' => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111],
+        '3' => [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110],
+        '4' => [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010],
+        '5' => [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110],
+        '6' => [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110],
+        '7' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000],
+        '8' => [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110],
+        '9' => [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100],
+        'A' => [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+        'B' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110],
+        'C' => [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110],
+        'D' => [0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100],
+        'E' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111],
+        'F' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000],
+        'G' => [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111],
+        'H' => [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+        'I' => [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        'J' => [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100],
+        'K' => [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001],
+        'L' => [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111],
+        'M' => [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001],
+        'N' => [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001],
+        'O' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'P' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000],
+        'Q' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101],
+        'R' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001],
+        'S' => [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110],
+        'T' => [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100],
+        'U' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'V' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100],
+        'W' => [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010],
+        'X' => [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001],
+        'Y' => [0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100],
+        'Z' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111],
+        '!' => [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100],
+        '"' => [0b01010, 0b01010, 0b01010, 0b00000, 0b00000, 0b00000, 0b00000],
+        '#' => [0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010],
+        '$' => [0b00100, 0b01111, 0b10100, 0b01110, 0b00101, 0b11110, 0b00100],
+        '%' => [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011],
+        '&' => [0b01100, 0b10010, 0b10100, 0b01000, 0b10101, 0b10010, 0b01101],
+        '\'' => [0b01100, 0b00100, 0b01000, 0b00000, 0b00000, 0b00000, 0b00000],
+        '(' => [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010],
+        ')' => [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000],
+        '*' => [0b00000, 0b00100, 0b10101, 0b01110, 0b10101, 0b00100, 0b00000],
+        '+' => [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000],
+        ',' => [0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000],
+        '-' => [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000],
+        '.' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100],
+        '/' => [0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000],
+        ':' => [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000],
+        ';' => [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b00100, 0b01000],
+        '<' => [0b00010, 0b00100, 0b01000, 0b10000, 0b01000, 0b00100, 0b00010],
+        '=' => [0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000],
+        '>' => [0b01000, 0b00100, 0b00010, 0b00001, 0b00010, 0b00100, 0b01000],
+        '@' => [0b01110, 0b10001, 0b00001, 0b01101, 0b10101, 0b10101, 0b01110],
+        '[' => [0b01110, 0b01000, 0b01000, 0b01000, 0b01000, 0b01000, 0b01110],
+        '\\' => [0b00000, 0b10000, 0b01000, 0b00100, 0b00010, 0b00001, 0b00000],
+        ']' => [0b01110, 0b00010, 0b00010, 0b00010, 0b00010, 0b00010, 0b01110],
+        '^' => [0b00100, 0b01010, 0b10001, 0b00000, 0b00000, 0b00000, 0b00000],
+        '_' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111],
+        '`' => [0b01000, 0b00100, 0b00010, 0b00000, 0b00000, 0b00000, 0b00000],
+        '{' => [0b00010, 0b00100, 0b00100, 0b01000, 0b00100, 0b00100, 0b00010],
+        '|' => [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100],
+        '}' => [0b01000, 0b00100, 0b00100, 0b00010, 0b00100, 0b00100, 0b01000],
+        '~' => [0b00000, 0b00000, 0b01000, 0b10101, 0b00010, 0b00000, 0b00000],
+        // question mark doubles as the fallback for anything we don't have a glyph for
+        _ => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100],
+    }
+}
+
+/// An RGBA8 image of some text.
+pub struct TextImage {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<[u8; 4]>,
+}
+
+/// Draw text into an image with a translucent dark background,
+/// wrapping lines longer than `max_columns`.
+pub fn rasterize(text: &str, color: [u8; 4], max_columns: usize) -> TextImage {
+    let max_columns = max_columns.max(1);
+    let lines: Vec<Vec<char>> = text
+        .lines()
+        .flat_map(|line| {
+            let chars: Vec<char> = line.replace('\t', "    ").chars().collect();
+            if chars.is_empty() {
+                vec![Vec::new()]
+            } else {
+                chars.chunks(max_columns).map(|c| c.to_vec()).collect()
+            }
+        })
+        .collect();
+    let columns = lines.iter().map(|l| l.len()).max().unwrap_or(0);
+
+    let width = columns * CELL_WIDTH + 2 * PADDING;
+    let height = lines.len() * CELL_HEIGHT + 2 * PADDING;
+    let mut pixels = vec![[0, 0, 0, 180]; width * height];
+
+    for (line_idx, line) in lines.iter().enumerate() {
+        for (col_idx, &c) in line.iter().enumerate() {
+            let origin_x = PADDING + col_idx * CELL_WIDTH;
+            let origin_y = PADDING + line_idx * CELL_HEIGHT;
+            for (row, bits) in glyph(c).into_iter().enumerate() {
+                for col in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - col)) != 0 {
+                        pixels[(origin_y + row) * width + origin_x + col] = color;
+                    }
+                }
+            }
+        }
+    }
+
+    TextImage {
+        width,
+        height,
+        pixels,
+    }
+}
+
+/// Which corner of the screen a [`TextOverlay`] is drawn in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    BottomRight,
+}
+
+/// A block of text drawn on top of everything else in a corner of the screen.
+pub struct TextOverlay {
+    text: String,
+    corner: Corner,
+    color: [u8; 4],
+    // how many screen pixels each font pixel takes
+    scale: u32,
+    window_size: winit::dpi::PhysicalSize<u32>,
+    // None when there's no text to draw
+    draw_data: Option<OverlayDrawData>,
+}
+
+struct OverlayDrawData {
+    bind_group: wgpu::BindGroup,
+    verts: wgpu::Buffer,
+}
+
+impl TextOverlay {
+    /// An overlay for messages in the top left corner.
+    pub fn new(window_size: winit::dpi::PhysicalSize<u32>) -> Self {
+        Self::with_style(window_size, Corner::TopLeft, [255, 90, 90, 255])
+    }
+
+    pub fn with_style(
+        window_size: winit::dpi::PhysicalSize<u32>,
+        corner: Corner,
+        color: [u8; 4],
+    ) -> Self {
+        Self {
+            text: String::new(),
+            corner,
+            color,
+            scale: 2,
+            window_size,
+            draw_data: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.draw_data.is_none()
+    }
+
+    /// Change the displayed text. Setting an empty string hides the overlay.
+    pub fn set_text(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        pipeline: &TexturePipeline,
+        sampler: &wgpu::Sampler,
+        text: &str,
+    ) {
+        if text == self.text {
+            return;
+        }
+        self.text = text.to_string();
+        self.regenerate(device, queue, pipeline, sampler);
+    }
+
+    /// Update the size of the window to keep text pixels square.
+    pub fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        pipeline: &TexturePipeline,
+        sampler: &wgpu::Sampler,
+        window_size: winit::dpi::PhysicalSize<u32>,
+    ) {
+        self.window_size = window_size;
+        self.regenerate(device, queue, pipeline, sampler);
+    }
+
+    fn regenerate(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        pipeline: &TexturePipeline,
+        sampler: &wgpu::Sampler,
+    ) {
+        let win_w = self.window_size.width.max(1) as usize;
+        let win_h = self.window_size.height.max(1) as usize;
+        if self.text.is_empty() {
+            self.draw_data = None;
+            return;
+        }
+
+        let max_columns = (win_w / self.scale as usize).saturating_sub(2 * PADDING) / CELL_WIDTH;
+        let image = rasterize(&self.text, self.color, max_columns);
+
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("text overlay"),
+                size: wgpu::Extent3d {
+                    width: image.width as u32,
+                    height: image.height as u32,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            bytemuck::cast_slice(&image.pixels),
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = pipeline.create_bind_group(device, "text overlay", &view, sampler);
+
+        // quad in the corner, sized in whole pixels
+        let quad_w = 2. * (image.width * self.scale as usize) as f32 / win_w as f32;
+        let quad_h = 2. * (image.height * self.scale as usize) as f32 / win_h as f32;
+        let (bottom_left, top_right) = match self.corner {
+            Corner::TopLeft => ([-1., 1. - quad_h], [-1. + quad_w, 1.]),
+            Corner::BottomRight => ([1. - quad_w, -1.], [1., -1. + quad_h]),
+        };
+        let verts = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("text overlay"),
+            contents: bytemuck::cast_slice(&TexturePipeline::quad_vertices(
+                bottom_left,
+                top_right,
+                0.,
+                UvRect::FULL,
+            )),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        self.draw_data = Some(OverlayDrawData { bind_group, verts });
+    }
+
+    /// Draw the overlay, assuming the texture pipeline is set.
+    pub fn draw<'pass>(&'pass self, pass: &mut wgpu::RenderPass<'pass>) {
+        if let Some(data) = &self.draw_data {
+            pass.set_bind_group(0, &data.bind_group, &[]);
+            pass.set_vertex_buffer(0, data.verts.slice(..));
+            pass.draw(0..6, 0..1);
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use wgpu::util::DeviceExt;
+
+/// How the color values in an image should be interpreted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// Colors stored with the sRGB transfer function, like painted artwork.
+    /// The GPU converts these to linear when sampling
+    /// so blending and the sRGB swapchain work out correctly.
+    #[default]
+    Srgb,
+    /// Values that are already linear or aren't colors at all (masks, noise, lookup tables),
+    /// used as-is without any conversion.
+    Linear,
+}
+
+impl ColorSpace {
+    /// The 8-bit RGBA texture format for data in this color space.
+    pub fn rgba8_format(self) -> wgpu::TextureFormat {
+        match self {
+            ColorSpace::Srgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+            ColorSpace::Linear => wgpu::TextureFormat::Rgba8Unorm,
+        }
+    }
+}
+
+/// Decode an image file (PNG, JPEG, WebP or TIFF, detected from the contents)
+/// into 8-bit RGBA, converting from whatever channel layout and bit depth it has.
+pub fn decode_image(bytes: &[u8]) -> anyhow::Result<image::RgbaImage> {
+    Ok(image::load_from_memory(bytes)?.into_rgba8())
+}
+
+/// Decode an image file and upload it into a new texture.
+pub fn load_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    label: &str,
+    bytes: &[u8],
+    color_space: ColorSpace,
+) -> anyhow::Result<wgpu::Texture> {
+    let image = decode_image(bytes)?;
+    Ok(create_rgba_texture(
+        device,
+        queue,
+        label,
+        &image,
+        color_space,
+    ))
+}
+
+/// Upload an already decoded image into a new texture.
+pub fn create_rgba_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    label: &str,
+    image: &image::RgbaImage,
+    color_space: ColorSpace,
+) -> wgpu::Texture {
+    device.create_texture_with_data(
+        queue,
+        &wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: image.width(),
+                height: image.height(),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: color_space.rgba8_format(),
+            usage: wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        },
+        image.as_raw(),
+    )
+}
+
+/// Load a texture, preferring a GPU-compressed KTX2 version if one is given
+/// and the device supports its format, and otherwise decoding `fallback_bytes`.
+///
+/// Compressed textures take a fraction of the memory and upload time of raw RGBA,
+/// which matters for the big painted images.
+pub fn load_texture_prefer_compressed(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    label: &str,
+    ktx2_bytes: Option<&[u8]>,
+    fallback_bytes: &[u8],
+    color_space: ColorSpace,
+) -> anyhow::Result<wgpu::Texture> {
+    if let Some(bytes) = ktx2_bytes {
+        match load_ktx2_texture(device, queue, label, bytes) {
+            Ok(Some(tex)) => return Ok(tex),
+            Ok(None) => {}
+            Err(err) => eprintln!("Failed to load compressed {label} texture: {err}"),
+        }
+    }
+    load_texture(device, queue, label, fallback_bytes, color_space)
+}
+
+/// Load a KTX2 file, uploading its contents as-is
+/// so compressed formats stay compressed on the GPU.
+///
+/// Returns `Ok(None)` if the device doesn't support the file's format.
+pub fn load_ktx2_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    label: &str,
+    bytes: &[u8],
+) -> anyhow::Result<Option<wgpu::Texture>> {
+    let reader = ktx2::Reader::new(bytes)?;
+    let header = reader.header();
+    if let Some(scheme) = header.supercompression_scheme {
+        anyhow::bail!("supercompression ({scheme:?}) is not supported");
+    }
+    if header.pixel_depth > 1 || header.layer_count > 1 || header.face_count > 1 {
+        anyhow::bail!("only plain 2D textures are supported");
+    }
+    let format = header
+        .format
+        .and_then(ktx2_format_to_wgpu)
+        .ok_or_else(|| anyhow::anyhow!("unsupported format {:?}", header.format))?;
+
+    if !device.features().contains(format.required_features()) {
+        return Ok(None);
+    }
+    let (block_w, block_h) = format.block_dimensions();
+    if header.pixel_width % block_w != 0 || header.pixel_height % block_h != 0 {
+        anyhow::bail!(
+            "size {}x{} is not a multiple of the {block_w}x{block_h} block size",
+            header.pixel_width,
+            header.pixel_height
+        );
+    }
+
+    // wgpu expects all mip levels back to back, largest first, same as they are in the file
+    let data: Vec<u8> = reader.levels().flatten().copied().collect();
+
+    Ok(Some(device.create_texture_with_data(
+        queue,
+        &wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: header.pixel_width,
+                height: header.pixel_height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: header.level_count.max(1),
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        },
+        &data,
+    )))
+}
+
+/// Get the wgpu equivalent of the KTX2 formats we know how to handle.
+fn ktx2_format_to_wgpu(format: ktx2::Format) -> Option<wgpu::TextureFormat> {
+    use ktx2::Format as K;
+    use wgpu::TextureFormat as W;
+    Some(match format {
+        K::BC7_UNORM_BLOCK => W::Bc7RgbaUnorm,
+        K::BC7_SRGB_BLOCK => W::Bc7RgbaUnormSrgb,
+        K::BC3_UNORM_BLOCK => W::Bc3RgbaUnorm,
+        K::BC3_SRGB_BLOCK => W::Bc3RgbaUnormSrgb,
+        K::BC1_RGBA_UNORM_BLOCK => W::Bc1RgbaUnorm,
+        K::BC1_RGBA_SRGB_BLOCK => W::Bc1RgbaUnormSrgb,
+        K::ETC2_R8G8B8A8_UNORM_BLOCK => W::Etc2Rgba8Unorm,
+        K::ETC2_R8G8B8A8_SRGB_BLOCK => W::Etc2Rgba8UnormSrgb,
+        K::R8G8B8A8_UNORM => W::Rgba8Unorm,
+        K::R8G8B8A8_SRGB => W::Rgba8UnormSrgb,
+        _ => return None,
+    })
+}
@@ -0,0 +1,136 @@
+//! Color themes that follow the time of day, for installations left running all day.
+//!
+//! There's a preset for dawn, day, dusk and night, each placed at an hour of the day,
+//! and the colors at any other hour are blended between the two presets around it.
+//! Night is the picture as it was painted.
+
+use palette::{LinSrgb, LinSrgba, Mix, Srgb, Srgba};
+
+use super::{
+    fire::{self, FirePalette},
+    triangle_grid::{self, GridPalette},
+};
+
+/// Colors for the parts of the picture that change with the theme.
+#[derive(Clone, Copy, Debug)]
+pub struct Theme {
+    pub grid: GridPalette,
+    pub fire: FirePalette,
+    /// Multiplied onto the whole picture in postprocessing.
+    pub tint: Srgb,
+}
+
+const NIGHT: Theme = Theme {
+    grid: triangle_grid::DEFAULT_PALETTE,
+    fire: fire::DEFAULT_PALETTE,
+    tint: Srgb::new(1., 1., 1.),
+};
+
+const DAWN: Theme = Theme {
+    grid: [
+        Srgb::new(0.110, 0.050, 0.140),
+        Srgb::new(0.260, 0.130, 0.250),
+        Srgb::new(0.480, 0.220, 0.220),
+        Srgb::new(0.300, 0.200, 0.320),
+        Srgb::new(0.140, 0.100, 0.240),
+    ],
+    fire: [
+        Srgba::new(0., 0., 0., 0.3),
+        Srgba::new(0.300, 0.040, 0.080, 0.5),
+        Srgba::new(0.600, 0.200, 0.250, 0.7),
+        Srgba::new(0.950, 0.550, 0.400, 0.8),
+        Srgba::new(1., 0.850, 0.600, 0.8),
+        Srgba::new(1., 1., 1., 0.8),
+    ],
+    tint: Srgb::new(1., 0.920, 0.900),
+};
+
+const DAY: Theme = Theme {
+    grid: [
+        Srgb::new(0.120, 0.200, 0.330),
+        Srgb::new(0.200, 0.310, 0.450),
+        Srgb::new(0.330, 0.420, 0.520),
+        Srgb::new(0.180, 0.290, 0.450),
+        Srgb::new(0.100, 0.180, 0.320),
+    ],
+    fire: [
+        Srgba::new(0., 0., 0., 0.2),
+        Srgba::new(0.350, 0.080, 0., 0.4),
+        Srgba::new(0.700, 0.300, 0.050, 0.6),
+        Srgba::new(0.980, 0.650, 0.200, 0.7),
+        Srgba::new(1., 0.900, 0.400, 0.7),
+        Srgba::new(1., 1., 1., 0.7),
+    ],
+    tint: Srgb::new(1., 1., 0.950),
+};
+
+const DUSK: Theme = Theme {
+    grid: [
+        Srgb::new(0.090, 0.020, 0.090),
+        Srgb::new(0.220, 0.050, 0.130),
+        Srgb::new(0.450, 0.130, 0.080),
+        Srgb::new(0.250, 0.080, 0.170),
+        Srgb::new(0.070, 0.020, 0.110),
+    ],
+    fire: [
+        Srgba::new(0., 0., 0., 0.3),
+        Srgba::new(0.350, 0.020, 0., 0.5),
+        Srgba::new(0.650, 0.150, 0.030, 0.7),
+        Srgba::new(0.950, 0.420, 0.100, 0.8),
+        Srgba::new(1., 0.700, 0.150, 0.8),
+        Srgba::new(1., 1., 0.900, 0.8),
+    ],
+    tint: Srgb::new(1., 0.880, 0.800),
+};
+
+// hours where each preset is shown as is, in order through the day.
+// night comes around again at the end so the evening blends into it
+const PRESETS: [(f32, Theme); 6] = [
+    (0., NIGHT),
+    (5., NIGHT),
+    (7., DAWN),
+    (12., DAY),
+    (19., DUSK),
+    (22., NIGHT),
+];
+
+impl Theme {
+    /// The theme for a time of day given in hours since midnight, e.g. 18.5 for 18:30.
+    pub fn at_hour(hour: f32) -> Self {
+        let hour = hour.rem_euclid(24.);
+        let next_idx = PRESETS
+            .iter()
+            .position(|&(h, _)| h > hour)
+            .unwrap_or(PRESETS.len());
+        let (prev_hour, prev) = PRESETS[next_idx - 1];
+        let (next_hour, next) = PRESETS
+            .get(next_idx)
+            .copied()
+            .unwrap_or((24., PRESETS[0].1));
+        prev.mix(&next, (hour - prev_hour) / (next_hour - prev_hour))
+    }
+
+    fn mix(&self, other: &Self, amount: f32) -> Self {
+        // blended in linear color like everything else that gets mixed
+        let mix_rgb = |a: Srgb, b: Srgb| {
+            let (a, b): (LinSrgb, LinSrgb) = (a.into_linear(), b.into_linear());
+            Srgb::from_linear(a.mix(b, amount))
+        };
+        let mix_rgba = |a: Srgba, b: Srgba| {
+            let (a, b): (LinSrgba, LinSrgba) = (a.into_linear(), b.into_linear());
+            Srgba::from_linear(a.mix(b, amount))
+        };
+        Self {
+            grid: std::array::from_fn(|i| mix_rgb(self.grid[i], other.grid[i])),
+            fire: std::array::from_fn(|i| mix_rgba(self.fire[i], other.fire[i])),
+            tint: mix_rgb(self.tint, other.tint),
+        }
+    }
+}
+
+/// The current local time in hours since midnight.
+pub fn local_hour() -> f32 {
+    use chrono::Timelike;
+    let now = chrono::Local::now();
+    now.hour() as f32 + now.minute() as f32 / 60. + now.second() as f32 / 3600.
+}
@@ -0,0 +1,180 @@
+//! Choreography for a whole performance, authored as a text file
+//! instead of being hardcoded.
+//!
+//! Each non-empty line that isn't a `#` comment is a time in seconds followed by
+//! either a layer and whether it's shown, e.g. `4.5 fire on`,
+//! or a parameter, its value and how to ease into it from the previous keyframe,
+//! e.g. `6 glow 2 ease-in-out`. Easing defaults to linear.
+//!
+//! Layers: `fire`, `characters`, `postprocess`. Parameters: `glow`.
+
+use std::path::Path;
+
+use super::scene::Scene;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Layer {
+    Fire,
+    Characters,
+    Postprocess,
+}
+
+impl Layer {
+    const ALL: [Layer; 3] = [Layer::Fire, Layer::Characters, Layer::Postprocess];
+
+    fn toggle(self, scene: &mut Scene) -> &mut bool {
+        match self {
+            Layer::Fire => &mut scene.draw_fire,
+            Layer::Characters => &mut scene.draw_characters,
+            Layer::Postprocess => &mut scene.draw_postprocess,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Easing {
+    Step,
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    /// Map progress between two keyframes from 0 to 1 to the eased progress.
+    fn apply(self, x: f32) -> f32 {
+        match self {
+            Easing::Step => 0.,
+            Easing::Linear => x,
+            Easing::EaseIn => x * x,
+            Easing::EaseOut => 1. - (1. - x) * (1. - x),
+            Easing::EaseInOut => x * x * (3. - 2. * x),
+        }
+    }
+}
+
+struct Keyframe {
+    t: f64,
+    value: f32,
+    easing: Easing,
+}
+
+pub struct Timeline {
+    // sorted by time
+    layer_events: Vec<(f64, Layer, bool)>,
+    glow: Vec<Keyframe>,
+    // layer states set on the last frame, so that toggling by hand
+    // is only overridden once the timeline changes its mind
+    applied_layers: [Option<bool>; 3],
+}
+
+impl Timeline {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|err| anyhow::anyhow!("{}: {err}", path.display()))
+    }
+
+    fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut layer_events = Vec::new();
+        let mut glow = Vec::new();
+        for (line_idx, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            match parse_line(line).map_err(|err| anyhow::anyhow!("line {}: {err}", line_idx + 1))? {
+                Entry::Layer(t, layer, shown) => layer_events.push((t, layer, shown)),
+                Entry::Glow(key) => glow.push(key),
+            }
+        }
+        // stable sort so events at the same time happen in the order they're written
+        layer_events.sort_by(|a, b| a.0.total_cmp(&b.0));
+        glow.sort_by(|a, b| a.t.total_cmp(&b.t));
+        Ok(Self {
+            layer_events,
+            glow,
+            applied_layers: [None; 3],
+        })
+    }
+
+    /// Set everything the timeline controls to where it should be at time `t`.
+    /// Works for jumps in time in either direction.
+    pub fn apply(&mut self, scene: &mut Scene, t: f64) {
+        for (layer, applied) in Layer::ALL.into_iter().zip(&mut self.applied_layers) {
+            let shown = self
+                .layer_events
+                .iter()
+                .take_while(|event| event.0 <= t)
+                .filter(|event| event.1 == layer)
+                .last()
+                .map(|event| event.2);
+            if shown.is_some() && shown != *applied {
+                *layer.toggle(scene) = shown.unwrap_or_default();
+            }
+            *applied = shown;
+        }
+        if let Some(glow) = sample(&self.glow, t) {
+            scene.glow = glow;
+        }
+    }
+}
+
+enum Entry {
+    Layer(f64, Layer, bool),
+    Glow(Keyframe),
+}
+
+fn parse_line(line: &str) -> anyhow::Result<Entry> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let [t, name, rest @ ..] = words.as_slice() else {
+        anyhow::bail!("expected a time and a layer or parameter");
+    };
+    let t: f64 = t.parse()?;
+    let layer = match *name {
+        "fire" => Layer::Fire,
+        "characters" => Layer::Characters,
+        "postprocess" => Layer::Postprocess,
+        "glow" => {
+            let (value, easing) = match rest {
+                [value] => (value, Easing::Linear),
+                [value, easing] => (value, parse_easing(easing)?),
+                _ => anyhow::bail!("expected a value and an optional easing"),
+            };
+            let value = value.parse()?;
+            return Ok(Entry::Glow(Keyframe { t, value, easing }));
+        }
+        other => anyhow::bail!("unknown layer or parameter {other:?}"),
+    };
+    let shown = match rest {
+        ["on"] => true,
+        ["off"] => false,
+        _ => anyhow::bail!("expected on or off"),
+    };
+    Ok(Entry::Layer(t, layer, shown))
+}
+
+fn parse_easing(name: &str) -> anyhow::Result<Easing> {
+    Ok(match name {
+        "linear" => Easing::Linear,
+        "step" => Easing::Step,
+        "ease-in" => Easing::EaseIn,
+        "ease-out" => Easing::EaseOut,
+        "ease-in-out" => Easing::EaseInOut,
+        other => anyhow::bail!("unknown easing {other:?}"),
+    })
+}
+
+/// The value of a parameter at time `t`, or `None` if it has no keyframes.
+/// Held at the first and last keyframe outside of their range.
+fn sample(keyframes: &[Keyframe], t: f64) -> Option<f32> {
+    let next_idx = keyframes.partition_point(|key| key.t <= t);
+    let next = match keyframes.get(next_idx) {
+        Some(next) => next,
+        None => return keyframes.last().map(|key| key.value),
+    };
+    let Some(prev) = next_idx.checked_sub(1).map(|idx| &keyframes[idx]) else {
+        return Some(next.value);
+    };
+    let progress = ((t - prev.t) / (next.t - prev.t)) as f32;
+    Some(prev.value + next.easing.apply(progress) * (next.value - prev.value))
+}
@@ -1,6 +1,6 @@
 use enterpolation::{linear::Linear, Generator};
 use itertools::chain;
-use palette::{IntoColor, LinSrgba, Srgb};
+use palette::{IntoColor, LinSrgb, LinSrgba, Srgb};
 use rand::Rng;
 use std::{f32::consts::PI, ops::Range};
 
@@ -15,6 +15,8 @@ pub struct TriangleGrid {
 #[derive(Clone, Copy, Debug, Default)]
 struct Point {
     root_pos: [f32; 2],
+    // height of the triangle's centroid, where it's colored from the gradient
+    gradient_y: f32,
     color: [f32; 4],
     // randomized parameters for a sine curve
     x_phase: f32,
@@ -25,6 +27,20 @@ struct Point {
     y_velocity: f32,
 }
 
+/// Colors of the background gradient from bottom to top.
+pub type GridPalette = [Srgb; 5];
+
+/// The background's colors as painted, used unless a theme changes them.
+pub const DEFAULT_PALETTE: GridPalette = [
+    Srgb::new(0.0637, 0.0143, 0.110),
+    Srgb::new(0.140, 0.073, 0.200),
+    Srgb::new(0.290, 0.0580, 0.155),
+    Srgb::new(0.163, 0.0756, 0.210),
+    Srgb::new(0.0637, 0.0143, 0.110),
+];
+// height of each color in the palette
+const PALETTE_KNOTS: [f32; 5] = [-1., -0.8, -0.3, 0.5, 1.];
+
 // const parameters for easy tweaking since this doesn't need to be reusable
 const POINTS_PER_ROW: usize = 16;
 const ROWS: usize = 14;
@@ -36,9 +52,7 @@ const Y_AMPLITUDE_RANGE: Range<f32> = 0.01..0.015;
 const Y_VELOCITY_RANGE: Range<f32> = 0.05 * PI..0.3 * PI;
 
 impl TriangleGrid {
-    pub fn generate(device: &wgpu::Device) -> Self {
-        let mut rng = rand::thread_rng();
-
+    pub fn generate(device: &wgpu::Device, rng: &mut impl Rng) -> Self {
         // first generate a series of rows of points;
         // we'll then turn them into triangles
         let mut pts: Vec<Vec<Point>> = Vec::new();
@@ -75,6 +89,7 @@ impl TriangleGrid {
                         Point {
                             root_pos: [x, y],
                             // color will be filled in later
+                            gradient_y: 0.,
                             color: [0.; 4],
                             // random movement parameters
                             x_phase: rng.gen_range(0.0..2. * PI),
@@ -95,20 +110,6 @@ impl TriangleGrid {
             pts.push(row_pts);
         }
 
-        // gradient for coloring the triangles
-
-        let color_curve = Linear::builder()
-            .elements([
-                Srgb::new(0.0637, 0.0143, 0.110).into_linear(),
-                Srgb::new(0.140, 0.073, 0.200).into_linear(),
-                Srgb::new(0.290, 0.0580, 0.155).into_linear(),
-                Srgb::new(0.163, 0.0756, 0.210).into_linear(),
-                Srgb::new(0.0637, 0.0143, 0.110).into_linear(),
-            ])
-            .knots([-1., -0.8, -0.3, 0.5, 1.])
-            .build()
-            .unwrap();
-
         // generate triangles from the rows of vertices
 
         let mut points = Vec::new();
@@ -121,11 +122,9 @@ impl TriangleGrid {
 
             // generate a triangle strip between the two rows
             let mut gen_triangle = |pts: [Point; 3]| {
-                let centroid_y =
+                let gradient_y =
                     (pts[0].root_pos[1] + pts[1].root_pos[1] + pts[2].root_pos[1]) / 3.;
-                let c_lin: LinSrgba = color_curve.gen(centroid_y).into_color();
-                let color = [c_lin.red, c_lin.green, c_lin.blue, c_lin.alpha];
-                points.extend(pts.into_iter().map(|p| Point { color, ..p }));
+                points.extend(pts.into_iter().map(|p| Point { gradient_y, ..p }));
             };
 
             for i in 0..shorter_row.len() - 1 {
@@ -150,10 +149,27 @@ impl TriangleGrid {
 
         let vertex_count = points.len() as u32;
 
-        Self {
+        let mut grid = Self {
             points,
             vertex_buf,
             vertex_count,
+        };
+        grid.set_palette(&DEFAULT_PALETTE.map(|c| c.into_linear()));
+        grid
+    }
+
+    /// Color the triangles with a different gradient, given in linear color
+    /// so that palettes can be blended between.
+    /// Shows up the next time the grid is updated.
+    pub fn set_palette(&mut self, palette: &[LinSrgb; 5]) {
+        let color_curve = Linear::builder()
+            .elements(*palette)
+            .knots(PALETTE_KNOTS)
+            .build()
+            .unwrap();
+        for p in &mut self.points {
+            let c_lin: LinSrgba = color_curve.gen(p.gradient_y).into_color();
+            p.color = [c_lin.red, c_lin.green, c_lin.blue, c_lin.alpha];
         }
     }
 
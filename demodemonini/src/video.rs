@@ -0,0 +1,161 @@
+//! Pre-rendered footage composited with the generative parts of the picture,
+//! decoded in the background by ffmpeg.
+
+use std::{
+    io::Read,
+    path::Path,
+    process::{Child, Command, Stdio},
+    sync::{Arc, Mutex},
+};
+
+use wgpu::util::DeviceExt;
+
+use super::{atlas::UvRect, pipelines::TexturePipeline, textures::ColorSpace};
+
+// videos are scaled to this so the texture can be created up front,
+// same aspect ratio as the picture
+const VIDEO_SIZE: (u32, u32) = (960, 720);
+const BACK_DEPTH: f32 = 0.9;
+// between the fire and the characters
+const MIDDLE_DEPTH: f32 = 0.58;
+
+/// Where the video goes among the other parts of the picture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Placement {
+    /// Behind everything except the background.
+    Back,
+    /// In front of the fire, behind the characters.
+    Middle,
+}
+
+impl Placement {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "back" => Some(Placement::Back),
+            "middle" => Some(Placement::Middle),
+            _ => None,
+        }
+    }
+
+    fn depth(self) -> f32 {
+        match self {
+            Placement::Back => BACK_DEPTH,
+            Placement::Middle => MIDDLE_DEPTH,
+        }
+    }
+}
+
+/// A video file playing in a loop as a fullscreen layer.
+pub struct VideoLayer {
+    ffmpeg: Child,
+    // the newest decoded frame, written from the decoding thread
+    latest: Arc<Mutex<Option<Vec<u8>>>>,
+    texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+    verts: wgpu::Buffer,
+}
+
+impl VideoLayer {
+    pub fn new(
+        path: &Path,
+        placement: Placement,
+        device: &wgpu::Device,
+        tex_pl: &TexturePipeline,
+        sampler: &wgpu::Sampler,
+    ) -> anyhow::Result<Self> {
+        let (width, height) = VIDEO_SIZE;
+        // -re decodes at the video's own frame rate, so it plays in real time
+        let mut ffmpeg = Command::new("ffmpeg")
+            .args(["-loglevel", "error", "-re", "-stream_loop", "-1", "-i"])
+            .arg(path)
+            .args(["-vf", &format!("scale={width}:{height}")])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgba", "-"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|err| anyhow::anyhow!("Failed to start ffmpeg: {err}"))?;
+        let mut stdout = ffmpeg.stdout.take().expect("ffmpeg stdout was piped");
+        let latest = Arc::new(Mutex::new(None));
+        std::thread::spawn({
+            let latest = latest.clone();
+            move || loop {
+                let mut frame = vec![0; (4 * width * height) as usize];
+                // ends when ffmpeg exits
+                if stdout.read_exact(&mut frame).is_err() {
+                    return;
+                }
+                *latest.lock().unwrap() = Some(frame);
+            }
+        });
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("video"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: ColorSpace::Srgb.rgba8_format(),
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let bind_group = tex_pl.create_bind_group(
+            device,
+            "video",
+            &texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            sampler,
+        );
+        let verts = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("video quad"),
+            contents: bytemuck::cast_slice(&TexturePipeline::quad_vertices(
+                [-1., -1.],
+                [1., 1.],
+                placement.depth(),
+                UvRect::FULL,
+            )),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        Ok(Self {
+            ffmpeg,
+            latest,
+            texture,
+            bind_group,
+            verts,
+        })
+    }
+
+    /// Upload the newest frame if one has been decoded since the last call.
+    pub fn update(&self, queue: &wgpu::Queue) {
+        let Some(frame) = self.latest.lock().unwrap().take() else {
+            return;
+        };
+        queue.write_texture(
+            self.texture.as_image_copy(),
+            &frame,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * VIDEO_SIZE.0),
+                rows_per_image: None,
+            },
+            self.texture.size(),
+        );
+    }
+
+    /// Draw with a pass that has a [`TexturePipeline`] set.
+    pub fn draw<'pass>(&'pass self, pass: &mut wgpu::RenderPass<'pass>) {
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.set_vertex_buffer(0, self.verts.slice(..));
+        pass.draw(0..6, 0..1);
+    }
+}
+
+impl Drop for VideoLayer {
+    fn drop(&mut self) {
+        // looping forever, ffmpeg would never exit on its own
+        let _ = self.ffmpeg.kill();
+        let _ = self.ffmpeg.wait();
+    }
+}
@@ -0,0 +1,154 @@
+//! Running as an animated desktop wallpaper behind the desktop icons.
+//!
+//! On X11 the window asks the window manager to be treated as the desktop,
+//! and on Windows it's put inside the WorkerW window Explorer draws the wallpaper in.
+//! Wayland compositors would need the layer-shell protocol, which winit doesn't support,
+//! so X11 through XWayland is the way to get a wallpaper there for now.
+//!
+//! A wallpaper spends most of its time behind other windows,
+//! so it draws at a lower frame rate to leave the GPU for the things in front.
+
+use std::time::Duration;
+
+use instant::Instant;
+use winit::{event_loop::EventLoop, window::WindowBuilder};
+
+const FPS: f64 = 20.;
+
+/// Set up the window to cover the primary monitor without decorations,
+/// marked as the desktop where that's possible before the window exists.
+pub fn window_builder<T>(
+    builder: WindowBuilder,
+    event_loop: &EventLoop<T>,
+) -> anyhow::Result<WindowBuilder> {
+    #[cfg(target_os = "linux")]
+    {
+        use winit::platform::wayland::EventLoopWindowTargetExtWayland;
+        if event_loop.is_wayland() {
+            anyhow::bail!(
+                "Wallpaper mode isn't supported on Wayland, \
+                run with WINIT_UNIX_BACKEND=x11 to use XWayland instead"
+            );
+        }
+    }
+    let monitor = event_loop
+        .primary_monitor()
+        .or_else(|| event_loop.available_monitors().next())
+        .ok_or(anyhow::anyhow!("No monitor to put the wallpaper on"))?;
+    let builder = builder
+        .with_decorations(false)
+        .with_resizable(false)
+        .with_position(monitor.position())
+        .with_inner_size(monitor.size());
+    #[cfg(target_os = "linux")]
+    let builder = {
+        use winit::platform::x11::{WindowBuilderExtX11, XWindowType};
+        builder.with_x11_window_type(vec![XWindowType::Desktop])
+    };
+    Ok(builder)
+}
+
+/// Paces frames for a window set up with [`window_builder`].
+pub struct Wallpaper {
+    next_frame: Instant,
+}
+
+impl Wallpaper {
+    /// Move the window behind the desktop icons if that has to be done after creating it.
+    pub fn attach(window: &winit::window::Window) -> anyhow::Result<Self> {
+        #[cfg(target_os = "windows")]
+        windows::attach(window)?;
+        #[cfg(not(target_os = "windows"))]
+        let _ = window;
+        Ok(Self {
+            next_frame: Instant::now(),
+        })
+    }
+
+    /// When the event loop should wake up for the next frame.
+    pub fn next_frame(&self) -> Instant {
+        self.next_frame
+    }
+
+    /// Whether it's time to draw, moving on to the next frame if it is.
+    pub fn frame_due(&mut self) -> bool {
+        let now = Instant::now();
+        if now < self.next_frame {
+            return false;
+        }
+        // counted from the frame that should've been drawn so the rate stays steady,
+        // unless drawing fell far enough behind that it's better to start over
+        self.next_frame += Duration::from_secs_f64(1. / FPS);
+        if self.next_frame < now {
+            self.next_frame = now;
+        }
+        true
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows {
+    use windows_sys::Win32::{
+        Foundation::{BOOL, HWND, LPARAM, TRUE},
+        UI::WindowsAndMessaging::{
+            EnumWindows, FindWindowExW, FindWindowW, GetSystemMetrics, SendMessageTimeoutW,
+            SetParent, SetWindowPos, SMTO_NORMAL, SM_XVIRTUALSCREEN, SM_YVIRTUALSCREEN,
+            SWP_NOACTIVATE, SWP_NOSIZE, SWP_NOZORDER,
+        },
+    };
+    use winit::platform::windows::WindowExtWindows;
+
+    // undocumented message that makes Explorer create a WorkerW window
+    // between the desktop icons and the wallpaper
+    const SPAWN_WORKER: u32 = 0x052C;
+
+    fn wide(s: &str) -> Vec<u16> {
+        s.encode_utf16().chain(Some(0)).collect()
+    }
+
+    // the WorkerW comes right after the top level window holding the icons
+    unsafe extern "system" fn find_worker(top: HWND, worker: LPARAM) -> BOOL {
+        let icons = FindWindowExW(top, 0, wide("SHELLDLL_DefView").as_ptr(), std::ptr::null());
+        if icons != 0 {
+            *(worker as *mut HWND) =
+                FindWindowExW(0, top, wide("WorkerW").as_ptr(), std::ptr::null());
+        }
+        TRUE
+    }
+
+    pub fn attach(window: &winit::window::Window) -> anyhow::Result<()> {
+        unsafe {
+            let progman = FindWindowW(wide("Progman").as_ptr(), std::ptr::null());
+            if progman == 0 {
+                anyhow::bail!("Couldn't find the desktop window, is Explorer running?");
+            }
+            let mut result = 0;
+            SendMessageTimeoutW(progman, SPAWN_WORKER, 0, 0, SMTO_NORMAL, 1000, &mut result);
+
+            let mut worker: HWND = 0;
+            EnumWindows(Some(find_worker), &mut worker as *mut HWND as LPARAM);
+            // newer versions of Windows put the WorkerW inside Progman instead
+            if worker == 0 {
+                worker = FindWindowExW(progman, 0, wide("WorkerW").as_ptr(), std::ptr::null());
+            }
+            if worker == 0 {
+                anyhow::bail!("Couldn't find the window behind the desktop icons");
+            }
+
+            let hwnd = window.hwnd() as HWND;
+            let pos = window.outer_position().unwrap_or_default();
+            SetParent(hwnd, worker);
+            // positions inside the WorkerW start from the corner of the whole virtual screen
+            SetWindowPos(
+                hwnd,
+                0,
+                pos.x - GetSystemMetrics(SM_XVIRTUALSCREEN),
+                pos.y - GetSystemMetrics(SM_YVIRTUALSCREEN),
+                0,
+                0,
+                SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE,
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+//! Options for the web build from the page's URL query,
+//! e.g. `?scale=0.5&fire=off&autoquality=off&seed=42`,
+//! so different links and embeds can show different variants of the same build.
+//! Settings saved by [`web_storage`] on an earlier visit are read the same way.
+//!
+//! Invalid values are logged and ignored rather than stopping the demo.
+
+use super::web_storage;
+
+/// Options given in the URL query or stored from an earlier visit.
+pub struct WebArgs {
+    /// Multiplier on the resolution the canvas is rendered at.
+    pub scale: f64,
+    pub seed: Option<u64>,
+    pub fire: bool,
+    pub characters: bool,
+    pub postprocess: bool,
+    /// Whether to lower the resolution automatically if frames take too long.
+    pub auto_quality: bool,
+    /// Resolution picked automatically on an earlier visit, to start from.
+    pub quality: Option<f64>,
+}
+
+impl Default for WebArgs {
+    fn default() -> Self {
+        Self {
+            scale: 1.,
+            seed: None,
+            fire: true,
+            characters: true,
+            postprocess: true,
+            auto_quality: true,
+            quality: None,
+        }
+    }
+}
+
+impl WebArgs {
+    /// Parse the settings stored from the last visit, if any,
+    /// and then the query of the page the demo is running on.
+    pub fn parse() -> Self {
+        let mut parsed = Self::default();
+        let stored = web_storage::load()
+            .and_then(|query| web_sys::UrlSearchParams::new_with_str(&query).ok());
+        let query = web_sys::window()
+            .and_then(|win| win.location().search().ok())
+            .and_then(|query| web_sys::UrlSearchParams::new_with_str(&query).ok());
+        for params in [stored, query].into_iter().flatten() {
+            parsed.apply(&params);
+        }
+        parsed
+    }
+
+    fn apply(&mut self, params: &web_sys::UrlSearchParams) {
+        if let Some(scale) = params.get("scale") {
+            match scale.parse::<f64>() {
+                Ok(scale) if scale > 0. => self.scale = scale,
+                _ => log::warn!("Ignoring invalid scale {scale:?}"),
+            }
+        }
+        if let Some(quality) = params.get("quality") {
+            match quality.parse::<f64>() {
+                Ok(quality) if quality > 0. && quality <= 1. => self.quality = Some(quality),
+                _ => log::warn!("Ignoring invalid quality {quality:?}"),
+            }
+        }
+        if let Some(seed) = params.get("seed") {
+            match seed.parse() {
+                Ok(seed) => self.seed = Some(seed),
+                Err(_) => log::warn!("Ignoring invalid seed {seed:?}"),
+            }
+        }
+        for (name, toggle) in [
+            ("fire", &mut self.fire),
+            ("characters", &mut self.characters),
+            ("postprocess", &mut self.postprocess),
+            ("autoquality", &mut self.auto_quality),
+        ] {
+            if let Some(value) = params.get(name) {
+                match value.as_str() {
+                    "on" | "true" | "1" => *toggle = true,
+                    "off" | "false" | "0" => *toggle = false,
+                    _ => log::warn!("Ignoring invalid {name} {value:?}, expected on or off"),
+                }
+            }
+        }
+    }
+}
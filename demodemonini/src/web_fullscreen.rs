@@ -0,0 +1,119 @@
+//! Showing the canvas fullscreen with the browser's Fullscreen API,
+//! toggled by double clicking or tapping the canvas or from javascript with `toggleFullscreen()`.
+
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    time::Duration,
+};
+
+use instant::Instant;
+use wasm_bindgen::{prelude::*, JsCast};
+
+use super::web_layout;
+
+thread_local! {
+    static CANVAS: RefCell<Option<web_sys::HtmlCanvasElement>> = RefCell::new(None);
+    // winit sets the canvas's size in its style, which has to be put back after fullscreen
+    static WINDOWED_SIZE: RefCell<Option<winit::dpi::LogicalSize<f64>>> = RefCell::new(None);
+}
+
+// longest time between two clicks that still counts as a double click
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(300);
+
+/// Toggle fullscreen on the canvas.
+///
+/// Browsers only allow entering fullscreen in response to user input,
+/// so this needs to be called from an input event handler.
+#[wasm_bindgen(js_name = toggleFullscreen)]
+pub fn toggle_fullscreen() -> Result<(), JsValue> {
+    let document = web_sys::window()
+        .and_then(|win| win.document())
+        .ok_or("No document to go fullscreen in")?;
+    if document.fullscreen_element().is_some() {
+        document.exit_fullscreen();
+        return Ok(());
+    }
+    CANVAS.with(|c| match &*c.borrow() {
+        Some(canvas) => {
+            WINDOWED_SIZE.with(|s| *s.borrow_mut() = Some(web_layout::css_size(canvas)));
+            canvas.request_fullscreen()
+        }
+        None => Err("The canvas hasn't been created yet".into()),
+    })
+}
+
+/// Resizes the canvas when going in and out of fullscreen
+/// and detects double clicks to toggle it.
+pub struct Fullscreen {
+    document: web_sys::Document,
+    canvas: web_sys::HtmlCanvasElement,
+    on_change: Closure<dyn FnMut()>,
+    changed: Rc<Cell<bool>>,
+    last_click: Option<Instant>,
+}
+
+impl Fullscreen {
+    pub fn new(canvas: web_sys::HtmlCanvasElement) -> Result<Self, JsValue> {
+        let document = web_sys::window()
+            .and_then(|win| win.document())
+            .ok_or("No document to go fullscreen in")?;
+        CANVAS.with(|c| *c.borrow_mut() = Some(canvas.clone()));
+
+        let changed = Rc::new(Cell::new(false));
+        let on_change = Closure::<dyn FnMut()>::new({
+            let changed = changed.clone();
+            move || changed.set(true)
+        });
+        document.add_event_listener_with_callback(
+            "fullscreenchange",
+            on_change.as_ref().unchecked_ref(),
+        )?;
+
+        Ok(Self {
+            document,
+            canvas,
+            on_change,
+            changed,
+            last_click: None,
+        })
+    }
+
+    /// Register a click or tap, toggling fullscreen if it was the second one in a row.
+    ///
+    /// Should be called on release, which browsers accept as a reason to go fullscreen.
+    pub fn click(&mut self) {
+        let now = Instant::now();
+        match self.last_click {
+            Some(last) if now - last < DOUBLE_CLICK_TIME => {
+                self.last_click = None;
+                if let Err(err) = toggle_fullscreen() {
+                    log::error!("Failed to toggle fullscreen: {err:?}");
+                }
+            }
+            _ => self.last_click = Some(now),
+        }
+    }
+
+    /// The size the canvas should be resized to
+    /// if it's gone in or out of fullscreen since the last call.
+    pub fn take_new_size(&self) -> Option<winit::dpi::LogicalSize<f64>> {
+        if !self.changed.replace(false) {
+            return None;
+        }
+        if self.document.fullscreen_element().is_some() {
+            Some(web_layout::css_size(&self.canvas))
+        } else {
+            WINDOWED_SIZE.with(|s| s.borrow_mut().take())
+        }
+    }
+}
+
+impl Drop for Fullscreen {
+    fn drop(&mut self) {
+        let _ = self.document.remove_event_listener_with_callback(
+            "fullscreenchange",
+            self.on_change.as_ref().unchecked_ref(),
+        );
+    }
+}
@@ -0,0 +1,140 @@
+//! Fitting the canvas into the layout of the page embedding it.
+//!
+//! When the canvas is placed in an element with the id `wgpu-canvas`,
+//! it's resized to fill that element whenever the element changes size.
+//! The element should get its size from the page layout and not from its contents,
+//! otherwise it would grow along with the canvas.
+//!
+//! The canvas is rendered at the display's `devicePixelRatio` to look sharp on high-DPI screens,
+//! up to a cap that the page can set with a `data-max-pixel-ratio` attribute on the element,
+//! and scaled further by the `scale` query parameter.
+
+use std::{cell::Cell, rc::Rc};
+
+use wasm_bindgen::{prelude::*, JsCast};
+
+// retina screens are sharp enough at this and higher ratios get expensive fast
+const DEFAULT_MAX_PIXEL_RATIO: f64 = 2.;
+
+/// Limits on how many canvas pixels are rendered per CSS pixel.
+///
+/// Winit always sizes the canvas at the full device pixel ratio,
+/// so this is applied on top after every resize.
+pub struct PixelRatioCap {
+    max_ratio: f64,
+    // set by the page to trade sharpness for speed or the other way around
+    scale: f64,
+    // lowered automatically on slow devices
+    quality: f64,
+    // the canvas can't be larger than a texture or the gbuffer couldn't be created
+    max_dimension: u32,
+}
+
+impl PixelRatioCap {
+    pub fn new(parent: Option<&web_sys::Element>, scale: f64, max_dimension: u32) -> Self {
+        let max_ratio = parent
+            .and_then(|parent| parent.get_attribute("data-max-pixel-ratio"))
+            .and_then(|attr| match attr.parse::<f64>() {
+                Ok(ratio) if ratio > 0. => Some(ratio),
+                _ => {
+                    log::warn!("Ignoring invalid data-max-pixel-ratio {attr:?}");
+                    None
+                }
+            })
+            .unwrap_or(DEFAULT_MAX_PIXEL_RATIO);
+        Self {
+            max_ratio,
+            scale,
+            quality: 1.,
+            max_dimension,
+        }
+    }
+
+    /// Change the resolution multiplier picked by [`crate::web_quality`],
+    /// taking effect on the next call to `apply`.
+    pub fn set_quality(&mut self, quality: f64) {
+        self.quality = quality;
+    }
+
+    /// Set the resolution of the canvas's backing store within the cap,
+    /// keeping its size on the page. Returns the new resolution.
+    pub fn apply(&self, canvas: &web_sys::HtmlCanvasElement) -> winit::dpi::PhysicalSize<u32> {
+        let css_size = css_size(canvas);
+        let device_ratio = web_sys::window().map_or(1., |win| win.device_pixel_ratio());
+        let mut ratio = device_ratio.min(self.max_ratio) * self.scale * self.quality;
+        let largest_side = css_size.width.max(css_size.height) * ratio;
+        if largest_side > self.max_dimension as f64 {
+            ratio *= self.max_dimension as f64 / largest_side;
+        }
+
+        let size = winit::dpi::PhysicalSize::new(
+            ((css_size.width * ratio).floor() as u32).max(1),
+            ((css_size.height * ratio).floor() as u32).max(1),
+        );
+        canvas.set_width(size.width);
+        canvas.set_height(size.height);
+        size
+    }
+}
+
+/// The size the canvas takes on the page in CSS pixels,
+/// which stays the same regardless of the resolution it's rendered at.
+pub fn css_size(canvas: &web_sys::HtmlCanvasElement) -> winit::dpi::LogicalSize<f64> {
+    let rect = canvas.get_bounding_client_rect();
+    winit::dpi::LogicalSize::new(rect.width(), rect.height())
+}
+
+/// Watches the size of the canvas's parent element.
+pub struct ParentSizeObserver {
+    observer: web_sys::ResizeObserver,
+    // kept alive for as long as the observer can call it
+    _on_resize: Closure<dyn FnMut(js_sys::Array)>,
+    new_size: Rc<Cell<Option<winit::dpi::LogicalSize<f64>>>>,
+}
+
+impl ParentSizeObserver {
+    pub fn new(parent: &web_sys::Element) -> Result<Self, JsValue> {
+        let new_size = Rc::new(Cell::new(None));
+        let on_resize = Closure::<dyn FnMut(js_sys::Array)>::new({
+            let new_size = new_size.clone();
+            move |entries: js_sys::Array| {
+                // only the latest size matters if there are several
+                let Some(entry) = entries
+                    .iter()
+                    .last()
+                    .and_then(|entry| entry.dyn_into::<web_sys::ResizeObserverEntry>().ok())
+                else {
+                    return;
+                };
+                let rect = entry.content_rect();
+                // the surface can't be zero-sized, keep the old size while the parent is hidden
+                if rect.width() >= 1. && rect.height() >= 1. {
+                    new_size.set(Some(winit::dpi::LogicalSize::new(
+                        rect.width(),
+                        rect.height(),
+                    )));
+                }
+            }
+        });
+        let observer = web_sys::ResizeObserver::new(on_resize.as_ref().unchecked_ref())?;
+        observer.observe(parent);
+
+        Ok(Self {
+            observer,
+            _on_resize: on_resize,
+            new_size,
+        })
+    }
+
+    /// The size the canvas should be resized to,
+    /// if the parent has changed size since the last call.
+    pub fn take_new_size(&self) -> Option<winit::dpi::LogicalSize<f64>> {
+        self.new_size.take()
+    }
+}
+
+impl Drop for ParentSizeObserver {
+    fn drop(&mut self) {
+        self.observer.disconnect();
+    }
+}
@@ -0,0 +1,56 @@
+//! A minimal loading indicator shown on the page while the GPU and assets are being set up,
+//! so viewers on slow connections don't stare at a blank canvas.
+
+use wasm_bindgen::{prelude::*, JsCast};
+
+const SPINNER_HTML: &str = "\
+<style>@keyframes demodemonini-spin { to { transform: rotate(360deg); } }</style>
+<div style=\"width: 32px; height: 32px; margin: 0 auto 12px;
+  border: 4px solid rgba(255, 255, 255, 0.2); border-top-color: rgb(255, 90, 90);
+  border-radius: 50%; animation: demodemonini-spin 1s linear infinite;\"></div>";
+
+const CONTAINER_STYLE: &str = "position: absolute; top: 50%; left: 50%; \
+    transform: translate(-50%, -50%); text-align: center; pointer-events: none; \
+    font-family: monospace; color: rgb(255, 255, 255);";
+
+/// A spinner with a status message in the middle of the canvas's parent element.
+/// Removed from the page when dropped.
+pub struct LoadingIndicator {
+    container: web_sys::Element,
+    status: web_sys::Element,
+}
+
+impl LoadingIndicator {
+    pub fn show(parent: &web_sys::Element) -> Result<Self, JsValue> {
+        let window = web_sys::window().ok_or("No window to show loading in")?;
+        let document = window.document().ok_or("No document to show loading in")?;
+
+        // centering is relative to the closest positioned element, make sure that's the parent
+        if let Some(style) = window.get_computed_style(parent)? {
+            if style.get_property_value("position")? == "static" {
+                let parent: &web_sys::HtmlElement = parent.dyn_ref().ok_or("Parent isn't HTML")?;
+                parent.style().set_property("position", "relative")?;
+            }
+        }
+
+        let container = document.create_element("div")?;
+        container.set_attribute("style", CONTAINER_STYLE)?;
+        container.set_inner_html(SPINNER_HTML);
+        let status = document.create_element("div")?;
+        container.append_child(&status)?;
+        parent.append_child(&container)?;
+
+        Ok(Self { container, status })
+    }
+
+    /// Show what's currently being waited for.
+    pub fn set_status(&self, text: &str) {
+        self.status.set_text_content(Some(text));
+    }
+}
+
+impl Drop for LoadingIndicator {
+    fn drop(&mut self) {
+        self.container.remove();
+    }
+}
@@ -0,0 +1,92 @@
+//! Lowering the resolution on devices that can't keep up with the target frame rate.
+//!
+//! Frame times are averaged over short windows during the first few seconds,
+//! and the resolution is stepped down whenever a window is clearly too slow.
+//! It's never stepped back up: with vsync on the web, frame times can't go below
+//! the display's refresh interval, so there's no telling how much headroom there is.
+//! Only stepping down, with a window skipped after each step for things to settle,
+//! means the quality can't bounce back and forth.
+
+use std::time::Duration;
+
+use instant::Instant;
+
+// resolution multipliers from best to worst
+const SCALES: [f64; 4] = [1., 0.75, 0.5, 0.35];
+const TARGET_FRAME_TIME: Duration = Duration::from_micros(16_667);
+// some slack so a display running at slightly under 60 Hz doesn't count as too slow
+const TOO_SLOW_RATIO: f64 = 1.2;
+const WINDOW_LENGTH: Duration = Duration::from_secs(1);
+const ADAPT_DURATION: Duration = Duration::from_secs(8);
+
+pub struct AutoQuality {
+    start: Instant,
+    window_start: Instant,
+    window_frames: u32,
+    // the first window after starting or changing the resolution isn't representative
+    settling: bool,
+    level: usize,
+}
+
+impl AutoQuality {
+    /// Start adapting from the highest scale that's at most `scale`,
+    /// e.g. one found on an earlier visit.
+    pub fn starting_at(scale: f64) -> Self {
+        let level = SCALES
+            .iter()
+            .position(|&s| s <= scale)
+            .unwrap_or(SCALES.len() - 1);
+        let now = Instant::now();
+        Self {
+            start: now,
+            window_start: now,
+            window_frames: 0,
+            settling: true,
+            level,
+        }
+    }
+
+    /// The resolution scale currently picked.
+    pub fn scale(&self) -> f64 {
+        SCALES[self.level]
+    }
+
+    /// Throw away the current measurement, e.g. after being paused.
+    pub fn restart_window(&mut self) {
+        self.window_start = Instant::now();
+        self.window_frames = 0;
+        self.settling = true;
+    }
+
+    /// Count a drawn frame. Returns a new resolution scale if it should change.
+    pub fn frame(&mut self) -> Option<f64> {
+        self.window_frames += 1;
+        let now = Instant::now();
+        let window_time = now - self.window_start;
+        if window_time < WINDOW_LENGTH
+            || now - self.start > ADAPT_DURATION
+            || self.level == SCALES.len() - 1
+        {
+            return None;
+        }
+
+        let avg_frame_time = window_time / self.window_frames;
+        let settling = self.settling;
+        self.restart_window();
+        self.settling = false;
+        if settling
+            || avg_frame_time.as_secs_f64() < TOO_SLOW_RATIO * TARGET_FRAME_TIME.as_secs_f64()
+        {
+            return None;
+        }
+
+        self.level += 1;
+        self.settling = true;
+        log::info!(
+            "Frames took {:.1} ms on average, lowering resolution to {}x",
+            avg_frame_time.as_secs_f64() * 1000.,
+            SCALES[self.level]
+        );
+        Some(SCALES[self.level])
+    }
+}
@@ -0,0 +1,93 @@
+//! Recording the canvas into a webm file in the browser,
+//! started and stopped from javascript with `startRecording()` and `stopRecording()`.
+
+use std::cell::RefCell;
+
+use wasm_bindgen::{prelude::*, JsCast};
+
+thread_local! {
+    static CANVAS: RefCell<Option<web_sys::HtmlCanvasElement>> = RefCell::new(None);
+    static RECORDER: RefCell<Option<web_sys::MediaRecorder>> = RefCell::new(None);
+}
+
+const FILE_NAME: &str = "demodemonini.webm";
+const FRAME_RATE: f64 = 60.;
+
+/// Set the canvas that recordings capture.
+pub fn set_canvas(canvas: web_sys::HtmlCanvasElement) {
+    CANVAS.with(|c| *c.borrow_mut() = Some(canvas));
+}
+
+/// Start recording the canvas.
+/// Does nothing if a recording is already running.
+#[wasm_bindgen(js_name = startRecording)]
+pub fn start_recording() -> Result<(), JsValue> {
+    if RECORDER.with(|r| r.borrow().is_some()) {
+        return Ok(());
+    }
+    let stream = CANVAS.with(|c| match &*c.borrow() {
+        Some(canvas) => canvas.capture_stream_with_frame_request_rate(FRAME_RATE),
+        None => Err("The canvas hasn't been created yet".into()),
+    })?;
+
+    // vp9 looks much better at the same size, but not every browser can encode it
+    let mime_type = ["video/webm;codecs=vp9", "video/webm"]
+        .into_iter()
+        .find(|ty| web_sys::MediaRecorder::is_type_supported(ty))
+        .ok_or("This browser can't record webm video")?;
+    let recorder = web_sys::MediaRecorder::new_with_media_stream_and_media_recorder_options(
+        &stream,
+        web_sys::MediaRecorderOptions::new().mime_type(mime_type),
+    )?;
+
+    // without a timeslice given to `start`, all the data arrives in one chunk when stopped
+    let chunks = js_sys::Array::new();
+    let on_data = Closure::<dyn FnMut(web_sys::BlobEvent)>::new({
+        let chunks = chunks.clone();
+        move |event: web_sys::BlobEvent| {
+            if let Some(data) = event.data() {
+                chunks.push(&data);
+            }
+        }
+    });
+    recorder.set_ondataavailable(Some(on_data.as_ref().unchecked_ref()));
+    let on_stop = Closure::once_into_js(move || {
+        if let Err(err) = download(&chunks) {
+            log::error!("Failed to save recording: {err:?}");
+        }
+        // the data callback can't be dropped until the recorder is done with it
+        drop(on_data);
+    });
+    recorder.set_onstop(Some(on_stop.unchecked_ref()));
+
+    recorder.start()?;
+    RECORDER.with(|r| *r.borrow_mut() = Some(recorder));
+    Ok(())
+}
+
+/// Stop recording and download what was recorded.
+/// Does nothing if there's no recording running.
+#[wasm_bindgen(js_name = stopRecording)]
+pub fn stop_recording() -> Result<(), JsValue> {
+    match RECORDER.with(|r| r.borrow_mut().take()) {
+        Some(recorder) => recorder.stop(),
+        None => Ok(()),
+    }
+}
+
+// save recorded chunks through a temporary link, which is the only way to start a download
+fn download(chunks: &js_sys::Array) -> Result<(), JsValue> {
+    let blob = web_sys::Blob::new_with_blob_sequence_and_options(
+        chunks,
+        web_sys::BlobPropertyBag::new().type_("video/webm"),
+    )?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+    let document = web_sys::window()
+        .and_then(|win| win.document())
+        .ok_or("No document to download from")?;
+    let link: web_sys::HtmlAnchorElement = document.create_element("a")?.dyn_into()?;
+    link.set_href(&url);
+    link.set_download(FILE_NAME);
+    link.click();
+    web_sys::Url::revoke_object_url(&url)
+}
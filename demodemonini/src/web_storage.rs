@@ -0,0 +1,37 @@
+//! Remembering the viewer's settings between visits with localStorage.
+//!
+//! Settings are stored in the same format as the URL query read by [`crate::web_args`],
+//! and options given in the URL take priority over stored ones.
+
+use super::scene::Scene;
+
+const KEY: &str = "demodemonini-settings";
+
+fn local_storage() -> Option<web_sys::Storage> {
+    // this fails e.g. when storage is disabled in the browser's privacy settings
+    web_sys::window()?.local_storage().ok()?
+}
+
+/// The stored settings as a query string, if there are any.
+pub fn load() -> Option<String> {
+    local_storage()?.get_item(KEY).ok()?
+}
+
+/// Store the layer toggles of the scene and the resolution picked by [`crate::web_quality`].
+pub fn save(scene: &Scene, quality: f64) {
+    let Some(storage) = local_storage() else {
+        return;
+    };
+    let Ok(params) = web_sys::UrlSearchParams::new() else {
+        return;
+    };
+    let on_off = |toggle: bool| if toggle { "on" } else { "off" };
+    params.append("fire", on_off(scene.draw_fire));
+    params.append("characters", on_off(scene.draw_characters));
+    params.append("postprocess", on_off(scene.draw_postprocess));
+    params.append("quality", &quality.to_string());
+
+    if let Err(err) = storage.set_item(KEY, &String::from(params.to_string())) {
+        log::warn!("Failed to save settings: {err:?}");
+    }
+}
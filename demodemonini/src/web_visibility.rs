@@ -0,0 +1,43 @@
+//! Pausing while the page is hidden, e.g. in a background tab,
+//! so the demo doesn't keep spending the viewer's battery.
+
+use wasm_bindgen::{prelude::*, JsCast};
+
+/// Wakes up the event loop when the page is hidden or shown.
+pub struct PageVisibility {
+    document: web_sys::Document,
+    on_change: Closure<dyn FnMut()>,
+}
+
+impl PageVisibility {
+    pub fn new(proxy: winit::event_loop::EventLoopProxy<()>) -> Result<Self, JsValue> {
+        let document = web_sys::window()
+            .and_then(|win| win.document())
+            .ok_or("No document to watch")?;
+        // the event itself carries nothing, the loop checks `is_hidden` when woken up
+        let on_change = Closure::<dyn FnMut()>::new(move || {
+            let _ = proxy.send_event(());
+        });
+        document.add_event_listener_with_callback(
+            "visibilitychange",
+            on_change.as_ref().unchecked_ref(),
+        )?;
+        Ok(Self {
+            document,
+            on_change,
+        })
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.document.hidden()
+    }
+}
+
+impl Drop for PageVisibility {
+    fn drop(&mut self) {
+        let _ = self.document.remove_event_listener_with_callback(
+            "visibilitychange",
+            self.on_change.as_ref().unchecked_ref(),
+        );
+    }
+}
@@ -0,0 +1,44 @@
+//! Camera input for interactive installations,
+//! e.g. burning the silhouettes of people in front of the piece into the fire.
+
+use std::sync::{Arc, Mutex};
+
+use nokhwa::{
+    pixel_format::RgbFormat,
+    utils::{CameraIndex, RequestedFormat, RequestedFormatType},
+    CallbackCamera,
+};
+
+pub struct Webcam {
+    // frames stop arriving when the camera is dropped
+    _camera: CallbackCamera,
+    // written from the camera's own thread
+    latest: Arc<Mutex<Option<image::RgbImage>>>,
+}
+
+impl Webcam {
+    /// Start capturing from the camera with the given index, 0 being the system's default.
+    pub fn open(index: u32) -> anyhow::Result<Self> {
+        let latest = Arc::new(Mutex::new(None));
+        let format =
+            RequestedFormat::new::<RgbFormat>(RequestedFormatType::AbsoluteHighestFrameRate);
+        let mut camera = CallbackCamera::new(CameraIndex::Index(index), format, {
+            let latest = latest.clone();
+            move |buffer| match buffer.decode_image::<RgbFormat>() {
+                Ok(frame) => *latest.lock().unwrap() = Some(frame),
+                Err(err) => eprintln!("Failed to decode camera frame: {err}"),
+            }
+        })?;
+        camera.open_stream()?;
+        println!("Capturing from camera {index}");
+        Ok(Self {
+            _camera: camera,
+            latest,
+        })
+    }
+
+    /// The newest frame from the camera, if one has arrived since the last call.
+    pub fn take_frame(&self) -> Option<image::RgbImage> {
+        self.latest.lock().unwrap().take()
+    }
+}
@@ -0,0 +1,286 @@
+//! Bloom over the finished frame,
+//! so the particle streaks and the staff moon glow into their surroundings
+//! instead of being hard-edged bright lines.
+//!
+//! The frame is copied out of the surface, its bright parts are picked out
+//! at a lower resolution, blurred and added back on top of it.
+
+use std::error::Error;
+
+use starframe as sf;
+
+use sf::wgpu;
+
+// the blur is done at this fraction of the frame's resolution,
+// which also makes it spread further for the same number of samples
+const DOWNSCALE: u32 = 4;
+// brighter than the frame can show, so the light adds up where it overlaps
+const BLUR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+pub struct Bloom {
+    bright_pipeline: wgpu::RenderPipeline,
+    blur_h_pipeline: wgpu::RenderPipeline,
+    blur_v_pipeline: wgpu::RenderPipeline,
+    shader: wgpu::ShaderModule,
+    pipeline_layout: wgpu::PipelineLayout,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    targets: Option<Targets>,
+}
+
+// textures the passes go through, made again when the frame changes size
+struct Targets {
+    format: wgpu::TextureFormat,
+    size: wgpu::Extent3d,
+    // draws in the surface's format, so it's made along with the textures
+    add_pipeline: wgpu::RenderPipeline,
+    // copy of the frame, since the surface can't be read while drawing on it
+    frame: wgpu::Texture,
+    frame_bind_group: wgpu::BindGroup,
+    // the bright parts and the blur go back and forth between these two
+    bright: wgpu::TextureView,
+    bright_bind_group: wgpu::BindGroup,
+    blurred: wgpu::TextureView,
+    blurred_bind_group: wgpu::BindGroup,
+}
+
+impl Bloom {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("bloom"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("bloom.wgsl").into()),
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("bloom"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("bloom"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("bloom"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let pipeline = |entry_point| {
+            create_pipeline(
+                device,
+                &pipeline_layout,
+                &shader,
+                entry_point,
+                BLUR_FORMAT,
+                None,
+            )
+        };
+        Self {
+            bright_pipeline: pipeline("fs_bright"),
+            blur_h_pipeline: pipeline("fs_blur_h"),
+            blur_v_pipeline: pipeline("fs_blur_v"),
+            shader,
+            pipeline_layout,
+            bind_group_layout,
+            sampler,
+            targets: None,
+        }
+    }
+
+    /// Record the passes that add bloom on top of the frame in `surface`.
+    /// Fails if the surface doesn't have `COPY_SRC` usage.
+    pub fn apply(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        surface: &wgpu::Texture,
+    ) -> Result<(), Box<dyn Error>> {
+        if !surface.usage().contains(wgpu::TextureUsages::COPY_SRC) {
+            return Err("The surface can't be read back".into());
+        }
+        let size = surface.size();
+        let format = surface.format();
+        let targets = match self.targets.take() {
+            Some(targets) if targets.size == size && targets.format == format => targets,
+            _ => self.create_targets(device, format, size),
+        };
+
+        encoder.copy_texture_to_texture(
+            surface.as_image_copy(),
+            targets.frame.as_image_copy(),
+            size,
+        );
+        let surface_view = surface.create_view(&wgpu::TextureViewDescriptor::default());
+        for (pipeline, source, target) in [
+            (
+                &self.bright_pipeline,
+                &targets.frame_bind_group,
+                &targets.bright,
+            ),
+            (
+                &self.blur_h_pipeline,
+                &targets.bright_bind_group,
+                &targets.blurred,
+            ),
+            (
+                &self.blur_v_pipeline,
+                &targets.blurred_bind_group,
+                &targets.bright,
+            ),
+            (
+                &targets.add_pipeline,
+                &targets.bright_bind_group,
+                &surface_view,
+            ),
+        ] {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("bloom"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        // every pass covers its whole target,
+                        // and the last one adds on top of the frame
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, source, &[]);
+            pass.draw(0..3, 0..1);
+        }
+        self.targets = Some(targets);
+        Ok(())
+    }
+
+    fn create_targets(
+        &self,
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        size: wgpu::Extent3d,
+    ) -> Targets {
+        let create_texture = |label, format, size| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            })
+        };
+        let bind_group = |view: &wgpu::TextureView| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("bloom"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                ],
+            })
+        };
+
+        let frame = create_texture("bloom frame", format, size);
+        let frame_view = frame.create_view(&wgpu::TextureViewDescriptor::default());
+        let blur_size = wgpu::Extent3d {
+            width: size.width.div_ceil(DOWNSCALE),
+            height: size.height.div_ceil(DOWNSCALE),
+            depth_or_array_layers: 1,
+        };
+        let bright = create_texture("bloom bright", BLUR_FORMAT, blur_size)
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let blurred = create_texture("bloom blurred", BLUR_FORMAT, blur_size)
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        Targets {
+            format,
+            size,
+            add_pipeline: create_pipeline(
+                device,
+                &self.pipeline_layout,
+                &self.shader,
+                "fs_add",
+                format,
+                Some(wgpu::BlendState {
+                    color: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::One,
+                        dst_factor: wgpu::BlendFactor::One,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                    alpha: wgpu::BlendComponent::REPLACE,
+                }),
+            ),
+            frame_bind_group: bind_group(&frame_view),
+            frame,
+            bright_bind_group: bind_group(&bright),
+            bright,
+            blurred_bind_group: bind_group(&blurred),
+            blurred,
+        }
+    }
+}
+
+fn create_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    fragment_entry: &str,
+    format: wgpu::TextureFormat,
+    blend: Option<wgpu::BlendState>,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(fragment_entry),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: fragment_entry,
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    })
+}
@@ -0,0 +1,71 @@
+// bloom over the finished frame, see bloom.rs
+
+// the brightness where things start to bloom, fully blooming at 1,
+// and how strongly the blurred light is added back on top
+const THRESHOLD: f32 = 0.5;
+const INTENSITY: f32 = 0.8;
+
+@group(0) @binding(0)
+var src_tex: texture_2d<f32>;
+@group(0) @binding(1)
+var src_samp: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// a single triangle covering the whole target, drawn with 3 vertices and no buffers
+@vertex
+fn vs_main(@builtin(vertex_index) idx: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((idx << 1u) & 2u), f32(idx & 2u));
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(2. * uv.x - 1., 1. - 2. * uv.y, 0., 1.);
+    out.uv = uv;
+    return out;
+}
+
+// downsample the frame, keeping only the bright parts.
+// four filtered samples a texel apart cover a 4x4 block of the frame
+@fragment
+fn fs_bright(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1. / vec2<f32>(textureDimensions(src_tex));
+    let color = 0.25 * (
+        textureSample(src_tex, src_samp, in.uv + vec2<f32>(-1., -1.) * texel).rgb
+        + textureSample(src_tex, src_samp, in.uv + vec2<f32>(1., -1.) * texel).rgb
+        + textureSample(src_tex, src_samp, in.uv + vec2<f32>(-1., 1.) * texel).rgb
+        + textureSample(src_tex, src_samp, in.uv + vec2<f32>(1., 1.) * texel).rgb
+    );
+    let brightness = max(color.r, max(color.g, color.b));
+    // eased in so things don't pop as they cross the threshold
+    return vec4<f32>(smoothstep(THRESHOLD, 1., brightness) * color, 1.);
+}
+
+// gaussian blur in one direction, separated into a horizontal and a vertical pass
+fn blur(uv: vec2<f32>, dir: vec2<f32>) -> vec4<f32> {
+    var weights = array<f32, 5>(0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+    let step = dir / vec2<f32>(textureDimensions(src_tex));
+    var color = weights[0] * textureSample(src_tex, src_samp, uv).rgb;
+    for (var i = 1; i < 5; i++) {
+        let offset = f32(i) * step;
+        color += weights[i] * textureSample(src_tex, src_samp, uv + offset).rgb;
+        color += weights[i] * textureSample(src_tex, src_samp, uv - offset).rgb;
+    }
+    return vec4<f32>(color, 1.);
+}
+
+@fragment
+fn fs_blur_h(in: VertexOutput) -> @location(0) vec4<f32> {
+    return blur(in.uv, vec2<f32>(1., 0.));
+}
+
+@fragment
+fn fs_blur_v(in: VertexOutput) -> @location(0) vec4<f32> {
+    return blur(in.uv, vec2<f32>(0., 1.));
+}
+
+// drawn with additive blending on top of the frame
+@fragment
+fn fs_add(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(INTENSITY * textureSample(src_tex, src_samp, in.uv).rgb, 1.);
+}
@@ -0,0 +1,196 @@
+//! Settings for the feel of the particle choreography, the soundtrack, the parallax
+//! and live control,
+//! read from a TOML file at startup and again whenever it changes
+//! so they can be tuned while the picture is running.
+//!
+//! Anything missing from the file keeps its default value.
+
+use std::{path::PathBuf, time::SystemTime};
+
+use serde::Deserialize;
+
+/// Everything in the config file, each part in a table of its own.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub particles: ParticleConfig,
+    pub soundtrack: SoundtrackConfig,
+    pub parallax: ParallaxConfig,
+    pub control: ControlConfig,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ParticleConfig {
+    /// Pull towards the staff, divided by the squared distance to it.
+    pub gravity_strength: f32,
+    pub max_speed: f32,
+    /// Distance from the staff where particles stop falling
+    /// and take a curved path the rest of the way.
+    pub orbit_distance: f32,
+    /// Range of the number of points in a trail.
+    pub trail_length: (usize, usize),
+    pub trail_width: (f32, f32),
+    /// Chance of a particle spawning from the moon on each tick.
+    pub spawn_probability: f64,
+    /// How hard the swirling wind pushes falling particles around, 0 for no wind.
+    pub wind_strength: f32,
+    /// Rough size of the wind's swirls.
+    pub wind_scale: f32,
+    /// How fast the swirls change shape.
+    pub wind_change_rate: f32,
+    /// Most particles alive at once. Fewer are allowed if frames start taking too long.
+    pub max_particles: usize,
+    /// How often particles spawned from the moon behave in each way.
+    pub behaviors: BehaviorWeights,
+}
+
+impl Default for ParticleConfig {
+    fn default() -> Self {
+        Self {
+            gravity_strength: 10000.,
+            max_speed: 10.,
+            orbit_distance: 0.25,
+            trail_length: (80, 160),
+            trail_width: (0.005, 0.015),
+            spawn_probability: 0.05,
+            wind_strength: 0.05,
+            wind_scale: 0.3,
+            wind_change_rate: 0.2,
+            max_particles: 400,
+            behaviors: BehaviorWeights::default(),
+        }
+    }
+}
+
+/// Relative chances of each [`Behavior`][super::particles::Behavior],
+/// 0 to never pick one.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct BehaviorWeights {
+    pub gravity: f32,
+    pub orbit: f32,
+    pub spiral: f32,
+    pub snowfall: f32,
+}
+
+impl Default for BehaviorWeights {
+    fn default() -> Self {
+        Self {
+            gravity: 1.,
+            orbit: 0.2,
+            spiral: 0.2,
+            snowfall: 0.1,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct SoundtrackConfig {
+    /// Audio file looped in the background, none by default.
+    pub path: Option<PathBuf>,
+    pub volume: f32,
+    /// Volume relative to the usual one at the height of the charge event.
+    pub duck_volume: f32,
+}
+
+impl Default for SoundtrackConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            volume: 0.5,
+            duck_volume: 0.6,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ParallaxConfig {
+    /// How far the farthest layers move with the cursor, 0 for no parallax.
+    pub strength: f32,
+    /// Seconds without the cursor moving before the layers start drifting by themselves.
+    pub idle_time: f32,
+}
+
+impl Default for ParallaxConfig {
+    fn default() -> Self {
+        Self {
+            strength: 0.02,
+            idle_time: 5.,
+        }
+    }
+}
+
+/// Where live control comes from, only read at startup.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ControlConfig {
+    /// MIDI input to listen to, any whose name contains this. None by default.
+    pub midi_port: Option<String>,
+    /// MIDI controller number that sets the spawn rate, the mod wheel by default.
+    pub spawn_rate_cc: u8,
+    /// Address to listen to OSC messages on, e.g. `0.0.0.0:9000`. None by default.
+    pub osc_address: Option<String>,
+}
+
+impl Default for ControlConfig {
+    fn default() -> Self {
+        Self {
+            midi_port: None,
+            spawn_rate_cc: 1,
+            osc_address: None,
+        }
+    }
+}
+
+/// A config file watched for changes.
+pub struct ConfigFile {
+    path: PathBuf,
+    // when the file was last read, None if it hasn't been or doesn't exist
+    modified: Option<SystemTime>,
+}
+
+impl ConfigFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            modified: None,
+        }
+    }
+
+    /// Read the config if the file has changed since the last call.
+    ///
+    /// Errors are printed and the file is tried again when it next changes,
+    /// so that a typo while tuning doesn't stop the picture.
+    pub fn reload_if_changed(&mut self) -> Option<Config> {
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|meta| meta.modified())
+            .ok();
+        if modified.is_none() || modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+
+        let parsed: Result<Config, String> = std::fs::read_to_string(&self.path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| toml::from_str(&text).map_err(|err| err.to_string()));
+        match parsed {
+            Ok(mut config) => {
+                // it's a chance, anything outside 0 to 1 would stop the picture
+                let chance = &mut config.particles.spawn_probability;
+                *chance = if chance.is_nan() {
+                    0.
+                } else {
+                    chance.clamp(0., 1.)
+                };
+                Some(config)
+            }
+            Err(err) => {
+                eprintln!("Failed to load {}: {err}", self.path.display());
+                None
+            }
+        }
+    }
+}
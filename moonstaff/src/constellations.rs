@@ -0,0 +1,79 @@
+//! Star patterns around the moon that particles spawn along when nobody is interacting,
+//! so the picture keeps a composition of its own while it's left alone.
+
+use rand::Rng;
+use starframe as sf;
+
+use super::MOON_RADIUS;
+
+// points relative to the moon's center in multiples of its radius,
+// visited in order so particles trace out each figure
+const CONSTELLATIONS: [&[(f32, f32)]; 4] = [
+    // a crown arching over the top
+    &[
+        (-1.3, 0.6),
+        (-0.9, 1.),
+        (-0.45, 1.2),
+        (0., 1.3),
+        (0.45, 1.2),
+        (0.9, 1.),
+        (1.3, 0.6),
+    ],
+    // a hook curling around the right side
+    &[
+        (1.1, -1.1),
+        (1.4, -0.6),
+        (1.55, 0.),
+        (1.45, 0.5),
+        (1.2, 0.9),
+        (0.95, 0.8),
+    ],
+    // a wing spread out below
+    &[
+        (-1.4, -0.5),
+        (-1., -0.9),
+        (-0.5, -1.15),
+        (-0.1, -1.),
+        (0.3, -1.2),
+        (0.8, -1.05),
+        (0.5, -0.75),
+    ],
+    // a small cross on the left
+    &[
+        (-1.6, 0.3),
+        (-1.6, -0.1),
+        (-1.6, -0.5),
+        (-1.9, -0.1),
+        (-1.3, -0.1),
+    ],
+];
+// random offset from each star so repeats don't land on the exact same spot,
+// in multiples of the moon's radius
+const JITTER: f32 = 0.04;
+
+#[derive(Default)]
+pub struct Constellations {
+    current: usize,
+    next_star: usize,
+}
+
+impl Constellations {
+    /// Where to spawn the next particle relative to the moon's center,
+    /// going through the stars of one constellation and then on to the next.
+    pub fn next_spawn(&mut self) -> sf::Vec2 {
+        let stars = CONSTELLATIONS[self.current];
+        let (x, y) = stars[self.next_star];
+        self.next_star += 1;
+        if self.next_star >= stars.len() {
+            self.next_star = 0;
+            self.current = (self.current + 1) % CONSTELLATIONS.len();
+        }
+
+        let mut rng = rand::thread_rng();
+        let jitter = sf::Vec2::new(
+            rng.gen_range(-JITTER..JITTER),
+            rng.gen_range(-JITTER..JITTER),
+        );
+        MOON_RADIUS * (sf::Vec2::new(x, y) + jitter)
+    }
+}
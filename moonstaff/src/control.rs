@@ -0,0 +1,131 @@
+//! Controlling the density of the particles live from a MIDI controller or over OSC,
+//! on top of the keyboard controls in main.
+//!
+//! MIDI notes emit a burst of particles, more the harder they're hit,
+//! and a control change sets the spawn rate.
+//! OSC messages `/moonstaff/burst <count>` and `/moonstaff/spawn_rate <0 to 1>`
+//! do the same, with ints or floats as arguments.
+
+use std::{error::Error, net::UdpSocket, sync::mpsc};
+
+use super::config::ControlConfig;
+
+/// Most particles in a burst, e.g. from a MIDI note at full velocity.
+const MAX_BURST: usize = 60;
+
+pub enum Command {
+    /// Spawn this many particles in a ring around the moon.
+    Burst(usize),
+    /// Set the chance of a particle spawning on each tick,
+    /// from 0 for none to 1 for the most the controls allow.
+    SpawnRate(f64),
+}
+
+pub struct Control {
+    commands: mpsc::Receiver<Command>,
+    // messages stop coming when the connection is dropped
+    _midi: Option<midir::MidiInputConnection<()>>,
+}
+
+impl Control {
+    /// Connect to the MIDI port and listen on the OSC address set in the config,
+    /// or return None if neither is set.
+    pub fn start(config: &ControlConfig) -> Result<Option<Self>, Box<dyn Error>> {
+        if config.midi_port.is_none() && config.osc_address.is_none() {
+            return Ok(None);
+        }
+        let (send, commands) = mpsc::channel();
+
+        let midi = match &config.midi_port {
+            Some(name) => Some(connect_midi(name, config.spawn_rate_cc, send.clone())?),
+            None => None,
+        };
+
+        if let Some(address) = &config.osc_address {
+            let socket = UdpSocket::bind(address)?;
+            println!("Listening for OSC on {}", socket.local_addr()?);
+            std::thread::spawn(move || {
+                let mut packet = [0; 1024];
+                while let Ok(len) = socket.recv(&mut packet) {
+                    let Some(command) = parse_osc(&packet[..len]) else {
+                        continue;
+                    };
+                    if send.send(command).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+
+        Ok(Some(Self {
+            commands,
+            _midi: midi,
+        }))
+    }
+
+    /// Commands received since the last call.
+    pub fn poll(&self) -> impl Iterator<Item = Command> + '_ {
+        self.commands.try_iter()
+    }
+}
+
+fn connect_midi(
+    name: &str,
+    spawn_rate_cc: u8,
+    send: mpsc::Sender<Command>,
+) -> Result<midir::MidiInputConnection<()>, Box<dyn Error>> {
+    let input = midir::MidiInput::new("moonstaff")?;
+    let port = input
+        .ports()
+        .into_iter()
+        .find(|port| input.port_name(port).is_ok_and(|port| port.contains(name)))
+        .ok_or(format!("No MIDI input named {name}"))?;
+    println!("Listening for MIDI on {}", input.port_name(&port)?);
+    let connection = input.connect(
+        &port,
+        "moonstaff-control",
+        move |_time, message, _| {
+            // status byte with the channel masked out, then the data bytes
+            let command = match *message {
+                [status, _note, velocity] if status & 0xf0 == 0x90 && velocity > 0 => {
+                    let count = MAX_BURST * velocity as usize / 127;
+                    Command::Burst(count.max(1))
+                }
+                [status, controller, value]
+                    if status & 0xf0 == 0xb0 && controller == spawn_rate_cc =>
+                {
+                    Command::SpawnRate(value as f64 / 127.)
+                }
+                _ => return,
+            };
+            send.send(command).ok();
+        },
+        (),
+    )?;
+    Ok(connection)
+}
+
+/// Read a command from an OSC message, ignoring anything else including bundles.
+fn parse_osc(packet: &[u8]) -> Option<Command> {
+    let (address, rest) = osc_string(packet)?;
+    let (type_tags, rest) = osc_string(rest)?;
+    // only the first argument is needed
+    let arg: [u8; 4] = rest.get(..4)?.try_into().ok()?;
+    let value = match type_tags.strip_prefix(',')?.chars().next()? {
+        'f' => f32::from_be_bytes(arg),
+        'i' => i32::from_be_bytes(arg) as f32,
+        _ => return None,
+    };
+    match address {
+        "/moonstaff/burst" => Some(Command::Burst((value.max(0.) as usize).min(MAX_BURST))),
+        "/moonstaff/spawn_rate" => Some(Command::SpawnRate(value.clamp(0., 1.) as f64)),
+        _ => None,
+    }
+}
+
+/// Split a null-terminated string padded to a multiple of four bytes off the start of OSC data.
+fn osc_string(data: &[u8]) -> Option<(&str, &[u8])> {
+    let len = data.iter().position(|&b| b == 0)?;
+    let string = std::str::from_utf8(&data[..len]).ok()?;
+    Some((string, data.get((len / 4 + 1) * 4..)?))
+}
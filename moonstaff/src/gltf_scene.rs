@@ -0,0 +1,108 @@
+//! Spawning the meshes of a glTF scene where they were placed in Blender,
+//! and finding empties used as markers for other things in the picture,
+//! so that re-exporting the scene doesn't require changing any coordinates in code.
+
+use std::path::Path;
+
+use starframe as sf;
+
+/// A mesh spawned from the scene and where it was placed.
+pub struct SpawnedMesh {
+    pub entity: sf::hecs::Entity,
+    pub position: sf::Vec2,
+    pub depth: f32,
+}
+
+/// Spawn every mesh in the default scene of the glTF file at `path`
+/// at its authored position, with the depth coming from the negative z coordinate.
+/// The file must already be loaded with `load_gltf`.
+///
+/// Poses don't have a scale and only rotate in 2D, so only positions are carried over.
+pub fn spawn_meshes(game: &mut sf::Game, path: &str) -> gltf::Result<Vec<SpawnedMesh>> {
+    let doc = gltf::Gltf::open(path)?;
+    // starframe names meshes after the file they came from
+    let prefix = Path::new(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+    let world_positions = world_positions(&doc);
+
+    let mut spawned = Vec::new();
+    for node in doc.nodes() {
+        let (Some(mesh), Some(node_pos)) = (node.mesh(), world_positions[node.index()]) else {
+            continue;
+        };
+        let name = format!("{prefix}.{}", mesh.name().unwrap_or_default());
+        let [x, y, z] = match node.skin() {
+            // starframe applies the joints' transforms on top of the entity's pose,
+            // so animated meshes show up offset by their root joint's position.
+            // placing them at the negative of it cancels that out,
+            // while the depth is that of the layer the joints were placed on
+            Some(skin) => {
+                let root = skin.skeleton().or_else(|| skin.joints().next());
+                let root_pos = root
+                    .and_then(|joint| world_positions[joint.index()])
+                    .unwrap_or_default();
+                [-root_pos[0], -root_pos[1], root_pos[2]]
+            }
+            None => node_pos,
+        };
+        let mesh_id = game
+            .graphics
+            .get_mesh_id(&name)
+            .unwrap_or_else(|| panic!("Mesh {name} wasn't loaded from {path}"));
+        let position = sf::Vec2::new(x, y);
+        let entity = game.world.spawn((
+            sf::Pose::new(position, sf::Angle::default()).with_depth(-z),
+            mesh_id,
+        ));
+        spawned.push(SpawnedMesh {
+            entity,
+            position,
+            depth: -z,
+        });
+    }
+
+    Ok(spawned)
+}
+
+/// Positions of the empties in the default scene whose name starts with `name_prefix`,
+/// e.g. `attractor`, `attractor.001` and so on, with the depth as the z coordinate
+/// the same way meshes are placed.
+pub fn find_empties(path: &str, name_prefix: &str) -> gltf::Result<Vec<sf::Vec3>> {
+    let doc = gltf::Gltf::open(path)?;
+    let world_positions = world_positions(&doc);
+    Ok(doc
+        .nodes()
+        .filter(|node| {
+            node.mesh().is_none()
+                && node.camera().is_none()
+                && node
+                    .name()
+                    .is_some_and(|name| name.starts_with(name_prefix))
+        })
+        .filter_map(|node| world_positions[node.index()])
+        .map(|[x, y, z]| sf::Vec3::new(x, y, -z))
+        .collect())
+}
+
+/// Walk the node hierarchy of the default scene, adding up positions from the root down.
+/// Nodes that aren't part of the scene get no position.
+fn world_positions(doc: &gltf::Gltf) -> Vec<Option<[f32; 3]>> {
+    let mut world_positions: Vec<Option<[f32; 3]>> = vec![None; doc.nodes().len()];
+    let Some(scene) = doc.default_scene().or_else(|| doc.scenes().next()) else {
+        return world_positions;
+    };
+    let mut nodes: Vec<(gltf::Node, [f32; 3])> = scene.nodes().map(|n| (n, [0.; 3])).collect();
+    while let Some((node, parent_pos)) = nodes.pop() {
+        let (offset, _, _) = node.transform().decomposed();
+        let pos = [
+            parent_pos[0] + offset[0],
+            parent_pos[1] + offset[1],
+            parent_pos[2] + offset[2],
+        ];
+        world_positions[node.index()] = Some(pos);
+        nodes.extend(node.children().map(|child| (child, pos)));
+    }
+    world_positions
+}
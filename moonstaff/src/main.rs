@@ -1,5 +1,18 @@
+mod bloom;
+mod config;
+mod constellations;
+mod control;
+mod gltf_scene;
+mod moon_glow;
+mod moon_phase;
+mod noise;
+mod parallax;
 mod particles;
-use particles::Particle;
+mod screenshot;
+mod sound;
+mod svg_paths;
+use config::ConfigFile;
+use particles::{Behavior, Particle, ParticlePool, TARGET_POS};
 
 use rand::Rng;
 use starframe as sf;
@@ -26,50 +39,100 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+// particle settings, tunable while running
+const CONFIG_PATH: &str = "moonstaff/moonstaff.toml";
+
 pub const MOON_POS: sf::Vec3 = sf::Vec3::new(0.2, 0.084, 30.);
 pub const MOON_RADIUS: f32 = 0.28;
+// the moon is painted on the sky, which is this far back
+const MOON_DEPTH: f32 = 50.;
+
+// the staff moon's mesh is a square of this radius
+const STAFF_MOON_SIZE: f32 = 0.02878;
+// number of particles it takes to fill the staff
+const FULL_CHARGE_PARTICLES: usize = 100;
+// when full, the staff flashes and lights up the scene for this many seconds
+// before the fill level resets
+const CHARGE_EVENT_TIME: f32 = 1.5;
+const CHARGE_BURST_PARTICLES: usize = 24;
+const CHARGE_AMBIENT_BOOST: f32 = 0.6;
+// velocity of a thrown particle relative to how far the mouse was dragged
+const THROW_SPEED: f32 = 2.;
+// the spawn rate goes up and down by this factor with the arrow keys,
+// never above the most that MIDI and OSC can set it to
+const SPAWN_RATE_STEP: f64 = 1.5;
+const MAX_SPAWN_PROBABILITY: f64 = 0.5;
+// particles in a burst around the moon from pressing B
+const KEY_BURST_PARTICLES: usize = 30;
+// seconds without any input before particles start spawning along constellations
+const CONSTELLATION_IDLE_TIME: f32 = 20.;
 
 pub struct State {
     camera: sf::Camera,
 
-    particles: Vec<Particle>,
-    particle_material: sf::MaterialId,
+    particles: ParticlePool,
+    config_file: ConfigFile,
     // moon mesh gets modified at runtime
     moon_mesh_id: sf::MeshId,
+    moon_glow: moon_glow::MoonGlow,
+    moon_phase: moon_phase::MoonPhase,
+    parallax: parallax::Parallax,
     particles_completed: usize,
+    // seconds since the staff got fully charged, while the charge event is going on
+    charge_event_time: Option<f32>,
+    sound: Option<sound::Sound>,
+    control: Option<control::Control>,
+    // starts from the config and is changed live with the controls
+    spawn_probability: f64,
+    // the painting stays on screen but nothing moves
+    paused: bool,
+    // particle paths are recorded for saving as SVG while this is set
+    path_recorder: Option<svg_paths::PathRecorder>,
+    // to react only once per key press
+    keys_held: Vec<sf::Key>,
+    // where the mouse was pressed, to throw a particle from when it's released
+    drag_start: Option<sf::Vec2>,
+    constellations: constellations::Constellations,
+    // None if the surface turned out not to support it
+    bloom: Option<bloom::Bloom>,
+    // seconds since anything was pressed,
+    // the parallax keeps track of when the cursor last moved
+    idle_time: f32,
 
     global_time: f32,
 }
 
+impl State {
+    /// How full the staff is, staying full until the charge event is over.
+    fn charge_level(&self) -> f32 {
+        (self.particles_completed as f32 / FULL_CHARGE_PARTICLES as f32).min(1.)
+    }
+
+    /// Spawn `count` particles spread around the edge of the moon.
+    fn moon_ring_burst(&mut self, count: usize) {
+        let mut rng = rand::thread_rng();
+        // wherever the parallax has moved the moon to
+        let moon_shift = self.parallax.shift(MOON_DEPTH);
+        for i in 0..count {
+            let angle = (i as f32 + rng.gen::<f32>()) / count as f32 * std::f32::consts::TAU;
+            let pos = sf::Vec3::new(
+                MOON_POS.x + moon_shift.x + MOON_RADIUS * angle.cos(),
+                MOON_POS.y + moon_shift.y + MOON_RADIUS * angle.sin(),
+                MOON_POS.z,
+            );
+            self.particles.spawn(pos);
+        }
+    }
+}
+
 impl sf::GameState for State {
     fn init(game: &mut sf::Game) -> Self {
         game.graphics
             .load_gltf("moonstaff/moonstaff.glb")
             .expect("Failed to load 3D assets");
 
-        // starframe doesn't support automatically spawning the contents of a gltf file yet,
-        // and I can't be bothered to implement that right now,
-        // so spawn all the stuff manually
-        for (name, pos) in [
-            ("moonstaff.sky", [0., 0., 50.]),
-            ("moonstaff.char", [-0.25419, -0.12275, 0.]),
-            ("moonstaff.cliff", [-0.354709, -0.384041, 0.02]),
-            // TODO: these animated clouds should be placed at (0, 0),
-            // but instead they need to be offset by the negative of the root bone's position
-            // to show up in the right spot.
-            // this is a Starframe bug, figure it out
-            ("moonstaff.clouds_back", [0.6932588, 0.17770857, 30.]),
-            ("moonstaff.clouds_mid", [0.6762213, 0.29390264, 29.9]),
-            ("moonstaff.clouds_front", [-0.65202147, 0.449862, 29.8]),
-            ("moonstaff.staffbg", [0.012096, 0.095921, 0.01]),
-            ("moonstaff.staffmoon", [0.009397, 0.093279, 0.0001]),
-        ] {
-            game.world.spawn((
-                sf::Pose::new(sf::Vec2::new(pos[0], pos[1]), sf::Angle::default())
-                    .with_depth(pos[2]),
-                game.graphics.get_mesh_id(name).unwrap(),
-            ));
-        }
+        let meshes = gltf_scene::spawn_meshes(game, "moonstaff/moonstaff.glb")
+            .expect("Failed to spawn 3D assets");
         let moon_mesh_id = game.graphics.get_mesh_id("moonstaff.staffmoon").unwrap();
 
         // start animations
@@ -84,35 +147,42 @@ impl sf::GameState for State {
             ));
         }
 
-        // particle texture
-
-        const TEX_HEIGHT: u32 = 16;
-        let line_tex_pixels: Vec<u8> = (0..TEX_HEIGHT)
-            .flat_map(|i| {
-                // white streak with a blue glow:
-                // alpha goes from 0 on the sides to 1 in the middle;
-                // channels other than blue do the same,
-                let x = i as f32 / TEX_HEIGHT as f32;
-                let curve = (2. * (x - 0.5)).powi(2);
-                let alpha = ((0.8 - 0.8 * curve) * 255.) as u8;
-                let r = ((1. - 0.5 * curve) * 255.) as u8;
-                let g = ((1. - 0.3 * curve) * 255.) as u8;
-                [r, g, 255, alpha]
+        // particle textures, one for each piece of a trail
+        // to color them in a gradient from head to tail
+
+        let particle_materials = particles::trail_segment_colors()
+            .iter()
+            .map(|color| {
+                line_material(game, |x| {
+                    // streak of the piece's color, bluer on the sides:
+                    // alpha goes from 0 on the sides to 1 in the middle;
+                    // channels other than blue do the same,
+                    let curve = (2. * (x - 0.5)).powi(2);
+                    [
+                        (1. - 0.5 * curve) * color.red,
+                        (1. - 0.3 * curve) * color.green,
+                        color.blue,
+                        (0.8 - 0.8 * curve) * color.alpha,
+                    ]
+                })
             })
             .collect();
-        let line_tex_data = sf::TextureData {
-            label: Some("particle".to_string()),
-            format: sf::wgpu::TextureFormat::Rgba8UnormSrgb,
-            dimensions: (1, TEX_HEIGHT),
-            pixels: &line_tex_pixels,
-        };
-        let particle_material = game.graphics.create_material(
-            sf::MaterialParams {
-                diffuse_tex: Some(line_tex_data),
-                ..Default::default()
-            },
-            None,
-        );
+
+        let mut config_file = ConfigFile::new(CONFIG_PATH);
+        let config = config_file.reload_if_changed().unwrap_or_default();
+        let attractors = gltf_scene::find_empties("moonstaff/moonstaff.glb", "attractor")
+            .expect("Failed to read attractors");
+
+        let mut sound = sound::Sound::new()
+            .map_err(|err| eprintln!("No sound: {err}"))
+            .ok();
+        if let Some(sound) = &mut sound {
+            sound.set_soundtrack(&config.soundtrack);
+        }
+        let control = control::Control::start(&config.control)
+            .map_err(|err| eprintln!("No live control: {err}"))
+            .ok()
+            .flatten();
 
         // camera
 
@@ -122,10 +192,24 @@ impl sf::GameState for State {
 
         Self {
             camera,
-            particles: Vec::new(),
-            particle_material,
+            spawn_probability: config.particles.spawn_probability,
+            particles: ParticlePool::new(particle_materials, config.particles, attractors),
+            config_file,
             moon_mesh_id,
+            moon_glow: moon_glow::MoonGlow::new(game),
+            moon_phase: moon_phase::MoonPhase::new(game),
+            parallax: parallax::Parallax::new(meshes, config.parallax),
             particles_completed: 0,
+            charge_event_time: None,
+            sound,
+            control,
+            paused: false,
+            path_recorder: None,
+            keys_held: Vec::new(),
+            drag_start: None,
+            constellations: constellations::Constellations::default(),
+            bloom: Some(bloom::Bloom::new(sf::graphics::device())),
+            idle_time: 0.,
             global_time: 0.,
         }
     }
@@ -135,75 +219,231 @@ impl sf::GameState for State {
             return None;
         }
 
+        if let Some(config) = self.config_file.reload_if_changed() {
+            // keep the spawn rate from the controls unless it's the one being tuned
+            if config.particles.spawn_probability != self.particles.config().spawn_probability {
+                // already clamped when loaded, but it goes straight into gen_bool
+                self.spawn_probability = config.particles.spawn_probability.clamp(0., 1.);
+            }
+            self.particles.set_config(config.particles);
+            self.parallax.set_config(config.parallax);
+            if let Some(sound) = &mut self.sound {
+                sound.set_soundtrack(&config.soundtrack);
+            }
+        }
+
+        // M mutes, P pauses and C clears away all particles,
+        // the latter two for composing stills of the painting
+        if key_pressed(game, sf::Key::M, &mut self.keys_held) {
+            if let Some(sound) = &self.sound {
+                sound.toggle_mute();
+            }
+        }
+        if key_pressed(game, sf::Key::P, &mut self.keys_held) {
+            self.paused = !self.paused;
+        }
+        if game.input.button(sf::Key::C.into()) {
+            self.particles.clear();
+        }
+        // the soundtrack dips for the charge event's swell,
+        // set before pausing so muting still reaches it
+        if let Some(sound) = &self.sound {
+            let duck = self
+                .charge_event_time
+                .map_or(0., |t| (std::f32::consts::PI * t / CHARGE_EVENT_TIME).sin());
+            sound.duck_soundtrack(duck);
+        }
+        if self.paused {
+            return Some(());
+        }
+
         self.global_time += game.dt_fixed as f32;
 
         let mut rng = rand::thread_rng();
 
-        // spawn particles on mouse click
-        if game.input.button(sf::MouseButton::Left.into()) {
-            let xy = self
-                .camera
-                .point_screen_to_world(game.input.cursor_position());
-            let pos = sf::Vec3::new(xy.x, xy.y, 30.);
-            let particle = Particle::new(pos, self.particle_material);
-            self.particles.push(particle);
-        }
-
-        // also spawn random particles
-        if rng.gen_bool(0.05) {
-            // generate a uniform distribution in a circle;
-            // see https://stackoverflow.com/questions/5837572/generate-a-random-point-within-a-circle-uniformly
-            let radius = MOON_RADIUS * rng.gen::<f32>().sqrt();
-            let angle = rng.gen::<f32>() * std::f32::consts::TAU;
+        let cursor = self
+            .camera
+            .point_screen_to_world(game.input.cursor_position());
+
+        // shift the layers with the cursor, from -1 to 1 across the view
+        let cursor_in_view = sf::Vec2::new(
+            2. * cursor.x / self.camera.view_width,
+            2. * cursor.y / self.camera.view_height,
+        );
+        let dt = game.dt_fixed as f32;
+        self.parallax.update(game, cursor_in_view, dt);
+        self.moon_phase.update(dt, self.parallax.shift(MOON_DEPTH));
+
+        // throw particles by dragging the mouse,
+        // from where it was pressed in the direction it was dragged
+        // the button reads as down for as long as it's held,
+        // so only the first tick of a drag sets where it started
+        if self.drag_start.is_none() && game.input.button(sf::MouseButton::Left.into()) {
+            self.drag_start = Some(cursor);
+        }
+        if game
+            .input
+            .button(sf::ButtonQuery::mouse(sf::MouseButton::Left).released())
+        {
+            if let Some(start) = self.drag_start.take() {
+                let pos = sf::Vec3::new(start.x, start.y, 30.);
+                self.particles.throw(pos, THROW_SPEED * (cursor - start));
+            }
+        }
+
+        // performing the density of particles live,
+        // up and down arrows for the spawn rate and B for a burst around the moon
+        let mut commands: Vec<control::Command> = self
+            .control
+            .iter()
+            .flat_map(|control| control.poll())
+            .collect();
+        if key_pressed(game, sf::Key::Up, &mut self.keys_held) {
+            let rate = (SPAWN_RATE_STEP * self.spawn_probability).max(0.01);
+            commands.push(control::Command::SpawnRate(rate / MAX_SPAWN_PROBABILITY));
+        }
+        if key_pressed(game, sf::Key::Down, &mut self.keys_held) {
+            let rate = self.spawn_probability / SPAWN_RATE_STEP;
+            commands.push(control::Command::SpawnRate(rate / MAX_SPAWN_PROBABILITY));
+        }
+        if key_pressed(game, sf::Key::B, &mut self.keys_held) {
+            commands.push(control::Command::Burst(KEY_BURST_PARTICLES));
+        }
+        // R starts recording the paths particles take and saves them when pressed again
+        if key_pressed(game, sf::Key::R, &mut self.keys_held) {
+            match self.path_recorder.take() {
+                None => {
+                    println!("Recording particle paths");
+                    self.path_recorder = Some(svg_paths::PathRecorder::default());
+                }
+                Some(recorder) => {
+                    match recorder.save(self.camera.view_width, self.camera.view_height) {
+                        Ok(path) => println!("Saved particle paths to {path}"),
+                        Err(err) => eprintln!("Failed to save particle paths: {err}"),
+                    }
+                }
+            }
+        }
+        // V makes every particle behave one way after another, then mixes them again
+        if key_pressed(game, sf::Key::V, &mut self.keys_held) {
+            let next = match self.particles.behavior() {
+                None => Some(Behavior::ALL[0]),
+                Some(current) => Behavior::ALL
+                    .iter()
+                    .skip_while(|&&behavior| behavior != current)
+                    .nth(1)
+                    .copied(),
+            };
+            match next {
+                Some(behavior) => println!("Particle behavior: {behavior:?}"),
+                None => println!("Particle behavior: mixed"),
+            }
+            self.particles.set_behavior(next);
+        }
+        let performing = !commands.is_empty();
+        for command in commands {
+            match command {
+                control::Command::Burst(count) => self.moon_ring_burst(count),
+                control::Command::SpawnRate(rate) => {
+                    self.spawn_probability = MAX_SPAWN_PROBABILITY * rate.clamp(0., 1.);
+                }
+            }
+        }
+
+        // nobody is around if the cursor stays put and nothing is pressed
+        if self.drag_start.is_some() || !self.keys_held.is_empty() || performing {
+            self.idle_time = 0.;
+        } else {
+            self.idle_time += dt;
+        }
+        let idle_time = self.idle_time.min(self.parallax.idle_time());
+
+        // also spawn random particles, fewer when there are already a lot of them,
+        // tracing out constellations around the moon when left alone for a while
+        let spawn_probability = self.spawn_probability * self.particles.detail() as f64;
+        if rng.gen_bool(spawn_probability) {
+            let offset = if idle_time > CONSTELLATION_IDLE_TIME {
+                self.constellations.next_spawn()
+            } else {
+                // generate a uniform distribution in a circle;
+                // see https://stackoverflow.com/questions/5837572/generate-a-random-point-within-a-circle-uniformly
+                let radius = MOON_RADIUS * rng.gen::<f32>().sqrt();
+                let angle = rng.gen::<f32>() * std::f32::consts::TAU;
+                sf::Vec2::new(radius * angle.cos(), radius * angle.sin())
+            };
+            // wherever the parallax has moved the moon to
+            let moon_shift = self.parallax.shift(MOON_DEPTH);
             let pos = sf::Vec3::new(
-                MOON_POS.x + radius * angle.cos(),
-                MOON_POS.y + radius * angle.sin(),
+                MOON_POS.x + moon_shift.x + offset.x,
+                MOON_POS.y + moon_shift.y + offset.y,
                 MOON_POS.z,
             );
-            self.particles
-                .push(Particle::new(pos, self.particle_material));
+            self.particles.spawn(pos);
         }
 
         // simulate particles
 
-        for particle in &mut self.particles {
-            particle.tick(game.dt_fixed as f32);
+        let arrived = self.particles.tick(game.dt_fixed as f32);
+        if let Some(recorder) = &mut self.path_recorder {
+            recorder.record(self.particles.iter());
+        }
+        self.particles_completed += self.particles.remove_completed();
+
+        // charge event: flash and burst when the staff fills up,
+        // then empty it once the light has faded
+        if let Some(time) = &mut self.charge_event_time {
+            *time += game.dt_fixed as f32;
+            if *time >= CHARGE_EVENT_TIME {
+                self.charge_event_time = None;
+                self.particles_completed -= FULL_CHARGE_PARTICLES;
+            }
+        } else if self.particles_completed >= FULL_CHARGE_PARTICLES {
+            self.charge_event_time = Some(0.);
+            self.moon_phase.advance();
+            self.particles.burst(TARGET_POS, CHARGE_BURST_PARTICLES);
+            if let Some(sound) = &self.sound {
+                sound.swell();
+            }
+        }
+
+        // sound
+        if let Some(sound) = &self.sound {
+            // a few chimes at once is plenty even if more particles arrive
+            for _ in 0..arrived.min(3) {
+                sound.chime();
+            }
         }
-        self.particles_completed += Particle::remove_completed(&mut self.particles);
 
         // update staff background
 
-        // the background mesh is a square of this radius
-        const BG_SIZE: f32 = 0.02878;
         let bg_mesh = game.graphics.get_mesh(&self.moon_mesh_id).unwrap();
 
-        const FULL_CHARGE_PARTICLES: usize = 100;
-        let curr_level = (self.particles_completed % FULL_CHARGE_PARTICLES) as f32
-            / FULL_CHARGE_PARTICLES as f32;
+        let curr_level = self.charge_level();
+        self.moon_glow.update(curr_level);
         // the coefficients make it start with a bit of "charge" already in
-        let level_y = -0.5 * BG_SIZE + curr_level * 1.5 * BG_SIZE;
+        let level_y = -0.5 * STAFF_MOON_SIZE + curr_level * 1.5 * STAFF_MOON_SIZE;
         let uv_y = 0.75 * (1. - curr_level);
         // make the top surface wave a little
         // to make it look like a liquid substance being filled in
         let wave_offset = 0.002 * self.global_time.sin();
         bg_mesh.overwrite(&[
             sf::MeshVertex {
-                position: sf::Vec3::new(-BG_SIZE, -BG_SIZE, 0.).into(),
+                position: sf::Vec3::new(-STAFF_MOON_SIZE, -STAFF_MOON_SIZE, 0.).into(),
                 tex_coords: sf::Vec2::new(0., 1.).into(),
                 ..Default::default()
             },
             sf::MeshVertex {
-                position: sf::Vec3::new(BG_SIZE, -BG_SIZE, 0.).into(),
+                position: sf::Vec3::new(STAFF_MOON_SIZE, -STAFF_MOON_SIZE, 0.).into(),
                 tex_coords: sf::Vec2::new(1., 1.).into(),
                 ..Default::default()
             },
             sf::MeshVertex {
-                position: sf::Vec3::new(-BG_SIZE, level_y - wave_offset, 0.).into(),
+                position: sf::Vec3::new(-STAFF_MOON_SIZE, level_y - wave_offset, 0.).into(),
                 tex_coords: sf::Vec2::new(0., uv_y).into(),
                 ..Default::default()
             },
             sf::MeshVertex {
-                position: sf::Vec3::new(BG_SIZE, level_y + wave_offset, 0.).into(),
+                position: sf::Vec3::new(STAFF_MOON_SIZE, level_y + wave_offset, 0.).into(),
                 tex_coords: sf::Vec2::new(1., uv_y).into(),
                 ..Default::default()
             },
@@ -216,25 +456,130 @@ impl sf::GameState for State {
         self.camera.upload();
         // slow down the animation in code here
         // because I can't be bothered to adjust it in blender
-        game.graphics.update_animations(0.5 * dt);
+        if !self.paused {
+            game.graphics.update_animations(0.5 * dt);
+        }
+        // fewer particles if they're slowing things down
+        self.particles.adapt_budget(dt);
+
+        // S saves the frame as it's presented
+        let take_screenshot = key_pressed(game, sf::Key::S, &mut self.keys_held);
 
         let mut frame = game.renderer.begin_frame();
 
-        frame.set_ambient_light([1.; 3]);
-        frame.extend_point_lights(self.particles.iter().map(|p| sf::PointLight {
-            position: p.position,
-            color: p.light_color,
-            // modulate light radius with the same value as line width
-            radius: 3. * Particle::point_to_line_vertex(p.position, 1.).width,
+        // the charge flash starts bright and fades out
+        let flash = self
+            .charge_event_time
+            .map(|t| (1. - t / CHARGE_EVENT_TIME).max(0.).powi(2));
+        let flash_light = flash.map(|flash| sf::PointLight {
+            position: TARGET_POS,
+            color: [2. * flash, 2. * flash, 3. * flash],
+            radius: 0.4 * flash,
             ..Default::default()
-        }));
+        });
+        // the staff moon glows brighter as it fills up
+        let level = self.charge_level();
+        let moon_light = sf::PointLight {
+            position: TARGET_POS,
+            color: [0.25 * level, 0.3 * level, 0.6 * level],
+            radius: 0.05 + 0.1 * level,
+            ..Default::default()
+        };
+
+        frame.set_ambient_light([1. + CHARGE_AMBIENT_BOOST * flash.unwrap_or(0.); 3]);
+        frame.extend_point_lights(
+            self.particles
+                .iter()
+                .map(|p| sf::PointLight {
+                    position: p.position,
+                    color: p.light_color,
+                    // modulate light radius with the same value as line width
+                    radius: 3. * Particle::point_to_line_vertex(p.position, 1.).width,
+                    ..Default::default()
+                })
+                .chain(flash_light)
+                .chain(std::iter::once(moon_light)),
+        );
         frame.draw_meshes(&mut game.graphics, &mut game.world, &self.camera);
 
-        // particle trails
         frame.draw_lines(
             &game.graphics,
             &self.camera,
-            self.particles.iter().map(|p| &p.trail_strip),
+            std::iter::once(self.moon_phase.strip()).chain(self.moon_glow.strip()),
         );
+
+        frame.draw_lines(
+            &game.graphics,
+            &self.camera,
+            self.particles.iter().flat_map(|p| &p.trail_strips),
+        );
+
+        // the bright trails and the staff moon bleed light into the painting around them
+        if let Some(bloom) = &mut self.bloom {
+            let result = bloom.apply(
+                sf::graphics::device(),
+                &mut frame.encoder,
+                &frame.surface_tex.texture,
+            );
+            if let Err(err) = result {
+                eprintln!("No bloom: {err}");
+                self.bloom = None;
+            }
+        }
+
+        let screenshot = take_screenshot
+            .then(|| {
+                screenshot::Screenshot::record(
+                    sf::graphics::device(),
+                    &mut frame.encoder,
+                    &frame.surface_tex.texture,
+                )
+                .map_err(|err| eprintln!("Failed to take screenshot: {err}"))
+                .ok()
+            })
+            .flatten();
+        // presenting the frame submits the copy into the screenshot
+        drop(frame);
+        if let Some(screenshot) = screenshot {
+            match screenshot.save(sf::graphics::device()) {
+                Ok(path) => println!("Saved screenshot to {path}"),
+                Err(err) => eprintln!("Failed to save screenshot: {err}"),
+            }
+        }
     }
 }
+
+/// Whether the key was pressed down since the last call,
+/// with `held` remembering which keys are down so that holding one down counts only once.
+fn key_pressed(game: &sf::Game, key: sf::Key, held: &mut Vec<sf::Key>) -> bool {
+    let down = game.input.button(key.into());
+    let was_held = held.contains(&key);
+    if down && !was_held {
+        held.push(key);
+    } else if !down && was_held {
+        held.retain(|&k| k != key);
+    }
+    down && !was_held
+}
+
+/// Create a material for lines with a texture going across their width,
+/// `color` giving the RGBA color at each point from one side (0) to the other (1).
+fn line_material(game: &mut sf::Game, color: impl Fn(f32) -> [f32; 4]) -> sf::MaterialId {
+    const TEX_HEIGHT: u32 = 16;
+    let pixels: Vec<u8> = (0..TEX_HEIGHT)
+        .flat_map(|i| color(i as f32 / TEX_HEIGHT as f32).map(|c| (c * 255.) as u8))
+        .collect();
+    let tex_data = sf::TextureData {
+        label: Some("particle".to_string()),
+        format: sf::wgpu::TextureFormat::Rgba8UnormSrgb,
+        dimensions: (1, TEX_HEIGHT),
+        pixels: &pixels,
+    };
+    game.graphics.create_material(
+        sf::MaterialParams {
+            diffuse_tex: Some(tex_data),
+            ..Default::default()
+        },
+        None,
+    )
+}
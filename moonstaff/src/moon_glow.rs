@@ -0,0 +1,94 @@
+//! A halo around the staff moon that grows with its charge,
+//! so how full it is can be seen from across the room and not just from the fill line.
+//!
+//! The halo is a ring of line with a soft texture across its width,
+//! in one of several materials of increasing opacity picked by the charge level.
+
+use starframe as sf;
+
+use super::{particles::TARGET_POS, STAFF_MOON_SIZE};
+
+// number of steps the opacity goes up in
+const LEVELS: usize = 8;
+const MAX_ALPHA: f32 = 0.5;
+const RING_POINTS: usize = 64;
+// radius of the middle of the ring and the ring's width
+// relative to the staff moon's size, when empty and when full
+const RADIUS: (f32, f32) = (1.2, 3.);
+const WIDTH: (f32, f32) = (0.8, 3.);
+// just behind the particles so they fly over it
+const DEPTH: f32 = -0.05;
+
+pub struct MoonGlow {
+    // one for each opacity step, only the current one is drawn
+    strips: Vec<sf::LineStrip>,
+    current: Option<usize>,
+    level: f32,
+}
+
+impl MoonGlow {
+    pub fn new(game: &mut sf::Game) -> Self {
+        let placeholder = vec![
+            sf::LineVertex {
+                position: TARGET_POS,
+                width: 0.,
+            };
+            RING_POINTS + 1
+        ];
+        let strips = (1..=LEVELS)
+            .map(|level| {
+                let alpha = MAX_ALPHA * level as f32 / LEVELS as f32;
+                let material = super::line_material(game, |x| {
+                    // brightest in the middle of the ring, fading to both sides
+                    let falloff = (-(4. * (x - 0.5)).powi(2)).exp();
+                    [0.7, 0.8, 1., alpha * falloff]
+                });
+                sf::LineStrip::new(&placeholder, Some(material))
+            })
+            .collect();
+        Self {
+            strips,
+            current: None,
+            level: 0.,
+        }
+    }
+
+    /// Size the halo for a charge level between 0 and 1.
+    pub fn update(&mut self, level: f32) {
+        let level = level.clamp(0., 1.);
+        // the level only changes when particles arrive
+        if level == self.level {
+            return;
+        }
+        self.level = level;
+        self.current = match (level * LEVELS as f32).ceil() as usize {
+            0 => None,
+            step => Some(step.min(LEVELS) - 1),
+        };
+        let Some(current) = self.current else {
+            return;
+        };
+
+        let lerp = |(a, b): (f32, f32)| STAFF_MOON_SIZE * (a + level * (b - a));
+        let (radius, width) = (lerp(RADIUS), lerp(WIDTH));
+        let points: Vec<sf::LineVertex> = (0..=RING_POINTS)
+            .map(|i| {
+                let angle = i as f32 / RING_POINTS as f32 * std::f32::consts::TAU;
+                sf::LineVertex {
+                    position: sf::Vec3::new(
+                        TARGET_POS.x + radius * angle.cos(),
+                        TARGET_POS.y + radius * angle.sin(),
+                        DEPTH,
+                    ),
+                    width,
+                }
+            })
+            .collect();
+        self.strips[current].overwrite(&points);
+    }
+
+    /// The halo to draw, nothing if the staff is empty.
+    pub fn strip(&self) -> Option<&sf::LineStrip> {
+        self.current.map(|idx| &self.strips[idx])
+    }
+}
@@ -0,0 +1,94 @@
+//! The big moon in the sky going through its phases, one step every time the staff is charged,
+//! so the sky slowly changes over a long session.
+//!
+//! The moon is painted on the sky's texture, so the phase is a shadow drawn over it:
+//! a line going down through the moon that's as wide as the dark part at each height,
+//! fading out at the edges to not look cut out.
+
+use starframe as sf;
+
+use super::{MOON_DEPTH, MOON_POS, MOON_RADIUS};
+
+// steps in a full cycle from full moon to new moon and back
+const STEPS: usize = 8;
+// time constant of easing into the next phase, in seconds
+const TRANSITION_TIME: f32 = 1.;
+// heights the shadow's width is computed at
+const ROWS: usize = 48;
+// the night sky's color, not quite opaque so the dark side shows faintly like earthshine
+const SHADOW_COLOR: [f32; 4] = [0.01, 0.015, 0.05, 0.9];
+// just in front of the sky
+const DEPTH: f32 = MOON_DEPTH - 0.1;
+
+pub struct MoonPhase {
+    strip: sf::LineStrip,
+    // angle through the cycle, starting at full moon (pi) and growing forever
+    phase: f32,
+    target: f32,
+}
+
+impl MoonPhase {
+    pub fn new(game: &mut sf::Game) -> Self {
+        let material = super::line_material(game, |x| {
+            // fading in over the outermost tenth on both sides
+            let edge = (10. * x.min(1. - x)).min(1.);
+            let [r, g, b, a] = SHADOW_COLOR;
+            [r, g, b, a * edge * edge * (3. - 2. * edge)]
+        });
+        let placeholder = vec![
+            sf::LineVertex {
+                position: sf::Vec3::new(MOON_POS.x, MOON_POS.y, DEPTH),
+                width: 0.,
+            };
+            ROWS
+        ];
+        Self {
+            strip: sf::LineStrip::new(&placeholder, Some(material)),
+            phase: std::f32::consts::PI,
+            target: std::f32::consts::PI,
+        }
+    }
+
+    /// Move on to the next phase.
+    pub fn advance(&mut self) {
+        self.target += std::f32::consts::TAU / STEPS as f32;
+    }
+
+    /// Ease towards the current phase and place the shadow
+    /// over wherever the parallax has moved the moon to.
+    pub fn update(&mut self, dt: f32, moon_shift: sf::Vec2) {
+        self.phase += (1. - (-dt / TRANSITION_TIME).exp()) * (self.target - self.phase);
+        let cos = self.phase.cos();
+        // waxing with the shadow on the left in the first half of the cycle,
+        // waning with it on the right in the second
+        let waxing = self.phase.rem_euclid(std::f32::consts::TAU) < std::f32::consts::PI;
+
+        let points: Vec<sf::LineVertex> = (0..ROWS)
+            .map(|i| {
+                // from the bottom of the moon to the top
+                let y = MOON_RADIUS * (2. * i as f32 / (ROWS - 1) as f32 - 1.);
+                // half of the moon's width at this height;
+                // the terminator is this half-ellipse squashed by the phase
+                let half_width = (MOON_RADIUS.powi(2) - y * y).max(0.).sqrt();
+                let (left, right) = if waxing {
+                    (-half_width, half_width * cos)
+                } else {
+                    (-half_width * cos, half_width)
+                };
+                sf::LineVertex {
+                    position: sf::Vec3::new(
+                        MOON_POS.x + moon_shift.x + 0.5 * (left + right),
+                        MOON_POS.y + moon_shift.y + y,
+                        DEPTH,
+                    ),
+                    width: (right - left).max(0.),
+                }
+            })
+            .collect();
+        self.strip.overwrite(&points);
+    }
+
+    pub fn strip(&self) -> &sf::LineStrip {
+        &self.strip
+    }
+}
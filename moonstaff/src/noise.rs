@@ -0,0 +1,46 @@
+//! Smooth pseudorandom noise for the CPU side, the same as the shaders' `common/noise.wgsl`
+//! in demodemonini, and a swirling wind made from it.
+
+use starframe as sf;
+
+// cheap hash-based pseudorandom noise between 0 and 1
+fn hash(x: sf::Vec2) -> f32 {
+    let h = (x.x * 12.9898 + x.y * 78.233).sin() * 43_758.547;
+    // the shader version's fract, which is never negative
+    h - h.floor()
+}
+
+/// Smoothly varying noise between 0 and 1 with features about one unit apart,
+/// made by interpolating hash noise between integer points.
+pub fn value_noise(x: sf::Vec2) -> f32 {
+    let cell = sf::Vec2::new(x.x.floor(), x.y.floor());
+    let f = x - cell;
+    let smooth = |f: f32| f * f * (3. - 2. * f);
+    let (sx, sy) = (smooth(f.x), smooth(f.y));
+    let mix = |a: f32, b: f32, t: f32| a + t * (b - a);
+    let bottom = mix(hash(cell), hash(cell + sf::Vec2::new(1., 0.)), sx);
+    let top = mix(
+        hash(cell + sf::Vec2::new(0., 1.)),
+        hash(cell + sf::Vec2::new(1., 1.)),
+        sx,
+    );
+    mix(bottom, top, sy)
+}
+
+/// Curl of value noise drifting over time `t`: a flow that swirls around
+/// without bunching things up or spreading them out,
+/// with swirls about one unit across and a strength of roughly 1.
+pub fn curl(p: sf::Vec2, t: f32) -> sf::Vec2 {
+    const EPS: f32 = 1e-3;
+    // two layers drifting in different directions so the swirls change shape
+    // instead of just sliding along
+    let potential = |p: sf::Vec2| {
+        value_noise(p + sf::Vec2::new(t, 0.4 * t))
+            + 0.5 * value_noise(1.9 * p + sf::Vec2::new(-0.7 * t, 23.1 + 0.3 * t))
+    };
+    let d_dx = (potential(p + sf::Vec2::new(EPS, 0.)) - potential(p - sf::Vec2::new(EPS, 0.)))
+        / (2. * EPS);
+    let d_dy = (potential(p + sf::Vec2::new(0., EPS)) - potential(p - sf::Vec2::new(0., EPS)))
+        / (2. * EPS);
+    sf::Vec2::new(d_dy, -d_dx)
+}
@@ -0,0 +1,88 @@
+//! Shifting the layers of the picture against each other with the cursor
+//! for a bit of depth, drifting slowly by themselves when the cursor is left alone.
+//!
+//! The camera doesn't have perspective, so instead of moving it
+//! every mesh is moved by an amount depending on its depth.
+//! The character's layer stays put and the farther a layer is, the more it moves.
+
+use starframe as sf;
+
+use super::{config::ParallaxConfig, gltf_scene::SpawnedMesh};
+
+// depth where a layer moves half as much as the farthest ones could
+const HALF_DEPTH: f32 = 10.;
+// time constant of following the cursor, in seconds
+const SMOOTHING_TIME: f32 = 0.5;
+// how far the cursor needs to move to stop the idle drift, relative to the view's size
+const CURSOR_MOVE_THRESHOLD: f32 = 1e-3;
+
+pub struct Parallax {
+    layers: Vec<SpawnedMesh>,
+    config: ParallaxConfig,
+    // where the layers are headed, -1 to 1 on both axes like the cursor
+    aim: sf::Vec2,
+    last_cursor: sf::Vec2,
+    idle_time: f32,
+    time: f32,
+}
+
+impl Parallax {
+    pub fn new(layers: Vec<SpawnedMesh>, config: ParallaxConfig) -> Self {
+        Self {
+            layers,
+            config,
+            aim: sf::Vec2::zero(),
+            last_cursor: sf::Vec2::zero(),
+            idle_time: 0.,
+            time: 0.,
+        }
+    }
+
+    pub fn set_config(&mut self, config: ParallaxConfig) {
+        self.config = config;
+    }
+
+    /// How far things at the given depth are moved from where they were placed.
+    pub fn shift(&self, depth: f32) -> sf::Vec2 {
+        let depth = depth.max(0.);
+        -self.config.strength * depth / (depth + HALF_DEPTH) * self.aim
+    }
+
+    /// Seconds since the cursor last moved.
+    pub fn idle_time(&self) -> f32 {
+        self.idle_time
+    }
+
+    /// Follow the cursor, given from -1 to 1 across the view on both axes,
+    /// and move the layers to match.
+    pub fn update(&mut self, game: &mut sf::Game, cursor: sf::Vec2, dt: f32) {
+        self.time += dt;
+        if (cursor - self.last_cursor).mag() > CURSOR_MOVE_THRESHOLD {
+            self.idle_time = 0.;
+        } else {
+            self.idle_time += dt;
+        }
+        self.last_cursor = cursor;
+
+        let target = if self.idle_time > self.config.idle_time {
+            // slow wandering around the middle
+            sf::Vec2::new(
+                0.8 * (0.13 * self.time).sin(),
+                0.5 * (0.07 * self.time + 1.).sin(),
+            )
+        } else {
+            sf::Vec2::new(cursor.x.clamp(-1., 1.), cursor.y.clamp(-1., 1.))
+        };
+        self.aim += (1. - (-dt / SMOOTHING_TIME).exp()) * (target - self.aim);
+
+        for layer in &self.layers {
+            let pose = sf::Pose::new(
+                layer.position + self.shift(layer.depth),
+                sf::Angle::default(),
+            )
+            .with_depth(layer.depth);
+            // the entities never get despawned
+            game.world.insert_one(layer.entity, pose).ok();
+        }
+    }
+}
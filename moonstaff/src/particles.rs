@@ -1,27 +1,165 @@
-use std::collections::VecDeque;
+use std::{collections::VecDeque, ops::Range};
 
+use palette::{LinSrgba, Mix, Srgba};
 use rand::Rng;
 use starframe as sf;
 
+use super::{
+    config::{BehaviorWeights, ParticleConfig},
+    noise,
+};
+
 // particles gravitate towards the staff the character is holding,
-// which is at roughly this position in the world
-const TARGET_POS: sf::Vec3 = sf::Vec3::new(0.012096, 0.095921, -0.1);
-const GRAVITY_STRENGTH: f32 = 10000.;
-const MAX_SPEED: f32 = 10.;
-const ORBIT_DISTANCE: f32 = 0.25;
+// which is at roughly this position in the world,
+// unless the scene has attractors placed in it
+pub const TARGET_POS: sf::Vec3 = sf::Vec3::new(0.012096, 0.095921, -0.1);
 const ORBIT_TIME: f32 = 0.5;
 const ORBIT_PATH_SIZE: f32 = 0.05;
+// sparks flung out in a burst, slowed down by drag until they fade out
+const SPARK_SPEED: Range<f32> = 0.3..0.8;
+const SPARK_DRAG: f32 = 3.;
+const SPARK_LIFETIME: Range<f32> = 0.4..0.9;
+const SPARK_TRAIL_LENGTH: Range<usize> = 8..16;
+// particles circling the moon do so for this many seconds at this many radians per second
+const MOON_ORBIT_TIME: Range<f32> = 1.5..4.;
+const MOON_ORBIT_SPEED: f32 = 1.2;
+// spiralling particles start this far from the center of the spiral
+// and circle it at this many radians per second, closing in as they fall
+const SPIRAL_RADIUS: f32 = 0.08;
+const SPIRAL_SPEED: f32 = 4.;
+// snowflakes fall at most this fast and get blown around this much more by the wind
+const SNOWFALL_SPEED: f32 = 2.5;
+const SNOWFALL_WIND: f32 = 1.5;
+
+// colors along a trail from the bright head through blue to nothing at the tail
+const TRAIL_GRADIENT: [Srgba; 3] = [
+    Srgba::new(1., 1., 1., 1.),
+    Srgba::new(0.45, 0.6, 1., 0.8),
+    Srgba::new(0.2, 0.3, 1., 0.),
+];
+/// Trails are drawn in this many pieces, each in its own color from the gradient.
+pub const TRAIL_SEGMENTS: usize = 6;
+
+// the particle budget shrinks when frames take longer than this (50 fps)
+// and grows back by one particle per frame when they don't
+const TARGET_FRAME_TIME: f32 = 1. / 50.;
+const BUDGET_SHRINK: f32 = 0.98;
+const MIN_BUDGET: usize = 20;
+// fraction of the budget in use where new particles start getting shorter trails,
+// and how short they get when it's full, relative to the configured length
+const DETAIL_START: f32 = 0.5;
+const MIN_DETAIL: f32 = 0.25;
+
+/// The color of each piece of a trail from the head to the tail,
+/// taken from the middle of the piece's part of the gradient.
+pub fn trail_segment_colors() -> [Srgba; TRAIL_SEGMENTS] {
+    std::array::from_fn(|i| {
+        let t = (i as f32 + 0.5) / TRAIL_SEGMENTS as f32 * (TRAIL_GRADIENT.len() - 1) as f32;
+        let idx = (t as usize).min(TRAIL_GRADIENT.len() - 2);
+        // blended in linear color
+        let (a, b): (LinSrgba, LinSrgba) = (
+            TRAIL_GRADIENT[idx].into_linear(),
+            TRAIL_GRADIENT[idx + 1].into_linear(),
+        );
+        Srgba::from_linear(a.mix(b, t - idx as f32))
+    })
+}
+
+/// How a particle makes its way from the moon to the end zone around its attractor.
+/// The curve into the attractor at the end is the same for all of them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Behavior {
+    /// Falling straight in with gravity.
+    Gravity,
+    /// Circling the moon for a while before falling.
+    Orbit,
+    /// Falling in a spiral that narrows on the way down.
+    Spiral,
+    /// Drifting down slowly, swaying in the wind.
+    Snowfall,
+}
+
+impl Behavior {
+    pub const ALL: [Behavior; 4] = [
+        Behavior::Gravity,
+        Behavior::Orbit,
+        Behavior::Spiral,
+        Behavior::Snowfall,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Behavior::Gravity => "gravity",
+            Behavior::Orbit => "orbit",
+            Behavior::Spiral => "spiral",
+            Behavior::Snowfall => "snowfall",
+        }
+    }
+
+    /// Pick a behavior at random, each as likely as its weight says.
+    fn pick(weights: &BehaviorWeights) -> Self {
+        let weighted = [
+            (Behavior::Gravity, weights.gravity),
+            (Behavior::Orbit, weights.orbit),
+            (Behavior::Spiral, weights.spiral),
+            (Behavior::Snowfall, weights.snowfall),
+        ];
+        let total: f32 = weighted.iter().map(|(_, weight)| weight.max(0.)).sum();
+        if total <= 0. {
+            return Behavior::Gravity;
+        }
+        let mut choice = rand::thread_rng().gen_range(0.0..total);
+        for (behavior, weight) in weighted {
+            choice -= weight.max(0.);
+            if choice < 0. {
+                return behavior;
+            }
+        }
+        Behavior::Gravity
+    }
+}
+
+// state of a particle's behavior before it reaches the end zone
+enum Motion {
+    Gravity,
+    // circling the moon's center at the particle's depth
+    Orbit {
+        center: sf::Vec3,
+        radius: f32,
+        angle: f32,
+        angular_speed: f32,
+        time_left: f32,
+    },
+    // gravity moves the spiral's center and the particle circles around it
+    Spiral {
+        center: sf::Vec3,
+        angle: f32,
+        start_dist: f32,
+    },
+    Snowfall,
+}
 
 pub struct Particle {
+    /// Different for every particle spawned, even when reusing a finished one.
+    pub id: u64,
     pub position: sf::Vec3,
     pub velocity: sf::Vec3,
+    /// The attractor this particle ends up in.
+    pub attractor: sf::Vec3,
+    /// Point near the attractor that the particle falls towards.
     pub target: sf::Vec3,
     pub light_color: [f32; 3],
     pub trail_width: f32,
     pub trail_length: usize,
     pub trail_points: VecDeque<sf::LineVertex>,
-    pub trail_strip: sf::LineStrip,
+    /// The trail in pieces from the head to the tail, see [`TRAIL_SEGMENTS`].
+    pub trail_strips: Vec<sf::LineStrip>,
+    pub behavior: Behavior,
+    motion: Motion,
     pub end: Option<EndPath>,
+    /// Seconds left for a spark, which flies in a straight line
+    /// instead of falling into an attractor.
+    pub spark_time: Option<f32>,
 }
 
 // when the particles get close enough they change
@@ -34,40 +172,131 @@ pub struct EndPath {
 }
 
 impl Particle {
-    pub fn new(position: sf::Vec3, material: sf::MaterialId) -> Self {
+    fn new(
+        position: sf::Vec3,
+        attractor: sf::Vec3,
+        materials: &[sf::MaterialId],
+        config: &ParticleConfig,
+        detail: f32,
+    ) -> Self {
+        // trails are allocated at the longest possible length up front
+        // so that reusing a particle never needs to grow them
+        let capacity = config.trail_length.1.max(2);
+        // pieces share their end points so there are no gaps between them
+        let segment_capacity = capacity.div_ceil(TRAIL_SEGMENTS) + 1;
+        let first_point = Self::point_to_line_vertex(position, 0.);
+        let mut particle = Self {
+            id: 0,
+            position,
+            velocity: sf::Vec3::zero(),
+            attractor,
+            target: attractor,
+            light_color: [0.; 3],
+            trail_width: 0.,
+            trail_length: 0,
+            trail_points: VecDeque::with_capacity(capacity),
+            // fill the whole capacity with placeholder points to size the GPU buffer,
+            // we'll overwrite this before we draw
+            trail_strips: materials
+                .iter()
+                .map(|&material| {
+                    sf::LineStrip::new(&vec![first_point; segment_capacity], Some(material))
+                })
+                .collect(),
+            behavior: Behavior::Gravity,
+            motion: Motion::Gravity,
+            end: None,
+            spark_time: None,
+        };
+        particle.reset(position, attractor, config, detail);
+        particle
+    }
+
+    /// Start over as a new particle at the given position,
+    /// keeping the memory allocated for the trail.
+    /// `detail` scales the trail's length down from the configured one.
+    fn reset(
+        &mut self,
+        position: sf::Vec3,
+        attractor: sf::Vec3,
+        config: &ParticleConfig,
+        detail: f32,
+    ) {
         let mut rng = rand::thread_rng();
-        let trail_length = rng.gen_range(80..160);
-        let trail_width = rng.gen_range(0.005..0.015);
+        let (min_length, max_length) = config.trail_length;
+        let length = rng.gen_range(min_length.min(max_length)..=max_length);
+        self.trail_length = ((detail * length as f32) as usize).max(2);
+        let (min_width, max_width) = config.trail_width;
+        self.trail_width = rng.gen_range(min_width.min(max_width)..=max_width);
 
-        let mut trail_positions = VecDeque::with_capacity(trail_length);
-        let first_point = Self::point_to_line_vertex(position, trail_width);
-        trail_positions.push_front(first_point);
+        self.trail_points.clear();
+        self.trail_points
+            .push_front(Self::point_to_line_vertex(position, self.trail_width));
 
         // starting velocity that pushes away from the moon's center for a nice curving effect
         let dist = position - super::MOON_POS;
         let normal_vel = rng.gen_range(0.1..0.3);
-        let velocity = normal_vel * dist + sf::Vec3::new(0., 0., -5.);
+        self.velocity = normal_vel * dist + sf::Vec3::new(0., 0., -5.);
 
-        // offset target from the staff position by a random amount
+        // offset target from the attractor by a random amount
         let to_angle = rng.gen_range(0.0..std::f32::consts::TAU);
         let to_dist = rng.gen_range(0.02..0.1);
         let target_offset = to_dist * sf::Vec3::new(to_angle.cos(), to_angle.sin(), 0.);
-        let target = TARGET_POS + target_offset;
+        self.attractor = attractor;
+        self.target = attractor + target_offset;
 
-        let light_color = [0.4, 0.4, rng.gen_range(0.6..0.8)];
-        Self {
-            position,
-            velocity,
-            target,
-            light_color,
-            trail_width,
-            trail_length,
-            trail_points: trail_positions,
-            // write some placeholder points because lines need to have at least two,
-            // we'll overwrite this before we draw
-            trail_strip: sf::LineStrip::new(&[first_point, first_point], Some(material)),
-            end: None,
-        }
+        self.light_color = [0.4, 0.4, rng.gen_range(0.6..0.8)];
+        self.position = position;
+        self.behavior = Behavior::Gravity;
+        self.motion = Motion::Gravity;
+        self.end = None;
+        self.spark_time = None;
+    }
+
+    /// Start behaving some other way than falling with gravity from where the particle is.
+    fn set_behavior(&mut self, behavior: Behavior) {
+        let mut rng = rand::thread_rng();
+        self.behavior = behavior;
+        self.motion = match behavior {
+            Behavior::Gravity => Motion::Gravity,
+            Behavior::Orbit => {
+                let center = sf::Vec3::new(super::MOON_POS.x, super::MOON_POS.y, self.position.z);
+                let offset = self.position - center;
+                Motion::Orbit {
+                    center,
+                    radius: offset.mag(),
+                    angle: offset.y.atan2(offset.x),
+                    angular_speed: if rng.gen() {
+                        MOON_ORBIT_SPEED
+                    } else {
+                        -MOON_ORBIT_SPEED
+                    },
+                    time_left: rng.gen_range(MOON_ORBIT_TIME),
+                }
+            }
+            Behavior::Spiral => {
+                // the particle starts at the edge of the spiral
+                let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+                let center =
+                    self.position - SPIRAL_RADIUS * sf::Vec3::new(angle.cos(), angle.sin(), 0.);
+                Motion::Spiral {
+                    center,
+                    angle,
+                    start_dist: (center - self.target).mag().max(0.01),
+                }
+            }
+            Behavior::Snowfall => Motion::Snowfall,
+        };
+    }
+
+    /// Turn into a spark flying off with the given velocity.
+    fn make_spark(&mut self, velocity: sf::Vec3) {
+        let mut rng = rand::thread_rng();
+        self.velocity = velocity;
+        self.spark_time = Some(rng.gen_range(SPARK_LIFETIME));
+        // sparks have short trails, never longer than there's room for
+        self.trail_length = rng.gen_range(SPARK_TRAIL_LENGTH).min(self.trail_length);
+        self.light_color = [0.8, 0.8, 1.];
     }
 
     /// Generate a line vertex with the given position
@@ -80,11 +309,30 @@ impl Particle {
         }
     }
 
-    /// Apply gravity, move the particle, update the trail
-    pub fn tick(&mut self, dt: f32) {
-        if let Some(end) = &mut self.end {
+    /// Apply gravity, move the particle, update the trail.
+    ///
+    /// Returns true on the tick the particle arrives at its attractor.
+    pub fn tick(&mut self, dt: f32, time: f32, config: &ParticleConfig) -> bool {
+        let mut arrived = false;
+        if let Some(time_left) = &mut self.spark_time {
+            *time_left -= dt;
+            if *time_left > 0. {
+                self.velocity *= (1. - SPARK_DRAG * dt).max(0.);
+                self.position += dt * self.velocity;
+                if self.trail_points.len() >= self.trail_length {
+                    self.trail_points.pop_back();
+                }
+                self.trail_points
+                    .push_front(Self::point_to_line_vertex(self.position, self.trail_width));
+            } else {
+                // burnt out, let the trail catch up like at the end of a path
+                self.trail_points.pop_back();
+                self.trail_points.pop_back();
+            }
+        } else if let Some(end) = &mut self.end {
             if end.t < 1. {
                 end.t += dt / ORBIT_TIME;
+                arrived = end.t >= 1.;
                 // bezier curve as repeated linear interpolation
                 let lerp = |a: sf::Vec3, b: sf::Vec3| -> sf::Vec3 { a + end.t * (b - a) };
                 self.position = lerp(
@@ -94,7 +342,7 @@ impl Particle {
                     ),
                     lerp(
                         lerp(end.control1, end.control2),
-                        lerp(end.control2, TARGET_POS),
+                        lerp(end.control2, self.attractor),
                     ),
                 );
                 self.trail_points.pop_back();
@@ -108,22 +356,79 @@ impl Particle {
                 self.trail_points.pop_back();
             }
         } else {
-            // apply gravity as per newton's law F = Gm_1m_2/r^2
-            // (disregarding masses and going directly to acceleration;
-            // GRAVITY_CONSTANT = G * m_1)
-            let dist = self.position - self.target;
-            let dist_sq = dist.mag_sq();
-            if dist_sq > ORBIT_DISTANCE.powi(2) {
-                // falling
-                let grav_accel = GRAVITY_STRENGTH / dist_sq;
-                self.velocity -= dt.powi(2) * grav_accel * dist.normalized();
-
-                let speed = self.velocity.mag();
-                if speed > MAX_SPEED {
-                    self.velocity *= MAX_SPEED / speed;
+            // a spiral's center falls instead of the particle itself
+            let falling_point = match self.motion {
+                Motion::Spiral { center, .. } => center,
+                _ => self.position,
+            };
+            let dist = falling_point - self.target;
+            if dist.mag_sq() > config.orbit_distance.powi(2) {
+                let mut orbit_over = false;
+                match &mut self.motion {
+                    Motion::Gravity => {
+                        fall(
+                            &mut self.position,
+                            &mut self.velocity,
+                            self.target,
+                            (dt, time),
+                            config,
+                            false,
+                        );
+                    }
+                    Motion::Snowfall => {
+                        fall(
+                            &mut self.position,
+                            &mut self.velocity,
+                            self.target,
+                            (dt, time),
+                            config,
+                            true,
+                        );
+                    }
+                    Motion::Orbit {
+                        center,
+                        radius,
+                        angle,
+                        angular_speed,
+                        time_left,
+                    } => {
+                        *angle += dt * *angular_speed;
+                        let dir = sf::Vec3::new(angle.cos(), angle.sin(), 0.);
+                        self.position = *center + *radius * dir;
+                        *time_left -= dt;
+                        if *time_left <= 0. {
+                            // fly off along the circle, slower so as not to leave the picture,
+                            // and into the picture
+                            let tangent = sf::Vec3::new(-dir.y, dir.x, 0.);
+                            self.velocity = 0.5 * *angular_speed * *radius * tangent
+                                + sf::Vec3::new(0., 0., -5.);
+                            orbit_over = true;
+                        }
+                    }
+                    Motion::Spiral {
+                        center,
+                        angle,
+                        start_dist,
+                    } => {
+                        fall(
+                            center,
+                            &mut self.velocity,
+                            self.target,
+                            (dt, time),
+                            config,
+                            false,
+                        );
+                        *angle += dt * SPIRAL_SPEED;
+                        let closeness = ((*center - self.target).mag() / *start_dist).min(1.);
+                        self.position = *center
+                            + SPIRAL_RADIUS
+                                * closeness
+                                * sf::Vec3::new(angle.cos(), angle.sin(), 0.);
+                    }
+                }
+                if orbit_over {
+                    self.motion = Motion::Gravity;
                 }
-
-                self.position += dt * self.velocity;
 
                 if self.trail_points.len() >= self.trail_length {
                     self.trail_points.pop_back();
@@ -136,7 +441,7 @@ impl Particle {
                 let control1 = self.position + vel_scaled;
                 // always turn towards the target to avoid loops
                 // (those don't look good with the current line rendering impl)
-                let dir_to_target = TARGET_POS - control1;
+                let dir_to_target = self.attractor - control1;
                 let vel_turned = sf::Vec3::new(-vel_scaled.y, vel_scaled.x, 0.);
                 let c2_offset = if dir_to_target.dot(vel_turned) > 0. {
                     vel_turned
@@ -156,20 +461,273 @@ impl Particle {
         if self.trail_points.len() >= 2 {
             self.update_trail();
         }
+        arrived
     }
 
     /// Push trail vertices to the GPU.
     fn update_trail(&mut self) {
-        let vertices: Vec<sf::LineVertex> = self.trail_points.iter().cloned().collect();
-        self.trail_strip.overwrite(&vertices);
+        let points = self.trail_points.make_contiguous();
+        write_segments(&mut self.trail_strips, points);
+    }
+}
+
+// apply gravity as per newton's law F = Gm_1m_2/r^2
+// (disregarding masses and going directly to acceleration;
+// gravity_strength = G * m_1) and the wind to a falling point,
+// slower and windier for snowfall
+fn fall(
+    position: &mut sf::Vec3,
+    velocity: &mut sf::Vec3,
+    target: sf::Vec3,
+    (dt, time): (f32, f32),
+    config: &ParticleConfig,
+    snowfall: bool,
+) {
+    let dist = *position - target;
+    let grav_accel = config.gravity_strength / dist.mag_sq();
+    *velocity -= dt.powi(2) * grav_accel * dist.normalized();
+
+    // meandering in the wind on the way
+    let wind_strength = if snowfall {
+        SNOWFALL_WIND * config.wind_strength
+    } else {
+        config.wind_strength
+    };
+    let wind_pos = sf::Vec2::new(position.x, position.y) / config.wind_scale.max(0.01);
+    let wind = noise::curl(wind_pos, config.wind_change_rate * time);
+    *velocity += dt * wind_strength * sf::Vec3::new(wind.x, wind.y, 0.);
+
+    let max_speed = if snowfall {
+        config.max_speed.min(SNOWFALL_SPEED)
+    } else {
+        config.max_speed
+    };
+    let speed = velocity.mag();
+    if speed > max_speed {
+        *velocity *= max_speed / speed;
+    }
+
+    *position += dt * *velocity;
+}
+
+/// Split points between line strips, each piece sharing its end points with the next.
+fn write_segments(strips: &mut [sf::LineStrip], points: &[sf::LineVertex]) {
+    let segment_len = points.len().div_ceil(strips.len());
+    for (i, strip) in strips.iter_mut().enumerate() {
+        let start = (i * segment_len).min(points.len() - 1);
+        let end = (start + segment_len + 1).min(points.len());
+        if end - start >= 2 {
+            strip.overwrite(&points[start..end]);
+        } else {
+            // short trails don't reach the last pieces, hide them
+            let hidden = sf::LineVertex {
+                width: 0.,
+                ..points[start]
+            };
+            strip.overwrite(&[hidden, hidden]);
+        }
+    }
+}
+
+/// Particles along with finished ones kept around for reuse,
+/// so that a long run doesn't keep allocating new trails.
+pub struct ParticlePool {
+    live: Vec<Particle>,
+    free: Vec<Particle>,
+    materials: Vec<sf::MaterialId>,
+    config: ParticleConfig,
+    attractors: Vec<sf::Vec3>,
+    // for the wind to change over
+    time: f32,
+    // most particles allowed alive at once, adapted to the frame rate
+    budget: f32,
+    frame_time: f32,
+    // every particle spawned from the moon behaves like this if set,
+    // otherwise behaviors are picked at random
+    behavior: Option<Behavior>,
+    next_id: u64,
+}
+
+impl ParticlePool {
+    /// Create a pool whose particles each fly into one of `attractors`, picked at random.
+    /// With no attractors they all go to the staff.
+    pub fn new(
+        materials: Vec<sf::MaterialId>,
+        config: ParticleConfig,
+        mut attractors: Vec<sf::Vec3>,
+    ) -> Self {
+        if attractors.is_empty() {
+            attractors.push(TARGET_POS);
+        }
+        Self {
+            live: Vec::new(),
+            free: Vec::new(),
+            materials,
+            budget: config.max_particles.max(MIN_BUDGET) as f32,
+            frame_time: 0.,
+            behavior: None,
+            next_id: 0,
+            config,
+            attractors,
+            time: 0.,
+        }
+    }
+
+    pub fn config(&self) -> &ParticleConfig {
+        &self.config
+    }
+
+    /// Change the settings for particles spawned from now on.
+    pub fn set_config(&mut self, config: ParticleConfig) {
+        // finished particles may not have room for longer trails
+        if config.trail_length.1 > self.config.trail_length.1 {
+            self.free.clear();
+        }
+        self.budget = self.budget.min(config.max_particles.max(MIN_BUDGET) as f32);
+        self.config = config;
+        self.make_room(0);
+    }
+
+    /// Adjust the particle budget to how long the last frame took,
+    /// shrinking it while frames are slow and growing it back up to the configured maximum.
+    pub fn adapt_budget(&mut self, frame_time: f32) {
+        // smoothed so a single hitch doesn't cost a lot of particles
+        self.frame_time += 0.1 * (frame_time - self.frame_time);
+        self.budget = if self.frame_time > TARGET_FRAME_TIME {
+            (BUDGET_SHRINK * self.budget).max(MIN_BUDGET as f32)
+        } else {
+            (self.budget + 1.).min(self.config.max_particles.max(MIN_BUDGET) as f32)
+        };
+        self.make_room(0);
+    }
+
+    /// How much detail new particles get, from 1 while there's plenty of room in the budget
+    /// down to a fraction when it's full. Scales trail lengths and the random spawn rate.
+    pub fn detail(&self) -> f32 {
+        let load = self.live.len() as f32 / self.budget;
+        (1. - (load - DETAIL_START) / (1. - DETAIL_START)).clamp(MIN_DETAIL, 1.)
+    }
+
+    pub fn behavior(&self) -> Option<Behavior> {
+        self.behavior
     }
 
-    /// Destroy particles that have reached the staff and had their trails fully consumed.
+    /// Make every particle spawned from now on behave the same way,
+    /// or with None go back to picking at random with the weights in the config.
+    pub fn set_behavior(&mut self, behavior: Option<Behavior>) {
+        self.behavior = behavior;
+    }
+
+    /// Start a particle at the given position, reusing a finished one if there is any.
+    pub fn spawn(&mut self, position: sf::Vec3) {
+        let mut particle = self.take(position);
+        let behavior = self
+            .behavior
+            .unwrap_or_else(|| Behavior::pick(&self.config.behaviors));
+        particle.set_behavior(behavior);
+        self.live.push(particle);
+    }
+
+    /// Start a particle thrown across the picture with the given velocity.
+    /// It still falls into the depth of the picture like any other particle.
+    pub fn throw(&mut self, position: sf::Vec3, velocity: sf::Vec2) {
+        let mut particle = self.take(position);
+        particle.velocity.x = velocity.x;
+        particle.velocity.y = velocity.y;
+        self.live.push(particle);
+    }
+
+    /// Fling `count` sparks out from `center` in every direction.
+    pub fn burst(&mut self, center: sf::Vec3, count: usize) {
+        let mut rng = rand::thread_rng();
+        for i in 0..count {
+            // evenly spread out with a bit of jitter
+            let angle = (i as f32 + rng.gen::<f32>()) / count as f32 * std::f32::consts::TAU;
+            let speed = rng.gen_range(SPARK_SPEED);
+            let mut particle = self.take(center);
+            particle.make_spark(speed * sf::Vec3::new(angle.cos(), angle.sin(), 0.));
+            self.live.push(particle);
+        }
+    }
+
+    fn take(&mut self, position: sf::Vec3) -> Particle {
+        self.make_room(1);
+        let attractor = self.attractors[rand::thread_rng().gen_range(0..self.attractors.len())];
+        let detail = self.detail();
+        let mut particle = match self.free.pop() {
+            Some(mut particle) => {
+                particle.reset(position, attractor, &self.config, detail);
+                particle
+            }
+            None => Particle::new(position, attractor, &self.materials, &self.config, detail),
+        };
+        particle.id = self.next_id;
+        self.next_id += 1;
+        particle
+    }
+
+    /// Drop live particles until `count` more fit in the budget,
+    /// cheapest to lose first: sparks, then falling particles with the shortest trails.
+    /// Ones already on their way into an attractor are kept as long as possible
+    /// because they count towards the charge.
+    fn make_room(&mut self, count: usize) {
+        let budget = self.budget as usize;
+        while self.live.len() + count > budget {
+            let cheapest = (0..self.live.len()).min_by_key(|&idx| {
+                let particle = &self.live[idx];
+                (
+                    particle.spark_time.is_none(),
+                    particle.end.is_some(),
+                    particle.trail_points.len(),
+                )
+            });
+            let Some(idx) = cheapest else {
+                return;
+            };
+            self.free.push(self.live.swap_remove(idx));
+        }
+    }
+
+    /// Set aside every live particle at once.
+    pub fn clear(&mut self) {
+        self.free.append(&mut self.live);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Particle> {
+        self.live.iter()
+    }
+
+    /// Move every particle, returning the number that arrived at their attractor.
+    pub fn tick(&mut self, dt: f32) -> usize {
+        self.time += dt;
+        let mut arrived = 0;
+        for particle in &mut self.live {
+            if particle.tick(dt, self.time, &self.config) {
+                arrived += 1;
+            }
+        }
+        arrived
+    }
+
+    /// Set aside particles that have reached their attractor or burnt out as sparks
+    /// and had their trails fully consumed.
     ///
-    /// Returns the number of particles removed, used for driving the "charging" animation.
-    pub fn remove_completed(particles: &mut Vec<Self>) -> usize {
-        let len_before = particles.len();
-        particles.retain(|p| !p.trail_points.is_empty());
-        len_before - particles.len()
+    /// Returns the number of particles that reached an attractor,
+    /// used for driving the "charging" animation.
+    pub fn remove_completed(&mut self) -> usize {
+        let mut removed = 0;
+        let mut idx = 0;
+        while idx < self.live.len() {
+            if self.live[idx].trail_points.is_empty() {
+                let particle = self.live.swap_remove(idx);
+                if particle.spark_time.is_none() {
+                    removed += 1;
+                }
+                self.free.push(particle);
+            } else {
+                idx += 1;
+            }
+        }
+        removed
     }
 }
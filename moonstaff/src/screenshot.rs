@@ -0,0 +1,110 @@
+//! Saving the presented frame as a png.
+//! The picture builds up from everything that's happened since it started,
+//! so there's no other way to get a still of it in a particular state.
+
+use std::{error::Error, sync::mpsc};
+
+use starframe as sf;
+
+use sf::wgpu;
+
+/// A frame being copied into a buffer on the GPU,
+/// saved to a file once the copy has been submitted.
+pub struct Screenshot {
+    buffer: wgpu::Buffer,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    // rows in the buffer are padded to wgpu's copy alignment
+    padded_bytes_per_row: u32,
+}
+
+impl Screenshot {
+    /// Record a copy of the texture into a new buffer.
+    /// Fails if the texture doesn't have `COPY_SRC` usage.
+    pub fn record(
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+    ) -> Result<Self, Box<dyn Error>> {
+        if !texture.usage().contains(wgpu::TextureUsages::COPY_SRC) {
+            return Err("The surface can't be read back".into());
+        }
+        let size = texture.size();
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = (4 * size.width).div_ceil(align) * align;
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("screenshot"),
+            size: (padded_bytes_per_row * size.height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                depth_or_array_layers: 1,
+                ..size
+            },
+        );
+        Ok(Self {
+            buffer,
+            format: texture.format(),
+            width: size.width,
+            height: size.height,
+            padded_bytes_per_row,
+        })
+    }
+
+    /// Wait for the copy to finish and save it as a png named after the current time,
+    /// returning the file's name.
+    pub fn save(self, device: &wgpu::Device) -> Result<String, Box<dyn Error>> {
+        let slice = self.buffer.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            sender.send(result).ok();
+        });
+        // waiting for the device runs the callback before returning
+        device.poll(wgpu::Maintain::Wait);
+        receiver.try_recv()??;
+
+        let swap_red_blue = match self.format {
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            other => return Err(format!("Can't save screenshots in format {other:?}").into()),
+        };
+        let row_bytes = (4 * self.width) as usize;
+        let mut pixels = Vec::with_capacity(row_bytes * self.height as usize);
+        for row in slice
+            .get_mapped_range()
+            .chunks_exact(self.padded_bytes_per_row as usize)
+        {
+            pixels.extend_from_slice(&row[..row_bytes]);
+        }
+        for pixel in pixels.chunks_exact_mut(4) {
+            if swap_red_blue {
+                pixel.swap(0, 2);
+            }
+            // the picture is opaque, but the surface's alpha may not be
+            pixel[3] = 255;
+        }
+
+        let time = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?;
+        let path = format!("moonstaff-{}.png", time.as_millis());
+        image::save_buffer(
+            &path,
+            &pixels,
+            self.width,
+            self.height,
+            image::ColorType::Rgba8,
+        )?;
+        Ok(path)
+    }
+}
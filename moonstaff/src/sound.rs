@@ -0,0 +1,220 @@
+//! Sounds for particles arriving at the staff, synthesized on the fly
+//! so there are no audio files to go with the picture,
+//! and a soundtrack looped in the background if one is set in the config.
+//! Without an audio device the picture runs silently.
+
+use std::{
+    error::Error,
+    fs::File,
+    io::BufReader,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use rand::Rng;
+
+use super::config::SoundtrackConfig;
+
+const SAMPLE_RATE: u32 = 44100;
+// chimes are picked from a pentatonic scale above this note (A5),
+// given as semitones above it, so that any of them sound good together
+const CHIME_BASE_HZ: f32 = 880.;
+const CHIME_NOTES: [i32; 6] = [0, 2, 4, 7, 9, 12];
+const CHIME_VOLUME: f32 = 0.06;
+// a low chord swelling up and fading out (A2, E3, A3, C#4)
+const SWELL_CHORD_HZ: [f32; 4] = [110., 164.81, 220., 277.18];
+const SWELL_VOLUME: f32 = 0.05;
+
+pub struct Sound {
+    // playback stops when the stream is dropped
+    _stream: rodio::OutputStream,
+    handle: rodio::OutputStreamHandle,
+    // shared with the voices so muting also silences ones already playing
+    muted: Arc<AtomicBool>,
+    soundtrack: Option<Soundtrack>,
+}
+
+struct Soundtrack {
+    config: SoundtrackConfig,
+    sink: rodio::Sink,
+}
+
+impl Sound {
+    /// Open the default audio output.
+    pub fn new() -> Result<Self, rodio::StreamError> {
+        let (stream, handle) = rodio::OutputStream::try_default()?;
+        Ok(Self {
+            _stream: stream,
+            handle,
+            muted: Arc::new(AtomicBool::new(false)),
+            soundtrack: None,
+        })
+    }
+
+    pub fn toggle_mute(&self) {
+        self.muted.fetch_xor(true, Ordering::Relaxed);
+    }
+
+    /// Loop the soundtrack set in the config, starting over if the file has changed.
+    pub fn set_soundtrack(&mut self, config: &SoundtrackConfig) {
+        if let Some(soundtrack) = &mut self.soundtrack {
+            if soundtrack.config.path == config.path {
+                soundtrack.config = config.clone();
+                return;
+            }
+        }
+        // dropping the sink stops the previous soundtrack
+        self.soundtrack = None;
+        let Some(path) = &config.path else {
+            return;
+        };
+        match self.loop_file(path) {
+            Ok(sink) => {
+                self.soundtrack = Some(Soundtrack {
+                    config: config.clone(),
+                    sink,
+                })
+            }
+            Err(err) => eprintln!("Failed to play {}: {err}", path.display()),
+        }
+    }
+
+    /// Turn the soundtrack down by `amount` between 0 for the usual volume
+    /// and 1 for the ducked volume set in the config.
+    pub fn duck_soundtrack(&self, amount: f32) {
+        let Some(soundtrack) = &self.soundtrack else {
+            return;
+        };
+        let volume = if self.muted.load(Ordering::Relaxed) {
+            0.
+        } else {
+            let config = &soundtrack.config;
+            config.volume * (1. - amount * (1. - config.duck_volume))
+        };
+        soundtrack.sink.set_volume(volume);
+    }
+
+    fn loop_file(&self, path: &Path) -> Result<rodio::Sink, Box<dyn Error>> {
+        let decoder = rodio::Decoder::new_looped(BufReader::new(File::open(path)?))?;
+        let sink = rodio::Sink::try_new(&self.handle)?;
+        sink.append(decoder);
+        Ok(sink)
+    }
+
+    /// A short bell-like note at a random pitch.
+    pub fn chime(&self) {
+        let mut rng = rand::thread_rng();
+        let note = CHIME_NOTES[rng.gen_range(0..CHIME_NOTES.len())];
+        let freq = CHIME_BASE_HZ * 2f32.powf(note as f32 / 12.);
+        let volume = CHIME_VOLUME * rng.gen_range(0.6..1.);
+        // the overtones of a bell aren't whole multiples of the fundamental
+        self.play(Voice {
+            partials: vec![
+                (freq, volume),
+                (2.76 * freq, 0.3 * volume),
+                (5.4 * freq, 0.1 * volume),
+            ],
+            attack: 0.005,
+            decay: rng.gen_range(0.25..0.45),
+        });
+    }
+
+    /// A soft chord rising and falling, for when the staff is fully charged.
+    pub fn swell(&self) {
+        self.play(Voice {
+            partials: SWELL_CHORD_HZ
+                .iter()
+                .flat_map(|&freq| [(freq, SWELL_VOLUME), (2. * freq, 0.3 * SWELL_VOLUME)])
+                .collect(),
+            attack: 0.8,
+            decay: 1.2,
+        });
+    }
+
+    fn play(&self, voice: Voice) {
+        if self.muted.load(Ordering::Relaxed) {
+            return;
+        }
+        let source = VoiceSource {
+            voice,
+            sample_idx: 0,
+            muted: self.muted.clone(),
+        };
+        if let Err(err) = self.handle.play_raw(source) {
+            eprintln!("Failed to play sound: {err}");
+        }
+    }
+}
+
+/// A sum of sine waves with a shared envelope.
+struct Voice {
+    /// Frequency in Hz and amplitude of each sine wave.
+    partials: Vec<(f32, f32)>,
+    /// Seconds to rise to full volume.
+    attack: f32,
+    /// Time constant of the exponential fade after the attack, in seconds.
+    decay: f32,
+}
+
+impl Voice {
+    /// Seconds until the fade has gone too quiet to hear.
+    fn length(&self) -> f32 {
+        self.attack + 6. * self.decay
+    }
+}
+
+struct VoiceSource {
+    voice: Voice,
+    sample_idx: u32,
+    muted: Arc<AtomicBool>,
+}
+
+impl Iterator for VoiceSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let t = self.sample_idx as f32 / SAMPLE_RATE as f32;
+        if t >= self.voice.length() {
+            return None;
+        }
+        self.sample_idx += 1;
+        if self.muted.load(Ordering::Relaxed) {
+            return Some(0.);
+        }
+
+        let v = &self.voice;
+        let envelope = if t < v.attack {
+            t / v.attack
+        } else {
+            (-(t - v.attack) / v.decay).exp()
+        };
+        let wave: f32 = v
+            .partials
+            .iter()
+            .map(|&(freq, amp)| amp * (std::f32::consts::TAU * freq * t).sin())
+            .sum();
+        Some(envelope * wave)
+    }
+}
+
+impl rodio::Source for VoiceSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(self.voice.length()))
+    }
+}
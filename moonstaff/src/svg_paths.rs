@@ -0,0 +1,116 @@
+//! Recording the paths particles take over a run and saving them as SVG,
+//! to turn the motion into artwork for a pen plotter or print.
+//!
+//! Each kind of particle goes on its own Inkscape layer
+//! so they can be drawn with different pens.
+
+use std::{collections::HashMap, error::Error, fmt::Write};
+
+use starframe as sf;
+
+use super::particles::{Behavior, Particle};
+
+// millimeters in the SVG per unit in the world
+const SCALE: f32 = 200.;
+// pen width in millimeters
+const STROKE_WIDTH: f32 = 0.3;
+// points closer than this to the previous one in a path are skipped, in world units
+const MIN_SEGMENT: f32 = 0.002;
+// colors for previewing the layers, one for each behavior and one for sparks
+const LAYER_COLORS: [&str; 5] = ["#1f3c88", "#6a4c93", "#1982c4", "#8ac926", "#ff595e"];
+
+struct RecordedPath {
+    layer: &'static str,
+    points: Vec<sf::Vec2>,
+}
+
+#[derive(Default)]
+pub struct PathRecorder {
+    // paths of particles that are still alive, by particle id
+    active: HashMap<u64, RecordedPath>,
+    finished: Vec<RecordedPath>,
+}
+
+impl PathRecorder {
+    /// Add where each particle is now to its path.
+    /// Particles missing since the last call are done and their paths finished.
+    pub fn record<'a>(&mut self, particles: impl Iterator<Item = &'a Particle>) {
+        let mut still_active = HashMap::with_capacity(self.active.len());
+        for particle in particles {
+            let mut path = self
+                .active
+                .remove(&particle.id)
+                .unwrap_or_else(|| RecordedPath {
+                    layer: layer_name(particle),
+                    points: Vec::new(),
+                });
+            let point = sf::Vec2::new(particle.position.x, particle.position.y);
+            if path
+                .points
+                .last()
+                .is_none_or(|&last| (point - last).mag() >= MIN_SEGMENT)
+            {
+                path.points.push(point);
+            }
+            still_active.insert(particle.id, path);
+        }
+        self.finished
+            .extend(self.active.drain().map(|(_, path)| path));
+        self.active = still_active;
+    }
+
+    /// Write everything recorded to an SVG named after the current time,
+    /// covering a view of the given size centered on the origin.
+    /// Returns the file's name.
+    pub fn save(self, view_width: f32, view_height: f32) -> Result<String, Box<dyn Error>> {
+        let (width, height) = (SCALE * view_width, SCALE * view_height);
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape" width="{width}mm" height="{height}mm" viewBox="0 0 {width} {height}">"#
+        )?;
+
+        let paths: Vec<RecordedPath> = self
+            .finished
+            .into_iter()
+            .chain(self.active.into_values())
+            .filter(|path| path.points.len() >= 2)
+            .collect();
+        let layers = Behavior::ALL
+            .iter()
+            .map(|behavior| behavior.name())
+            .chain(["sparks"]);
+        for (layer, color) in layers.zip(LAYER_COLORS) {
+            writeln!(
+                svg,
+                r#"<g id="{layer}" inkscape:label="{layer}" inkscape:groupmode="layer" fill="none" stroke="{color}" stroke-width="{STROKE_WIDTH}" stroke-linecap="round" stroke-linejoin="round">"#
+            )?;
+            for path in paths.iter().filter(|path| path.layer == layer) {
+                svg.push_str(r#"<path d=""#);
+                for (i, point) in path.points.iter().enumerate() {
+                    // y goes down in SVG
+                    let x = 0.5 * width + SCALE * point.x;
+                    let y = 0.5 * height - SCALE * point.y;
+                    let command = if i == 0 { 'M' } else { 'L' };
+                    write!(svg, "{command}{x:.2} {y:.2} ")?;
+                }
+                svg.push_str("\"/>\n");
+            }
+            svg.push_str("</g>\n");
+        }
+        svg.push_str("</svg>\n");
+
+        let time = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?;
+        let path = format!("moonstaff-paths-{}.svg", time.as_millis());
+        std::fs::write(&path, svg)?;
+        Ok(path)
+    }
+}
+
+fn layer_name(particle: &Particle) -> &'static str {
+    if particle.spark_time.is_some() {
+        "sparks"
+    } else {
+        particle.behavior.name()
+    }
+}